@@ -0,0 +1,37 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The bot is a binary crate, so the module is included directly.
+#[allow(dead_code)]
+#[path = "../../src/config.rs"]
+mod config;
+
+use config::{parse_config_value_path, Config};
+
+// Input format: `<path>\n<json object>`
+fuzz_target!(|data: &[u8]| {
+    let Ok(data) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Some((path, value)) = data.split_once('\n') else {
+        return;
+    };
+
+    let Ok(path) = parse_config_value_path(path) else {
+        return;
+    };
+    let Ok(serde_json::Value::Object(object)) = serde_json::from_str(value) else {
+        return;
+    };
+
+    let mut config = Config::from_value("config.json", object).unwrap();
+    let value = config.get(&path).map(|value| value.into_owned());
+
+    _ = config.remove(&path);
+    _ = config.batch_remove(&[path.clone(), path.clone()]);
+    if let Ok(value) = value {
+        _ = config.set(&path, value);
+    }
+    _ = config.get(&path);
+});
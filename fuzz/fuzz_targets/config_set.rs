@@ -0,0 +1,37 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The bot is a binary crate, so the module is included directly.
+#[allow(dead_code)]
+#[path = "../../src/config.rs"]
+mod config;
+
+use config::{parse_config_value_path, Config};
+
+// Input format: `<path>\n<json value>`
+fuzz_target!(|data: &[u8]| {
+    let Ok(data) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (path, value) = data.split_once('\n').unwrap_or((data, "null"));
+
+    let Ok(path) = parse_config_value_path(path) else {
+        return;
+    };
+    let value = serde_json::from_str(value).unwrap_or(serde_json::Value::Null);
+
+    let serde_json::Value::Object(object) = serde_json::json!({
+        "server": { "port": 8080 },
+        "peers": [{ "address": "127.0.0.1" }, "raw"],
+        "empty": [],
+        "null": null,
+    }) else {
+        unreachable!();
+    };
+
+    let mut config = Config::from_value("config.json", object).unwrap();
+    if config.set(&path, value).is_ok() {
+        config.get(&path).unwrap();
+    }
+});
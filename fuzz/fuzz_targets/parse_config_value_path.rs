@@ -0,0 +1,22 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The bot is a binary crate, so the module is included directly.
+#[allow(dead_code)]
+#[path = "../../src/config.rs"]
+mod config;
+
+use config::parse_config_value_path;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(data) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    // Parsed paths must survive a roundtrip through their display form
+    if let Ok(path) = parse_config_value_path(data) {
+        let printed = path.iter().map(ToString::to_string).collect::<String>();
+        assert_eq!(parse_config_value_path(&printed).unwrap(), path);
+    }
+});
@@ -11,32 +11,87 @@ use teloxide::utils::command::BotCommands;
 pub enum Command {
     #[command(description = "display this text")]
     Start,
+    #[command(description = "show extended help for the command, e.g. `/help reset`.")]
+    Help(String),
     #[command(description = "get chat ID.")]
     GetChatId,
+    #[command(description = "show a summary of the bot state.")]
+    Info,
     #[command(description = "get network status.")]
     Status,
+    #[command(description = "show a health report of all networks and the bot.")]
+    HealthReport,
+    #[command(description = "recheck JRPC endpoints of the specified (or all) networks.")]
+    RecheckNetwork(String),
+    #[command(
+        description = "check that ansible can reach hosts of the specified (or current) network."
+    )]
+    CheckNetwork(String),
+    #[command(
+        description = "change the JRPC endpoint of the network: `<network> <url>`.",
+        parse_with = "split"
+    )]
+    SetRpcUrl { network: String, url: String },
+    #[command(description = "pause health alerts for some time or until /resume.")]
+    Pause(String),
+    #[command(description = "resume paused health alerts.")]
+    Resume,
+    #[command(description = "get per-shard synchronization status.")]
+    Shards,
+    #[command(description = "get the last masterchain block seqno.")]
+    GetSeqno,
     #[command(description = "freeze network reset for some time.")]
     Freeze(String),
     #[command(description = "unfreeze network reset.")]
     Unfreeze,
+    #[command(description = "list frozen resets of all networks.")]
+    ListFrozen,
     #[command(description = "reset network with the commit hash or branch name.")]
     Reset(String),
+    #[command(description = "show networks with a reset in progress.")]
+    GetResetStatus,
+    #[command(description = "run a maintenance playbook: `<alias> [extra_vars]`.")]
+    RunPlaybook(String),
+    #[command(description = "list open pull requests to reset the network to.")]
+    ListPrs(String),
     #[command(description = "retrieve the current deployed commit.")]
     GetCommit,
-    #[command(description = "get workspace")]
-    GetWorkspace,
+    #[command(
+        description = "compare two commits: `[base] <head>` (base defaults to the deployed one)."
+    )]
+    CompareCommits(String),
+    #[command(description = "show CI workflow runs of the specified (or deployed) commit.")]
+    CiStatus(String),
+    #[command(description = "fetch the latest commits of branches tracked by workspaces")]
+    RefreshCommits,
+    #[command(description = "get details of the specified (or current) workspace")]
+    GetWorkspace(String),
+    #[command(description = "list workspaces with their networks and config overrides")]
+    ListWorkspaces,
     #[command(description = "set current workspace")]
     SetWorkspace(String),
+    #[command(description = "copy a workspace without switching: `<source> <dest> [--overwrite]`")]
+    CloneWorkspace(String),
+    #[command(description = "create a workspace from a template: `<name>; template=<template>`")]
+    CreateFromTemplate(String),
+    #[command(description = "list available workspace templates")]
+    ListTemplates,
     #[command(description = "remove the specified workspace")]
     DelWorkspace(String),
     #[command(description = "get network inventory")]
     GetNetwork,
+    #[command(description = "list networks with their sync and freeze status")]
+    ListNetworks,
     #[command(description = "set current network inventory")]
     SetNetwork(String),
-    #[command(description = "use one of reset types: `full` or `restart`")]
+    #[command(
+        description = "use one of reset types for the current workspace: `full` or `restart`"
+    )]
     SetResetType(String),
     #[command(description = "get current reset type.")]
     GetResetType,
+    #[command(description = "fill a config value from an env variable: `<path> [env_var]`.")]
+    SetEnvOverride(String),
     #[command(description = "set node config.")]
     SetNodeConfig(String),
     #[command(description = "get node config.")]
@@ -45,6 +100,26 @@ pub enum Command {
     SetLoggerConfig(String),
     #[command(description = "get logger config.")]
     GetLoggerConfig(String),
+    #[command(description = "show pending config changes: `node`, `logger` or `zerostate`.")]
+    Pending(String),
+    #[command(
+        description = "find config paths with the value: `<node|logger|zerostate> <value>`."
+    )]
+    FindConfig(String),
+    #[command(
+        description = "find values with types differing from the config file: `node`, `logger` or `zerostate`."
+    )]
+    CheckConfig(String),
+    #[command(
+        description = "rename config keys in the current workspace using a JSON map of renames."
+    )]
+    MigrateConfig(String),
+    #[command(
+        description = "generate a JSON Schema of the config: `node`, `logger` or `zerostate`."
+    )]
+    GenerateSchema(String),
+    #[command(description = "show config overrides of all workspaces.")]
+    AuditConfig,
     #[command(description = "set zero state.")]
     SetZeroState(String),
     #[command(description = "get zero state.")]
@@ -57,10 +132,76 @@ pub enum Command {
         address: StdAddr,
         amount: DecimalTokens,
     },
+    #[command(description = "check that the giver wallet is funded.")]
+    TestWallet,
     #[command(description = "get an account state of the specified address.")]
     Account { address: StdAddr },
-    #[command(description = "get the blockchain config param.")]
-    GetParam { param: i32 },
+    #[command(description = "get account states of multiple addresses, one per line.")]
+    GetAccounts { addresses: AddressList },
+    #[command(description = "get a Merkle proof of the account state.")]
+    GetProof { address: StdAddr },
+    #[command(description = "verify the Merkle proof of the account state.")]
+    VerifyAccount { address: StdAddr },
+    #[command(description = "get a prometheus metric of the current network nodes.")]
+    NodeMetric(String),
+    #[command(description = "get the validator elections status.")]
+    GetElections,
+    #[command(description = "get the blockchain config params, comma-separated.")]
+    GetParam { params: ParamList },
+    #[command(description = "show blockchain config changes since the last reset.")]
+    ConfigChanges,
+    #[command(description = "get the rate limit status of GitHub tokens.")]
+    GithubTokenStatus,
+    #[command(description = "set the GitHub repository to use: `<org>/<repo>` or `default`.")]
+    SetGithub(String),
+    #[command(description = "repeat a read-only command periodically: `<interval> <command>`.")]
+    Watch(String),
+    #[command(description = "stop watching a command.")]
+    Unwatch,
+    #[command(description = "show aggregate statistics from the reset history.")]
+    ResetStats,
+    #[command(description = "export the bot state as a document.")]
+    ExportState,
+    #[command(description = "import the bot state from the replied document.")]
+    ImportState,
+    #[command(description = "list state backups.")]
+    GetBackups,
+    #[command(description = "trim the reset history stored in the state.")]
+    Compact,
+    #[command(description = "restore state from the specified backup.")]
+    RestoreBackup(String),
+}
+
+impl Command {
+    /// Whether the command queries the JRPC of the current network.
+    pub fn uses_network(&self) -> bool {
+        matches!(
+            self,
+            Self::Status
+                | Self::Shards
+                | Self::GetSeqno
+                | Self::Account { .. }
+                | Self::GetAccounts { .. }
+                | Self::GetProof { .. }
+                | Self::VerifyAccount { .. }
+                | Self::GetParam { .. }
+                | Self::GetElections
+                | Self::TestWallet
+        )
+    }
+
+    /// Whether the command manages the current network, so its alerts
+    /// should go to the same thread.
+    ///
+    /// NOTE: `/reset` can target another network, so it registers
+    /// its thread only after parsing the params.
+    pub fn manages_network(&self) -> bool {
+        self.uses_network()
+            || matches!(
+                self,
+                Self::RunPlaybook(_) | Self::Freeze(_) | Self::Unfreeze | Self::GetCommit
+            )
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -95,6 +236,58 @@ impl FromStr for DecimalTokens {
     }
 }
 
+/// Addresses separated by newlines or commas.
+#[derive(Debug, Default, Clone)]
+pub struct AddressList(pub Vec<StdAddr>);
+
+impl AddressList {
+    const MAX_LEN: usize = 50;
+}
+
+impl FromStr for AddressList {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let addresses = s
+            .split(|c| c == '\n' || c == ',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| StdAddr::from_str(s).map_err(|e| anyhow::anyhow!("invalid address {s}: {e}")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        anyhow::ensure!(!addresses.is_empty(), "no addresses specified");
+        anyhow::ensure!(
+            addresses.len() <= Self::MAX_LEN,
+            "too many addresses (max {})",
+            Self::MAX_LEN
+        );
+        Ok(Self(addresses))
+    }
+}
+
+/// Blockchain config param numbers separated by commas.
+#[derive(Debug, Default, Clone)]
+pub struct ParamList(pub Vec<i32>);
+
+impl FromStr for ParamList {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let params = s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse::<i32>()
+                    .map_err(|e| anyhow::anyhow!("invalid param {s}: {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        anyhow::ensure!(!params.is_empty(), "no params specified");
+        Ok(Self(params))
+    }
+}
+
 pub struct Currency;
 
 impl std::fmt::Display for Currency {
@@ -1,23 +1,62 @@
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use serde::Deserialize;
 use similar::{ChangeTag, TextDiff};
 
+pub type ConfigValidator = Box<dyn Fn(&serde_json::Value) -> Result<()> + Send + Sync>;
+
+/// Config file contents captured by [`Config::snapshot`].
+#[must_use = "a snapshot is useless unless it is restored"]
+pub struct ConfigSnapshot {
+    path: PathBuf,
+    content: Option<Vec<u8>>,
+}
+
+impl ConfigSnapshot {
+    /// Writes back the captured contents, removes the file if it did not exist.
+    pub fn restore(self) -> Result<()> {
+        match self.content {
+            Some(content) => std::fs::write(&self.path, content),
+            None => match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                res => res,
+            },
+        }
+        .context("Failed to restore config file")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMismatch {
+    pub path: String,
+    pub baseline_type: &'static str,
+    pub current_type: &'static str,
+}
+
 pub struct Config {
     path: PathBuf,
     value: serde_json::Value,
     initial_value: String,
+    validators: Vec<(String, Vec<PathSegment>, ConfigValidator)>,
 }
 
 impl Config {
+    /// Loads a JSON config, or a TOML one if the file has the `.toml` extension.
     pub fn from_file(path: &str) -> Result<Self> {
         let config_str = std::fs::read_to_string(path).context("Failed to read config file")?;
+        if is_toml_path(Path::new(path)) {
+            return Self::from_toml_str(path, &config_str);
+        }
+
         let value = serde_json::from_str(&config_str).context("Failed to parse config file")?;
         Ok(Self {
             path: PathBuf::from(path),
             value,
             initial_value: config_str,
+            validators: Vec::new(),
         })
     }
 
@@ -31,87 +70,958 @@ impl Config {
             path: PathBuf::from(path),
             value: serde_json::Value::Object(value),
             initial_value,
+            validators: Vec::new(),
+        })
+    }
+
+    /// Parses a TOML config. Datetimes are converted to strings.
+    pub fn from_toml_str(path: &str, content: &str) -> Result<Self> {
+        let value =
+            toml::from_str::<toml::Value>(content).context("Failed to parse TOML config")?;
+        let value = toml_to_json(value);
+        let initial_value =
+            serde_json::to_string_pretty(&value).context("Failed to serialize config")?;
+        Ok(Self {
+            path: PathBuf::from(path),
+            value,
+            initial_value,
+            validators: Vec::new(),
         })
     }
 
+    /// Serializes the config as TOML. Values without a TOML representation
+    /// (`null`s and integers out of the `i64` range) are written as strings.
+    pub fn to_toml_str(&self) -> Result<String> {
+        let Some(toml::Value::Table(table)) = json_to_toml(&self.value, true) else {
+            anyhow::bail!("TOML config must be an object");
+        };
+        toml::to_string_pretty(&table).context("Failed to serialize TOML config")
+    }
+
     pub fn as_object(&self) -> Result<serde_json::Map<String, serde_json::Value>> {
         self.value.as_object().cloned().context("expected object")
     }
 
+    /// Captures the current file contents to undo a partially written
+    /// [`Config::save`] with [`ConfigSnapshot::restore`].
+    pub fn snapshot(&self) -> Result<ConfigSnapshot> {
+        let content = match std::fs::read(&self.path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).context("Failed to read config file"),
+        };
+        Ok(ConfigSnapshot {
+            path: self.path.clone(),
+            content,
+        })
+    }
+
+    /// Writes the config with sorted keys and returns [`Config::diff`].
+    /// Writes the config in the format of its file. The returned diff is always JSON.
     pub fn save(self) -> Result<ConfigDiff> {
+        let diff = self.diff()?;
+        if is_toml_path(&self.path) {
+            let content = self.to_toml_str()?;
+            std::fs::write(&self.path, content).context("Failed to write config file")?;
+        } else {
+            std::fs::write(&self.path, &diff.new).context("Failed to write config file")?;
+        }
+        Ok(diff)
+    }
+
+    /// Compares the current value with the initial one. Keys are sorted on both
+    /// sides so that reordered keys are not reported as changes.
+    pub fn diff(&self) -> Result<ConfigDiff> {
+        let mut value = self.value.clone();
+        sort_keys_impl(&mut value);
         let new_value =
-            serde_json::to_string_pretty(&self.value).context("Failed to serialize config")?;
-        std::fs::write(&self.path, &new_value).context("Failed to write config file")?;
+            serde_json::to_string_pretty(&value).context("Failed to serialize config")?;
+
+        let old_value = match serde_json::from_str::<serde_json::Value>(&self.initial_value) {
+            Ok(mut value) => {
+                sort_keys_impl(&mut value);
+                serde_json::to_string_pretty(&value).context("Failed to serialize config")?
+            }
+            Err(_) => self.initial_value.clone(),
+        };
 
         Ok(ConfigDiff {
-            old: self.initial_value,
+            old: old_value,
             new: new_value,
         })
     }
 
-    pub fn get(&self, path: &[String]) -> Result<&serde_json::Value> {
+    /// Recursively sorts object keys alphabetically.
+    pub fn sort_keys(&mut self) {
+        sort_keys_impl(&mut self.value);
+    }
+
+    /// Compares the current value with the file at `self.path`.
+    pub fn diff_with_disk(&self) -> Result<ConfigDiff> {
+        let path = self.path.to_str().context("invalid config path")?;
+        let disk_value = Self::from_file(path)?.value;
+
+        Ok(ConfigDiff {
+            old: serde_json::to_string_pretty(&disk_value).context("Failed to serialize config")?,
+            new: serde_json::to_string_pretty(&self.value).context("Failed to serialize config")?,
+        })
+    }
+
+    pub fn get(&self, path: &[PathSegment]) -> Result<Cow<'_, serde_json::Value>> {
+        get_impl(&self.value, path, String::new())
+    }
+
+    /// Returns the value at the specified path deserialized as `T`.
+    #[cfg(test)]
+    pub fn get_typed<T>(&self, path: &[PathSegment]) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let value = self.get(path)?;
+        serde_json::from_value(value.into_owned()).with_context(|| {
+            format!(
+                "value at '{}' is not {}",
+                path.iter().map(ToString::to_string).collect::<String>(),
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Returns the value at the specified path or `default` if any segment
+    /// is not found. Wildcard paths always resolve to `default`, use [`Config::get`]
+    /// to collect them.
+    pub fn get_or_default<'a>(
+        &'a self,
+        path: &[PathSegment],
+        default: &'a serde_json::Value,
+    ) -> &'a serde_json::Value {
         let mut current = &self.value;
-        let mut full_path = String::new();
-        for key in path {
-            let serde_json::Value::Object(object) = current else {
-                return Err(object_expected(&full_path));
+        for segment in path {
+            let next = match (segment, current) {
+                (PathSegment::Key(key), serde_json::Value::Object(object)) => object.get(key),
+                (PathSegment::Index(index), serde_json::Value::Array(array)) => array.get(*index),
+                (PathSegment::NegativeIndex(n), serde_json::Value::Array(array)) => array
+                    .len()
+                    .checked_sub(*n)
+                    .and_then(|index| array.get(index)),
+                _ => None,
             };
-            full_path = format!("{full_path}.{key}");
-
-            match object.get(key) {
+            match next {
                 Some(value) => current = value,
-                None => anyhow::bail!("'{full_path}' not found"),
+                None => return default,
             }
         }
+        current
+    }
 
-        Ok(current)
+    /// Assigns `value` at the specified path.
+    ///
+    /// Missing object keys along the path are created as empty objects.
+    /// An array can only be extended by one element: index `len` appends
+    /// a new element, while any index past it is rejected instead of filling
+    /// the gap with `null`s. Negative indices only address existing elements.
+    /// Wildcards are not supported.
+    pub fn set(&mut self, path: &[PathSegment], value: serde_json::Value) -> Result<()> {
+        // NOTE: Missing keys are created along the way, so the value is
+        // restored if the path turns out to be invalid further on.
+        let original = self.value.clone();
+        let res = set_impl(&mut self.value, path, value);
+        if res.is_err() {
+            self.value = original;
+        }
+        res
     }
 
-    pub fn set(&mut self, path: &[String], value: serde_json::Value) -> Result<()> {
+    /// Moves the value of `old_key` to `new_key` in the object at `parent_path`.
+    ///
+    /// The renamed key is placed at the end of the object. Fails if the old key
+    /// is missing or the new one is already present.
+    pub fn rename_key(
+        &mut self,
+        parent_path: &[PathSegment],
+        old_key: &str,
+        new_key: &str,
+    ) -> Result<()> {
         let mut current = &mut self.value;
         let mut full_path = String::new();
-        for key in path {
-            let serde_json::Value::Object(object) = current else {
-                return Err(object_expected(&full_path));
+        for segment in parent_path {
+            let parent_path = std::mem::take(&mut full_path);
+            full_path = format!("{parent_path}{segment}");
+
+            current = match segment {
+                PathSegment::Key(key) => {
+                    let serde_json::Value::Object(object) = current else {
+                        return Err(object_expected(&parent_path));
+                    };
+                    object
+                        .get_mut(key)
+                        .with_context(|| format!("'{full_path}' not found"))?
+                }
+                PathSegment::Index(index) => {
+                    let serde_json::Value::Array(array) = current else {
+                        return Err(array_expected(&parent_path));
+                    };
+                    array
+                        .get_mut(*index)
+                        .with_context(|| format!("'{full_path}' not found"))?
+                }
+                PathSegment::NegativeIndex(n) => {
+                    let serde_json::Value::Array(array) = current else {
+                        return Err(array_expected(&parent_path));
+                    };
+                    let index = resolve_negative_index(*n, array.len(), &full_path)?;
+                    &mut array[index]
+                }
+                PathSegment::Wildcard => {
+                    anyhow::bail!("wildcards are not supported for '{full_path}' rename")
+                }
             };
-            full_path = format!("{full_path}.{key}");
+        }
+
+        let serde_json::Value::Object(object) = current else {
+            return Err(object_expected(&full_path));
+        };
+        let old_path = format!("{full_path}{}", PathSegment::Key(old_key.to_owned()));
+        let new_path = format!("{full_path}{}", PathSegment::Key(new_key.to_owned()));
+        anyhow::ensure!(!object.contains_key(new_key), "'{new_path}' already exists");
+
+        let value = object
+            .shift_remove(old_key)
+            .with_context(|| format!("'{old_path}' not found"))?;
+        object.insert(new_key.to_owned(), value);
+        Ok(())
+    }
+
+    /// Inserts values from `defaults` for all object keys missing in the config.
+    ///
+    /// Existing values are never overwritten and arrays are not merged.
+    /// Returns the list of added paths.
+    pub fn apply_defaults(&mut self, defaults: &serde_json::Value) -> Vec<String> {
+        let mut added = Vec::new();
+        apply_defaults_impl(&mut self.value, defaults, String::new(), &mut added);
+        added
+    }
+
+    /// Generates a draft-07 JSON Schema with the types of all current values.
+    ///
+    /// Array item schemas are inferred from the first element only.
+    pub fn infer_json_schema(&self) -> serde_json::Value {
+        let mut schema = infer_schema_impl(&self.value);
+        if let serde_json::Value::Object(object) = &mut schema {
+            object.insert(
+                "$schema".to_owned(),
+                serde_json::json!("http://json-schema.org/draft-07/schema#"),
+            );
+        }
+        schema
+    }
+
+    /// Yields every non-container value along with its full path in the notation
+    /// accepted by [`parse_config_value_path`]. Values are visited in document order.
+    pub fn iter_leaves(&self) -> impl Iterator<Item = (String, &serde_json::Value)> {
+        let mut stack = VecDeque::from([(String::new(), &self.value)]);
+        std::iter::from_fn(move || {
+            while let Some((path, value)) = stack.pop_back() {
+                match value {
+                    serde_json::Value::Object(object) => {
+                        for (key, value) in object.iter().rev() {
+                            let segment = PathSegment::Key(key.clone());
+                            stack.push_back((format!("{path}{segment}"), value));
+                        }
+                    }
+                    serde_json::Value::Array(array) => {
+                        for (index, value) in array.iter().enumerate().rev() {
+                            stack.push_back((format!("{path}[{index}]"), value));
+                        }
+                    }
+                    _ => return Some((path, value)),
+                }
+            }
+            None
+        })
+    }
+
+    /// Returns paths where the value type differs from the baseline one.
+    ///
+    /// Paths missing in either config are skipped.
+    pub fn list_type_mismatches(&self, baseline: &Config) -> Vec<TypeMismatch> {
+        fn lookup<'a>(config: &'a Config, path: &str) -> Option<Cow<'a, serde_json::Value>> {
+            let path = parse_config_value_path(path).ok()?;
+            get_impl(&config.value, &path, String::new()).ok()
+        }
+
+        let mut mismatches = Vec::new();
+        for (path, value) in self.iter_leaves() {
+            if let Some(baseline_value) = lookup(baseline, &path) {
+                let baseline_type = json_type_name(&baseline_value);
+                let current_type = json_type_name(value);
+                if baseline_type != current_type {
+                    mismatches.push(TypeMismatch {
+                        path,
+                        baseline_type,
+                        current_type,
+                    });
+                }
+            }
+        }
+
+        // Baseline leaves which became containers are not visited above
+        for (path, baseline_value) in baseline.iter_leaves() {
+            if let Some(value) = lookup(self, &path) {
+                if value.is_object() || value.is_array() {
+                    mismatches.push(TypeMismatch {
+                        path,
+                        baseline_type: json_type_name(baseline_value),
+                        current_type: json_type_name(&value),
+                    });
+                }
+            }
+        }
+
+        mismatches
+    }
+
+    /// Returns all leaf values matching the predicate along with their paths.
+    pub fn find(
+        &self,
+        predicate: impl Fn(&serde_json::Value) -> bool,
+    ) -> Vec<(String, &serde_json::Value)> {
+        self.iter_leaves()
+            .filter(|(_, value)| predicate(value))
+            .collect()
+    }
+
+    pub fn remove(&mut self, path: &[PathSegment]) -> Result<()> {
+        remove_impl(&mut self.value, path, String::new()).map(|_| ())
+    }
+
+    /// Removes all paths in order and returns the number of removed ones.
+    ///
+    /// Nothing is removed if any of the paths fails.
+    pub fn batch_remove(&mut self, paths: &[Vec<PathSegment>]) -> Result<usize> {
+        let original = self.value.clone();
+
+        let mut removed = 0;
+        for path in paths {
+            match remove_impl(&mut self.value, path, String::new()) {
+                Ok(true) => removed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    self.value = original;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Renames keys at the source paths to the new key names, either all or none.
+    ///
+    /// A new name can also be a path in the same object as the source one.
+    /// Missing source paths are skipped. Returns the list of renamed paths.
+    pub fn apply_rename_map(&mut self, renames: &HashMap<String, String>) -> Result<Vec<String>> {
+        let mut renames = renames.iter().collect::<Vec<_>>();
+        renames.sort();
+
+        let original = self.value.clone();
+
+        let mut renamed = Vec::new();
+        for (old, new) in renames {
+            match self.rename_path(old, new) {
+                Ok(true) => renamed.push(old.clone()),
+                Ok(false) => {}
+                Err(e) => {
+                    self.value = original;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(renamed)
+    }
 
-            current = object
-                .entry(key)
-                .or_insert_with(|| serde_json::Value::Object(Default::default()));
+    /// Returns `false` if the source path does not exist.
+    fn rename_path(&mut self, old: &str, new: &str) -> Result<bool> {
+        let mut path = parse_config_value_path(old)?;
+        if self.get(&path).is_err() {
+            return Ok(false);
         }
 
-        *current = value;
+        let Some(PathSegment::Key(old_key)) = path.pop() else {
+            anyhow::bail!("expected '{old}' to end with an object key");
+        };
+        let new_path = parse_config_value_path(new)?;
+        let new_key = match new_path.split_last() {
+            Some((PathSegment::Key(key), parent)) if parent.is_empty() || parent == path => key,
+            _ => anyhow::bail!("expected '{new}' to be a key name or a path next to '{old}'"),
+        };
+
+        self.rename_key(&path, &old_key, new_key)?;
+        Ok(true)
+    }
+
+    /// Applies overrides from `<prefix>_CONFIG_<path>` environment variables.
+    ///
+    /// Path segments in the variable name are separated by `_` and matched
+    /// case-insensitively against existing keys (the longest match wins),
+    /// so `TYCHONET_NODE_CONFIG_SERVER_MAX_CONNECTIONS` resolves to
+    /// `.server.max_connections`. When the current value is an array,
+    /// the next segment must be an index (`PEERS_0_ADDRESS` -> `.peers[0].address`).
+    /// An unmatched remainder is used as a single lowercase key.
+    ///
+    /// Values are parsed as JSON. Returns the list of overridden paths.
+    pub fn apply_env_overrides(&mut self, prefix: &str) -> Result<Vec<String>> {
+        self.apply_env_overrides_with(prefix, std::env::vars())
+    }
+
+    /// Same as [`Config::apply_env_overrides`] but with the provided variables.
+    pub fn apply_env_overrides_with(
+        &mut self,
+        prefix: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Vec<String>> {
+        let vars = collect_env_overrides(prefix, vars);
+
+        let mut overridden = Vec::with_capacity(vars.len());
+        for (var_name, name, value) in vars {
+            let path = resolve_env_path(&self.value, &name)
+                .with_context(|| format!("invalid config path in {var_name}"))?;
+            let value = serde_json::from_str(&value)
+                .with_context(|| format!("failed to parse {var_name} as JSON"))?;
+            self.set(&path, value)
+                .with_context(|| format!("failed to apply {var_name}"))?;
+
+            overridden.push(path.iter().map(ToString::to_string).collect());
+        }
+
+        Ok(overridden)
+    }
+
+    /// Returns paths which [`Config::apply_env_overrides`] would set.
+    pub fn env_override_paths(&self, prefix: &str) -> Result<Vec<Vec<PathSegment>>> {
+        self.env_override_paths_with(prefix, std::env::vars())
+    }
+
+    /// Same as [`Config::env_override_paths`] but with the provided variables.
+    pub fn env_override_paths_with(
+        &self,
+        prefix: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Vec<Vec<PathSegment>>> {
+        collect_env_overrides(prefix, vars)
+            .into_iter()
+            .map(|(var_name, name, _)| {
+                resolve_env_path(&self.value, &name)
+                    .with_context(|| format!("invalid config path in {var_name}"))
+            })
+            .collect()
+    }
+
+    /// Sets the value at `path` from the environment variable `var`
+    /// read with `lookup`.
+    ///
+    /// The variable is used as is when the current value is a string,
+    /// otherwise it is parsed as JSON falling back to a string.
+    /// Returns `false` if the variable is not set.
+    pub fn set_path_from_env(
+        &mut self,
+        path: &[PathSegment],
+        var: &str,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<bool> {
+        let Some(value) = lookup(var) else {
+            return Ok(false);
+        };
+
+        let is_string = matches!(self.get(path).as_deref(), Ok(serde_json::Value::String(_)));
+        let value = if is_string {
+            serde_json::Value::String(value)
+        } else {
+            serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value))
+        };
+
+        self.set(path, value)
+            .with_context(|| format!("failed to apply {var}"))?;
+        Ok(true)
+    }
+
+    pub fn register_validator(&mut self, path: &str, validator: ConfigValidator) -> Result<()> {
+        let segments = parse_config_value_path(path)?;
+        self.validators.push((path.to_owned(), segments, validator));
         Ok(())
     }
 
-    pub fn remove(&mut self, path: &[String]) -> Result<()> {
-        let mut current = &mut self.value;
+    /// Runs all registered validators. Missing and null paths are skipped.
+    pub fn validate_registered(&self) -> Result<()> {
+        for (path, segments, validator) in &self.validators {
+            let Ok(value) = get_impl(&self.value, segments, String::new()) else {
+                continue;
+            };
+            if value.is_null() {
+                continue;
+            }
+            validator(&value).with_context(|| format!("invalid value at '{path}'"))?;
+        }
+        Ok(())
+    }
+}
 
-        let mut full_path = String::new();
-        let mut iter = path.iter().peekable();
+/// Builds a predicate for [`Config::find`].
+///
+/// The query matches values equal to it parsed as JSON, strings equal to it
+/// ignoring case, and numbers or booleans with the same text. A quoted query
+/// (`"8080"`) is treated as a plain string.
+pub fn value_matcher(query: &str) -> impl Fn(&serde_json::Value) -> bool {
+    let query = query.trim();
+    let parsed = serde_json::from_str::<serde_json::Value>(query).ok();
+    let text = match &parsed {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        _ => query.to_owned(),
+    };
+
+    let lowercase = text.to_lowercase();
+
+    move |value| {
+        if parsed.as_ref() == Some(value) {
+            return true;
+        }
+        match value {
+            serde_json::Value::String(s) => s.to_lowercase() == lowercase,
+            serde_json::Value::Number(_) | serde_json::Value::Bool(_) => value.to_string() == text,
+            _ => false,
+        }
+    }
+}
+
+/// Integer range rule for a config field.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IntegerRange {
+    pub path: String,
+    #[serde(default)]
+    pub min: Option<i64>,
+    #[serde(default)]
+    pub max: Option<i64>,
+}
+
+impl IntegerRange {
+    pub fn to_validator(&self) -> ConfigValidator {
+        let min = self.min;
+        let max = self.max;
+        Box::new(move |value| {
+            let value = value.as_i64().context("expected an integer")?;
+            if let Some(min) = min {
+                anyhow::ensure!(value >= min, "expected a value >= {min}, got {value}");
+            }
+            if let Some(max) = max {
+                anyhow::ensure!(value <= max, "expected a value <= {max}, got {value}");
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+    /// Index from the end of the array, `NegativeIndex(1)` is the last element.
+    NegativeIndex(usize),
+    Wildcard,
+}
+
+impl std::fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Key(key) if is_plain_key(key) => write!(f, ".{key}"),
+            Self::Key(key) => {
+                let key = key.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "[\"{key}\"]")
+            }
+            Self::Index(index) => write!(f, "[{index}]"),
+            Self::NegativeIndex(n) => write!(f, "[-{n}]"),
+            Self::Wildcard => write!(f, "[*]"),
+        }
+    }
+}
+
+/// Whether the key can be written without quotes.
+fn is_plain_key(key: &str) -> bool {
+    !key.is_empty() && key.trim() == key && !key.contains(['.', '[', ']', '"', '\''])
+}
 
-        while let Some(key) = iter.next() {
-            let serde_json::Value::Object(object) = current else {
-                return Err(object_expected(&full_path));
+/// Returns sorted `(var_name, path_name, value)` of `<prefix>_CONFIG_*` variables.
+fn collect_env_overrides(
+    prefix: &str,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Vec<(String, String, String)> {
+    let var_prefix = format!("{prefix}_CONFIG_");
+
+    let mut vars = vars
+        .into_iter()
+        .filter_map(|(var_name, value)| {
+            let name = var_name.strip_prefix(&var_prefix)?.to_owned();
+            Some((var_name, name, value))
+        })
+        .collect::<Vec<_>>();
+    vars.sort_unstable();
+    vars
+}
+
+fn resolve_env_path(value: &serde_json::Value, name: &str) -> Result<Vec<PathSegment>> {
+    static NULL: serde_json::Value = serde_json::Value::Null;
+
+    anyhow::ensure!(!name.is_empty(), "empty config path");
+
+    let mut current = value;
+    let mut rest = name;
+    let mut path = Vec::new();
+
+    while !rest.is_empty() {
+        match current {
+            serde_json::Value::Array(array) => {
+                let (index, tail) = rest.split_once('_').unwrap_or((rest, ""));
+                let index = index
+                    .parse::<usize>()
+                    .with_context(|| format!("invalid array index `{index}`"))?;
+
+                current = array.get(index).unwrap_or(&NULL);
+                path.push(PathSegment::Index(index));
+                rest = tail;
+            }
+            serde_json::Value::Object(object) => {
+                let matched = object
+                    .iter()
+                    .filter(|(key, _)| {
+                        !key.is_empty()
+                            && rest
+                                .get(..key.len())
+                                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(key))
+                            && matches!(rest.as_bytes().get(key.len()), None | Some(b'_'))
+                    })
+                    .max_by_key(|(key, _)| key.len());
+
+                match matched {
+                    Some((key, value)) => {
+                        rest = rest[key.len()..].strip_prefix('_').unwrap_or_default();
+                        current = value;
+                        path.push(PathSegment::Key(key.clone()));
+                    }
+                    None => {
+                        path.push(PathSegment::Key(rest.to_ascii_lowercase()));
+                        break;
+                    }
+                }
+            }
+            _ => {
+                path.push(PathSegment::Key(rest.to_ascii_lowercase()));
+                break;
+            }
+        }
+    }
+
+    Ok(path)
+}
+
+fn set_impl(
+    mut current: &mut serde_json::Value,
+    path: &[PathSegment],
+    value: serde_json::Value,
+) -> Result<()> {
+    let mut full_path = String::new();
+    for segment in path {
+        let parent_path = std::mem::take(&mut full_path);
+        full_path = format!("{parent_path}{segment}");
+
+        current = match segment {
+            PathSegment::Key(key) => {
+                let serde_json::Value::Object(object) = current else {
+                    return Err(object_expected(&parent_path));
+                };
+                object
+                    .entry(key)
+                    .or_insert_with(|| serde_json::Value::Object(Default::default()))
+            }
+            PathSegment::Index(index) => {
+                let serde_json::Value::Array(array) = current else {
+                    return Err(array_expected(&parent_path));
+                };
+                let len = array.len();
+                if *index > len {
+                    return Err(out_of_bounds(&full_path, len));
+                } else if *index == len {
+                    array.push(serde_json::Value::Object(Default::default()));
+                }
+                &mut array[*index]
+            }
+            PathSegment::NegativeIndex(n) => {
+                let serde_json::Value::Array(array) = current else {
+                    return Err(array_expected(&parent_path));
+                };
+                let index = resolve_negative_index(*n, array.len(), &full_path)?;
+                &mut array[index]
+            }
+            PathSegment::Wildcard => {
+                anyhow::bail!("wildcards are not supported for '{full_path}' assignment")
+            }
+        };
+    }
+
+    *current = value;
+    Ok(())
+}
+
+fn get_impl<'a>(
+    value: &'a serde_json::Value,
+    path: &[PathSegment],
+    full_path: String,
+) -> Result<Cow<'a, serde_json::Value>> {
+    let Some((segment, rest)) = path.split_first() else {
+        return Ok(Cow::Borrowed(value));
+    };
+    let parent_path = full_path;
+    let full_path = format!("{parent_path}{segment}");
+
+    match segment {
+        PathSegment::Key(key) => {
+            let serde_json::Value::Object(object) = value else {
+                return Err(object_expected(&parent_path));
             };
-            full_path = format!("{full_path}.{key}");
+            match object.get(key) {
+                Some(value) => get_impl(value, rest, full_path),
+                None => anyhow::bail!("'{full_path}' not found"),
+            }
+        }
+        PathSegment::Index(index) => {
+            let serde_json::Value::Array(array) = value else {
+                return Err(array_expected(&parent_path));
+            };
+            match array.get(*index) {
+                Some(value) => get_impl(value, rest, full_path),
+                None => anyhow::bail!("'{full_path}' not found"),
+            }
+        }
+        PathSegment::NegativeIndex(n) => {
+            let serde_json::Value::Array(array) = value else {
+                return Err(array_expected(&parent_path));
+            };
+            let index = resolve_negative_index(*n, array.len(), &full_path)?;
+            get_impl(&array[index], rest, full_path)
+        }
+        PathSegment::Wildcard => {
+            let serde_json::Value::Array(array) = value else {
+                return Err(array_expected(&parent_path));
+            };
+            let items = array
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    get_impl(value, rest, format!("{parent_path}[{i}]")).map(Cow::into_owned)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Cow::Owned(serde_json::Value::Array(items)))
+        }
+    }
+}
+
+fn is_toml_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
 
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::Value::from(i),
+        toml::Value::Float(f) => serde_json::Value::from(f),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+        toml::Value::Array(array) => array.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// Converts the value to TOML. Values without a TOML representation (`null`s
+/// and integers out of the `i64` range) are written as strings when `lossy`,
+/// otherwise `None` is returned.
+fn json_to_toml(value: &serde_json::Value, lossy: bool) -> Option<toml::Value> {
+    Some(match value {
+        serde_json::Value::Null if lossy => toml::Value::String("null".to_owned()),
+        serde_json::Value::Null => return None,
+        serde_json::Value::Bool(b) => toml::Value::Boolean(*b),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => toml::Value::Integer(i),
+            (None, Some(f)) if !n.is_u64() => toml::Value::Float(f),
+            _ if lossy => toml::Value::String(n.to_string()),
+            _ => return None,
+        },
+        serde_json::Value::String(s) => toml::Value::String(s.clone()),
+        serde_json::Value::Array(array) => toml::Value::Array(
+            array
+                .iter()
+                .map(|value| json_to_toml(value, lossy))
+                .collect::<Option<_>>()?,
+        ),
+        serde_json::Value::Object(object) => toml::Value::Table(
+            object
+                .iter()
+                .map(|(key, value)| Some((key.clone(), json_to_toml(value, lossy)?)))
+                .collect::<Option<_>>()?,
+        ),
+    })
+}
+
+/// Formats the value as TOML for display. Returns `None` if the value has
+/// no exact TOML representation (`null`s or integers out of the `i64` range).
+pub fn to_toml_display(value: &serde_json::Value) -> Option<String> {
+    match json_to_toml(value, false)? {
+        toml::Value::Table(table) => toml::to_string_pretty(&table).ok(),
+        value => Some(value.to_string()),
+    }
+}
+
+pub fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+fn infer_schema_impl(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Null => serde_json::json!({ "type": "null" }),
+        serde_json::Value::Bool(_) => serde_json::json!({ "type": "boolean" }),
+        serde_json::Value::Number(_) => serde_json::json!({ "type": "number" }),
+        serde_json::Value::String(_) => serde_json::json!({ "type": "string" }),
+        serde_json::Value::Array(array) => match array.first() {
+            Some(item) => serde_json::json!({ "type": "array", "items": infer_schema_impl(item) }),
+            None => serde_json::json!({ "type": "array" }),
+        },
+        serde_json::Value::Object(object) => {
+            let properties = object
+                .iter()
+                .map(|(key, value)| (key.clone(), infer_schema_impl(value)))
+                .collect::<serde_json::Map<_, _>>();
+            serde_json::json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+fn apply_defaults_impl(
+    value: &mut serde_json::Value,
+    defaults: &serde_json::Value,
+    path: String,
+    added: &mut Vec<String>,
+) {
+    let (serde_json::Value::Object(object), serde_json::Value::Object(defaults)) =
+        (value, defaults)
+    else {
+        return;
+    };
+
+    for (key, default) in defaults {
+        let path = format!("{path}{}", PathSegment::Key(key.clone()));
+        match object.get_mut(key) {
+            Some(value) => apply_defaults_impl(value, default, path, added),
+            None => {
+                object.insert(key.clone(), default.clone());
+                added.push(path);
+            }
+        }
+    }
+}
+
+fn sort_keys_impl(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut entries = std::mem::take(object).into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, mut value) in entries {
+                sort_keys_impl(&mut value);
+                object.insert(key, value);
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(sort_keys_impl),
+        _ => {}
+    }
+}
+
+/// Returns whether anything was removed.
+fn remove_impl(
+    value: &mut serde_json::Value,
+    path: &[PathSegment],
+    full_path: String,
+) -> Result<bool> {
+    let Some((segment, rest)) = path.split_first() else {
+        return Ok(false);
+    };
+    let parent_path = full_path;
+    let full_path = format!("{parent_path}{segment}");
+
+    match segment {
+        PathSegment::Key(key) => {
+            let serde_json::Value::Object(object) = value else {
+                return Err(object_expected(&parent_path));
+            };
             match object.entry(key) {
-                serde_json::map::Entry::Occupied(entry) if iter.peek().is_none() => {
+                serde_json::map::Entry::Occupied(entry) if rest.is_empty() => {
                     entry.remove();
-                    return Ok(());
+                    Ok(true)
                 }
                 serde_json::map::Entry::Occupied(entry) => {
-                    current = entry.into_mut();
-                }
-                serde_json::map::Entry::Vacant(_) => {
-                    return Ok(());
+                    remove_impl(entry.into_mut(), rest, full_path)
                 }
+                serde_json::map::Entry::Vacant(_) => Ok(false),
             }
         }
-
-        Ok(())
+        PathSegment::Index(index) => {
+            let serde_json::Value::Array(array) = value else {
+                return Err(array_expected(&parent_path));
+            };
+            if *index >= array.len() {
+                return Ok(false);
+            }
+            if rest.is_empty() {
+                array.remove(*index);
+                Ok(true)
+            } else {
+                remove_impl(&mut array[*index], rest, full_path)
+            }
+        }
+        PathSegment::NegativeIndex(n) => {
+            let serde_json::Value::Array(array) = value else {
+                return Err(array_expected(&parent_path));
+            };
+            let index = resolve_negative_index(*n, array.len(), &full_path)?;
+            if rest.is_empty() {
+                array.remove(index);
+                Ok(true)
+            } else {
+                remove_impl(&mut array[index], rest, full_path)
+            }
+        }
+        PathSegment::Wildcard => {
+            let serde_json::Value::Array(array) = value else {
+                return Err(array_expected(&parent_path));
+            };
+            if rest.is_empty() {
+                let removed = !array.is_empty();
+                array.clear();
+                return Ok(removed);
+            }
+            let mut removed = false;
+            for (i, value) in array.iter_mut().enumerate() {
+                removed |= remove_impl(value, rest, format!("{parent_path}[{i}]"))?;
+            }
+            Ok(removed)
+        }
     }
 }
 
@@ -120,6 +1030,27 @@ pub struct ConfigDiff {
     new: String,
 }
 
+impl ConfigDiff {
+    pub fn from_values(old: &serde_json::Value, new: &serde_json::Value) -> Result<Self> {
+        Ok(Self {
+            old: serde_json::to_string_pretty(old).context("Failed to serialize config")?,
+            new: serde_json::to_string_pretty(new).context("Failed to serialize config")?,
+        })
+    }
+
+    /// Whether both sides are the same JSON ignoring key order and formatting.
+    pub fn is_empty(&self) -> bool {
+        if self.old == self.new {
+            return true;
+        }
+
+        // NOTE: Object equality does not depend on the key order.
+        let old = serde_json::from_str::<serde_json::Value>(&self.old);
+        let new = serde_json::from_str::<serde_json::Value>(&self.new);
+        matches!((old, new), (Ok(old), Ok(new)) if old == new)
+    }
+}
+
 impl std::fmt::Display for ConfigDiff {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         const LINES_BEFORE: usize = 3;
@@ -173,7 +1104,869 @@ impl std::fmt::Display for ConfigDiff {
     }
 }
 
+/// Parses a path like `.peers[0].address`, `.logger.outputs[*]`
+/// or `.["key.with.dots"].field` (both `"` and `'` quotes are supported).
+pub fn parse_config_value_path(s: &str) -> Result<Vec<PathSegment>> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut rest = s.strip_prefix('.').unwrap_or(s);
+
+    let mut path = Vec::new();
+    loop {
+        let key_end = rest.find(['.', '[']).unwrap_or(rest.len());
+        let (key, mut brackets) = rest.split_at(key_end);
+
+        let mut is_empty = true;
+        let key = key.trim();
+        if !key.is_empty() {
+            path.push(PathSegment::Key(key.to_owned()));
+            is_empty = false;
+        }
+
+        while let Some(inner) = brackets.strip_prefix('[') {
+            let (segment, tail) = parse_bracket_segment(inner)?;
+            path.push(segment);
+            brackets = tail.trim_start();
+            is_empty = false;
+        }
+        anyhow::ensure!(!is_empty, "empty path items are not allowed");
+
+        match brackets.strip_prefix('.') {
+            Some(tail) => rest = tail,
+            None if brackets.is_empty() => break,
+            None => anyhow::bail!("unexpected `{brackets}` in path"),
+        }
+    }
+
+    Ok(path)
+}
+
+/// Parses the segment after `[` up to and including the closing `]`.
+fn parse_bracket_segment(s: &str) -> Result<(PathSegment, &str)> {
+    let s = s.trim_start();
+
+    if let Some(quote) = s.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let mut key = String::new();
+        let mut escaped = false;
+        let mut key_end = None;
+        for (i, c) in s[1..].char_indices() {
+            if escaped {
+                key.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                key_end = Some(1 + i + c.len_utf8());
+                break;
+            } else {
+                key.push(c);
+            }
+        }
+
+        let key_end = key_end.with_context(|| format!("unterminated quoted key in `[{s}`"))?;
+        let rest = s[key_end..]
+            .trim_start()
+            .strip_prefix(']')
+            .with_context(|| format!("expected `]` after the quoted key in `[{s}`"))?;
+        return Ok((PathSegment::Key(key), rest));
+    }
+
+    let (index, rest) = s
+        .split_once(']')
+        .with_context(|| format!("invalid array index in `[{s}`"))?;
+
+    let segment = match index.trim() {
+        "*" => PathSegment::Wildcard,
+        index if index.starts_with('-') => {
+            let n = index[1..]
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .with_context(|| format!("invalid array index `{index}`"))?;
+            PathSegment::NegativeIndex(n)
+        }
+        index => PathSegment::Index(
+            index
+                .parse()
+                .with_context(|| format!("invalid array index `{index}`"))?,
+        ),
+    };
+    Ok((segment, rest))
+}
+
 fn object_expected(path: &str) -> anyhow::Error {
     let path = if path.is_empty() { "." } else { path };
     anyhow::anyhow!("expected '{path}' to be an object")
 }
+
+fn array_expected(path: &str) -> anyhow::Error {
+    let path = if path.is_empty() { "." } else { path };
+    anyhow::anyhow!("expected '{path}' to be an array")
+}
+
+fn out_of_bounds(path: &str, len: usize) -> anyhow::Error {
+    anyhow::anyhow!(
+        "'{path}' is out of bounds: the array has {len} elements, \
+        only index {len} can be used to append a new one"
+    )
+}
+
+/// Converts an index from the end into a regular one.
+fn resolve_negative_index(n: usize, len: usize, path: &str) -> Result<usize> {
+    match len.checked_sub(n) {
+        Some(index) if n > 0 => Ok(index),
+        _ => anyhow::bail!("'{path}' is out of bounds: the array has {len} elements"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::TempFile;
+
+    fn make_config(value: serde_json::Value) -> Config {
+        let serde_json::Value::Object(object) = value else {
+            panic!("object expected");
+        };
+        Config::from_value("config.json", object).unwrap()
+    }
+
+    #[test]
+    fn config_value_path_from_str() {
+        assert!(parse_config_value_path("").unwrap().is_empty());
+        assert_eq!(
+            parse_config_value_path(".logger.outputs[*]").unwrap(),
+            vec![
+                PathSegment::Key("logger".to_owned()),
+                PathSegment::Key("outputs".to_owned()),
+                PathSegment::Wildcard,
+            ]
+        );
+        assert_eq!(
+            parse_config_value_path(".peers[1].address").unwrap(),
+            vec![
+                PathSegment::Key("peers".to_owned()),
+                PathSegment::Index(1),
+                PathSegment::Key("address".to_owned()),
+            ]
+        );
+        assert!(parse_config_value_path(".a..b").is_err());
+        assert!(parse_config_value_path(".a[x]").is_err());
+        assert!(parse_config_value_path(".a[1").is_err());
+        assert!(parse_config_value_path(".a[1]b").is_err());
+    }
+
+    #[test]
+    fn config_value_path_quoted_keys() {
+        assert_eq!(
+            parse_config_value_path(".[\"key.with.dots\"].subfield").unwrap(),
+            vec![
+                PathSegment::Key("key.with.dots".to_owned()),
+                PathSegment::Key("subfield".to_owned()),
+            ]
+        );
+        assert_eq!(
+            parse_config_value_path("a['x.y'][0][\"[*]\"]").unwrap(),
+            vec![
+                PathSegment::Key("a".to_owned()),
+                PathSegment::Key("x.y".to_owned()),
+                PathSegment::Index(0),
+                PathSegment::Key("[*]".to_owned()),
+            ]
+        );
+        assert_eq!(
+            parse_config_value_path(r#".["say \"hi\""]"#).unwrap(),
+            vec![PathSegment::Key("say \"hi\"".to_owned())]
+        );
+        assert!(parse_config_value_path(".[\"unterminated]").is_err());
+        assert!(parse_config_value_path(".['a'x]").is_err());
+
+        // Display output can be parsed back
+        let path = vec![
+            PathSegment::Key("a.b".to_owned()),
+            PathSegment::Key("c\"d".to_owned()),
+            PathSegment::Index(1),
+        ];
+        let printed = path.iter().map(ToString::to_string).collect::<String>();
+        assert_eq!(parse_config_value_path(&printed).unwrap(), path);
+
+        let mut config = make_config(serde_json::json!({
+            "key.with.dots": { "subfield": 1 },
+        }));
+        let path = parse_config_value_path(".[\"key.with.dots\"].subfield").unwrap();
+        assert_eq!(*config.get(&path).unwrap(), serde_json::json!(1));
+        config.set(&path, serde_json::json!(2)).unwrap();
+        assert_eq!(*config.get(&path).unwrap(), serde_json::json!(2));
+        config.remove(&path).unwrap();
+        assert!(config.get(&path).is_err());
+    }
+
+    #[test]
+    fn get_or_default_fallback() {
+        let config = make_config(serde_json::json!({
+            "server": { "port": 8080 },
+            "peers": ["a"],
+        }));
+
+        let default = serde_json::Value::Null;
+        let get = |path: &str| {
+            let path = parse_config_value_path(path).unwrap();
+            config.get_or_default(&path, &default).clone()
+        };
+
+        assert_eq!(get(".server.port"), serde_json::json!(8080));
+        assert_eq!(get(".peers[0]"), serde_json::json!("a"));
+        assert_eq!(get(".server.host"), default);
+        assert_eq!(get(".peers[1]"), default);
+        assert_eq!(get(".server.port.value"), default);
+        assert_eq!(get(".peers[*]"), default);
+    }
+
+    #[test]
+    fn set_creates_missing_nesting() {
+        let mut config = make_config(serde_json::json!({
+            "peers": [{ "address": "a" }],
+        }));
+
+        let set = |config: &mut Config, path: &str, value: serde_json::Value| {
+            config.set(&parse_config_value_path(path).unwrap(), value)
+        };
+
+        set(&mut config, ".server.port", serde_json::json!(1)).unwrap();
+        set(&mut config, ".peers[0].address", serde_json::json!("b")).unwrap();
+        set(&mut config, ".peers[1].address", serde_json::json!("c")).unwrap();
+        assert!(set(&mut config, ".peers[5].address", serde_json::json!("d")).is_err());
+        assert!(set(&mut config, ".server.port.value", serde_json::json!(1)).is_err());
+        assert!(set(&mut config, ".peers[*]", serde_json::json!(1)).is_err());
+
+        let object = config.as_object().unwrap();
+        assert_eq!(object["server"], serde_json::json!({ "port": 1 }));
+        assert_eq!(
+            object["peers"],
+            serde_json::json!([{ "address": "b" }, { "address": "c" }])
+        );
+    }
+
+    #[test]
+    fn failed_set_leaves_config_unchanged() {
+        let value = serde_json::json!({ "peers": [{ "address": "a" }] });
+        let mut config = make_config(value.clone());
+
+        for path in [".server.ports[0]", ".peers[1].tags[2]", ".new.peers[-1]"] {
+            let path = parse_config_value_path(path).unwrap();
+            assert!(config.set(&path, serde_json::json!(1)).is_err());
+            assert_eq!(
+                serde_json::Value::Object(config.as_object().unwrap()),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn negative_array_indices() {
+        let mut config = make_config(serde_json::json!({
+            "peers": [{ "address": "a" }, { "address": "b" }, { "address": "c" }],
+        }));
+        let path = |path: &str| parse_config_value_path(path).unwrap();
+
+        assert_eq!(
+            path(".peers[-1].address"),
+            vec![
+                PathSegment::Key("peers".to_owned()),
+                PathSegment::NegativeIndex(1),
+                PathSegment::Key("address".to_owned()),
+            ]
+        );
+        assert_eq!(PathSegment::NegativeIndex(2).to_string(), "[-2]");
+        assert!(parse_config_value_path(".peers[-0]").is_err());
+        assert!(parse_config_value_path(".peers[-x]").is_err());
+
+        // get
+        assert_eq!(
+            *config.get(&path(".peers[-1].address")).unwrap(),
+            serde_json::json!("c")
+        );
+        assert_eq!(
+            *config.get(&path(".peers[-3].address")).unwrap(),
+            serde_json::json!("a")
+        );
+        let err = config.get(&path(".peers[-4]")).unwrap_err();
+        assert!(err.to_string().contains("out of bounds"), "{err}");
+
+        // set
+        config
+            .set(&path(".peers[-2].address"), serde_json::json!("x"))
+            .unwrap();
+        assert_eq!(
+            *config.get(&path(".peers[1].address")).unwrap(),
+            serde_json::json!("x")
+        );
+        assert!(config
+            .set(&path(".peers[-4]"), serde_json::json!(1))
+            .is_err());
+
+        // remove
+        config.remove(&path(".peers[-1]")).unwrap();
+        assert_eq!(
+            config.as_object().unwrap()["peers"],
+            serde_json::json!([{ "address": "a" }, { "address": "x" }])
+        );
+        assert!(config.remove(&path(".peers[-3]")).is_err());
+    }
+
+    #[test]
+    fn list_type_mismatches_against_baseline() {
+        let baseline = make_config(serde_json::json!({
+            "server": { "maxConnections": 100, "host": "localhost" },
+            "peers": [1, 2],
+            "metrics": "disabled",
+            "removed": true,
+        }));
+        let current = make_config(serde_json::json!({
+            "server": { "maxConnections": "100", "host": "localhost" },
+            "peers": ["1", 2, 3],
+            "metrics": { "enabled": false },
+            "added": 1,
+        }));
+
+        let mismatch = |path: &str, baseline_type, current_type| TypeMismatch {
+            path: path.to_owned(),
+            baseline_type,
+            current_type,
+        };
+        assert_eq!(
+            current.list_type_mismatches(&baseline),
+            [
+                mismatch(".server.maxConnections", "number", "string"),
+                mismatch(".peers[0]", "number", "string"),
+                mismatch(".metrics", "string", "object"),
+            ]
+        );
+        assert!(baseline.list_type_mismatches(&baseline).is_empty());
+    }
+
+    #[test]
+    fn snapshot_restore() {
+        let file = TempFile::new("json");
+        let path = |s: &str| parse_config_value_path(s).unwrap();
+
+        std::fs::write(file.path(), r#"{"a":1}"#).unwrap();
+        let mut config = Config::from_file(file.path()).unwrap();
+        let snapshot = config.snapshot().unwrap();
+        config.set(&path(".a"), serde_json::json!(2)).unwrap();
+        config.save().unwrap();
+        assert_ne!(std::fs::read_to_string(file.path()).unwrap(), r#"{"a":1}"#);
+
+        snapshot.restore().unwrap();
+        assert_eq!(std::fs::read_to_string(file.path()).unwrap(), r#"{"a":1}"#);
+
+        std::fs::remove_file(file.path()).unwrap();
+        let config = Config::from_value(file.path(), Default::default()).unwrap();
+        let snapshot = config.snapshot().unwrap();
+        config.save().unwrap();
+        snapshot.restore().unwrap();
+        assert!(!std::path::Path::new(file.path()).exists());
+    }
+
+    #[test]
+    fn apply_rename_map_skips_missing() {
+        let mut config = make_config(serde_json::json!({
+            "server": { "maxConnections": 10, "port": 8080 },
+            "legacy": 1,
+        }));
+        let renames = |items: &[(&str, &str)]| {
+            items
+                .iter()
+                .map(|(old, new)| (old.to_string(), new.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let renamed = config
+            .apply_rename_map(&renames(&[
+                (".server.maxConnections", "server.max_connections"),
+                (".legacy", "old"),
+                (".missing.key", "key"),
+            ]))
+            .unwrap();
+        assert_eq!(renamed, [".legacy", ".server.maxConnections"]);
+        assert_eq!(
+            config.value,
+            serde_json::json!({
+                "server": { "port": 8080, "max_connections": 10 },
+                "old": 1,
+            })
+        );
+
+        let before = config.value.clone();
+        assert!(config
+            .apply_rename_map(&renames(&[
+                (".old", "legacy"),
+                (".server.port", "max_connections")
+            ]))
+            .is_err());
+        assert_eq!(config.value, before);
+
+        assert!(config
+            .apply_rename_map(&renames(&[(".server.port", "other.port")]))
+            .is_err());
+    }
+
+    #[test]
+    fn sort_keys_recursive() {
+        let mut config = make_config(serde_json::json!({
+            "b": { "z": 1, "a": 2 },
+            "a": [{ "y": 1, "x": 2 }],
+        }));
+        config.sort_keys();
+
+        let text = serde_json::to_string(&config.value).unwrap();
+        assert_eq!(text, r#"{"a":[{"x":2,"y":1}],"b":{"a":2,"z":1}}"#);
+    }
+
+    #[test]
+    fn batch_remove_rolls_back_on_error() {
+        let path = |s: &str| parse_config_value_path(s).unwrap();
+        let mut config = make_config(serde_json::json!({
+            "a": 1,
+            "b": { "c": 2, "d": 3 },
+            "peers": [1, 2],
+        }));
+
+        let removed = config
+            .batch_remove(&[path(".a"), path(".missing"), path(".b.c")])
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(
+            serde_json::Value::Object(config.as_object().unwrap()),
+            serde_json::json!({ "b": { "d": 3 }, "peers": [1, 2] })
+        );
+
+        assert!(config.batch_remove(&[path(".b.d"), path(".b[0]")]).is_err());
+        assert_eq!(
+            serde_json::Value::Object(config.as_object().unwrap()),
+            serde_json::json!({ "b": { "d": 3 }, "peers": [1, 2] })
+        );
+    }
+
+    #[test]
+    fn config_diff_is_empty_normalized() {
+        let diff = |old: &str, new: &str| ConfigDiff {
+            old: old.to_owned(),
+            new: new.to_owned(),
+        };
+
+        assert!(diff("{}", "{}").is_empty());
+        assert!(diff(r#"{"a":1,"b":[1,2]}"#, "{\n  \"b\": [1, 2],\n  \"a\": 1\n}").is_empty());
+        assert!(!diff(r#"{"a":1}"#, r#"{"a":2}"#).is_empty());
+        assert!(!diff(r#"{"a":[1,2]}"#, r#"{"a":[2,1]}"#).is_empty());
+        assert!(!diff("not json", "not  json").is_empty());
+    }
+
+    #[test]
+    fn infer_json_schema_types() {
+        let config = make_config(serde_json::json!({
+            "port": 8080,
+            "enabled": true,
+            "name": "node",
+            "peers": [{ "address": "a" }, 1],
+            "tags": [],
+            "metrics": null,
+        }));
+
+        assert_eq!(
+            config.infer_json_schema(),
+            serde_json::json!({
+                "$schema": "http://json-schema.org/draft-07/schema#",
+                "type": "object",
+                "properties": {
+                    "port": { "type": "number" },
+                    "enabled": { "type": "boolean" },
+                    "name": { "type": "string" },
+                    "peers": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": { "address": { "type": "string" } },
+                        },
+                    },
+                    "tags": { "type": "array" },
+                    "metrics": { "type": "null" },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn rename_key_keeps_value() {
+        let mut config = make_config(serde_json::json!({
+            "server": { "maxConnections": 10, "port": 8080 },
+            "peers": [{ "addr": "a" }],
+        }));
+        let path = |path: &str| parse_config_value_path(path).unwrap();
+
+        config
+            .rename_key(&path(".server"), "maxConnections", "max_connections")
+            .unwrap();
+        config
+            .rename_key(&path(".peers[0]"), "addr", "address")
+            .unwrap();
+        config.rename_key(&[], "peers", "nodes").unwrap();
+
+        let object = config.as_object().unwrap();
+        assert_eq!(
+            object["server"],
+            serde_json::json!({ "port": 8080, "max_connections": 10 })
+        );
+        assert_eq!(object["nodes"], serde_json::json!([{ "address": "a" }]));
+        assert!(!object.contains_key("peers"));
+
+        assert!(config.rename_key(&path(".server"), "missing", "x").is_err());
+        assert!(config
+            .rename_key(&path(".server"), "port", "max_connections")
+            .is_err());
+        assert!(config.rename_key(&path(".server.port"), "a", "b").is_err());
+        assert!(config.rename_key(&path(".missing"), "a", "b").is_err());
+    }
+
+    #[test]
+    fn remove_wildcard_clears_array() {
+        let mut config = make_config(serde_json::json!({
+            "features": ["a", "b", "c"],
+            "other": 1,
+        }));
+
+        config
+            .remove(&[
+                PathSegment::Key("features".to_owned()),
+                PathSegment::Wildcard,
+            ])
+            .unwrap();
+
+        let object = config.as_object().unwrap();
+        assert_eq!(object["features"], serde_json::json!([]));
+        assert_eq!(object["other"], serde_json::json!(1));
+
+        let serialized = serde_json::to_string(&object).unwrap();
+        serde_json::from_str::<serde_json::Value>(&serialized).unwrap();
+    }
+
+    #[test]
+    fn validate_integer_range() {
+        let mut config = make_config(serde_json::json!({
+            "server": { "port": 8080 },
+        }));
+
+        let range = IntegerRange {
+            path: ".server.port".to_owned(),
+            min: Some(1),
+            max: Some(65535),
+        };
+        config
+            .register_validator(&range.path, range.to_validator())
+            .unwrap();
+        config.validate_registered().unwrap();
+
+        let path = parse_config_value_path(".server.port").unwrap();
+        config.set(&path, serde_json::json!(99999)).unwrap();
+        assert!(config.validate_registered().is_err());
+
+        config.set(&path, serde_json::json!("8080")).unwrap();
+        assert!(config.validate_registered().is_err());
+
+        // Nulled env override paths are not validated
+        config.set(&path, serde_json::Value::Null).unwrap();
+        config.validate_registered().unwrap();
+    }
+
+    #[test]
+    fn apply_env_overrides() {
+        const PREFIX: &str = "TYCHONET_TEST_ENV_OVERRIDES";
+
+        let mut config = make_config(serde_json::json!({
+            "server": { "max_connections": 1 },
+            "peers": ["a", "b"],
+        }));
+
+        let vars = [
+            (format!("{PREFIX}_CONFIG_SERVER_MAX_CONNECTIONS"), "10"),
+            (format!("{PREFIX}_CONFIG_PEERS_1"), "\"b2\""),
+            (format!("{PREFIX}_CONFIG_NEW_KEY"), "true"),
+            ("OTHER_CONFIG_PEERS_0".to_owned(), "\"x\""),
+        ]
+        .map(|(name, value)| (name, value.to_owned()));
+
+        let overridden = config.apply_env_overrides_with(PREFIX, vars).unwrap();
+        assert_eq!(
+            overridden,
+            [".new_key", ".peers[1]", ".server.max_connections"]
+        );
+
+        let object = config.as_object().unwrap();
+        assert_eq!(object["server"]["max_connections"], serde_json::json!(10));
+        assert_eq!(object["peers"], serde_json::json!(["a", "b2"]));
+        assert_eq!(object["new_key"], serde_json::json!(true));
+    }
+
+    #[test]
+    fn env_override_paths() {
+        const PREFIX: &str = "TYCHONET_TEST_ENV_OVERRIDE_PATHS";
+
+        let config = make_config(serde_json::json!({
+            "server": { "max_connections": 1 },
+            "peers": ["a", "b"],
+        }));
+
+        let vars = [
+            (format!("{PREFIX}_CONFIG_SERVER_MAX_CONNECTIONS"), "10"),
+            (format!("{PREFIX}_CONFIG_PEERS_1"), "\"b2\""),
+            ("OTHER_CONFIG_PEERS_0".to_owned(), "\"x\""),
+        ]
+        .map(|(name, value)| (name, value.to_owned()));
+
+        let paths = config.env_override_paths_with(PREFIX, vars).unwrap();
+        assert_eq!(
+            paths,
+            [
+                parse_config_value_path(".peers[1]").unwrap(),
+                parse_config_value_path(".server.max_connections").unwrap(),
+            ]
+        );
+        // Nothing is changed
+        assert_eq!(
+            config.as_object().unwrap()["peers"],
+            serde_json::json!(["a", "b"])
+        );
+
+        let vars = [(format!("{PREFIX}_CONFIG_"), "1".to_owned())];
+        assert!(config.env_override_paths_with(PREFIX, vars).is_err());
+    }
+
+    #[test]
+    fn set_path_from_env() {
+        const PREFIX: &str = "TYCHONET_TEST_SET_PATH_FROM_ENV";
+
+        let mut config = make_config(serde_json::json!({
+            "api": { "key": "", "port": 80 },
+        }));
+
+        let vars = HashMap::from([
+            (format!("{PREFIX}_KEY"), "123".to_owned()),
+            (format!("{PREFIX}_PORT"), "8080".to_owned()),
+        ]);
+        let lookup = |var: &str| vars.get(var).cloned();
+
+        let key = parse_config_value_path(".api.key").unwrap();
+        let port = parse_config_value_path(".api.port").unwrap();
+        let missing = parse_config_value_path(".api.missing").unwrap();
+
+        assert!(config
+            .set_path_from_env(&key, &format!("{PREFIX}_KEY"), lookup)
+            .unwrap());
+        assert!(config
+            .set_path_from_env(&port, &format!("{PREFIX}_PORT"), lookup)
+            .unwrap());
+        assert!(!config
+            .set_path_from_env(&missing, &format!("{PREFIX}_MISSING"), lookup)
+            .unwrap());
+
+        let object = config.as_object().unwrap();
+        assert_eq!(object["api"]["key"], serde_json::json!("123"));
+        assert_eq!(object["api"]["port"], serde_json::json!(8080));
+        assert!(object["api"].get("missing").is_none());
+    }
+
+    #[test]
+    fn get_typed() {
+        let config = make_config(serde_json::json!({
+            "server": { "port": 8080, "host": "localhost", "tls": false },
+            "ratio": 0.5,
+            "peers": ["a", "b"],
+            "proxy": null,
+        }));
+        let path = |path: &str| parse_config_value_path(path).unwrap();
+
+        assert_eq!(
+            config.get_typed::<u16>(&path(".server.port")).unwrap(),
+            8080
+        );
+        assert_eq!(
+            config.get_typed::<String>(&path(".server.host")).unwrap(),
+            "localhost"
+        );
+        assert!(!config.get_typed::<bool>(&path(".server.tls")).unwrap());
+        assert_eq!(config.get_typed::<f64>(&path(".ratio")).unwrap(), 0.5);
+        assert_eq!(
+            config.get_typed::<Vec<String>>(&path(".peers")).unwrap(),
+            ["a", "b"]
+        );
+        assert_eq!(
+            config.get_typed::<Option<String>>(&path(".proxy")).unwrap(),
+            None
+        );
+
+        // Type mismatch
+        assert!(config.get_typed::<String>(&path(".server.port")).is_err());
+        // Out of range
+        assert!(config.get_typed::<u8>(&path(".server.port")).is_err());
+        // Missing path
+        assert!(config.get_typed::<u16>(&path(".server.missing")).is_err());
+    }
+
+    #[test]
+    fn get_wildcard_collects_elements() {
+        let config = make_config(serde_json::json!({
+            "outputs": [{ "level": "info" }, { "level": "debug" }],
+        }));
+
+        let path = [
+            PathSegment::Key("outputs".to_owned()),
+            PathSegment::Wildcard,
+            PathSegment::Key("level".to_owned()),
+        ];
+        let value = config.get(&path).unwrap();
+        assert_eq!(*value, serde_json::json!(["info", "debug"]));
+    }
+
+    #[test]
+    fn iter_leaves_yields_full_paths() {
+        let config = make_config(serde_json::json!({
+            "server": { "port": 8080, "tags": ["a", "b"] },
+            "key.with.dots": null,
+            "empty": {},
+        }));
+
+        let leaves = config
+            .iter_leaves()
+            .map(|(path, value)| (path, value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            leaves,
+            [
+                (".server.port".to_owned(), serde_json::json!(8080)),
+                (".server.tags[0]".to_owned(), serde_json::json!("a")),
+                (".server.tags[1]".to_owned(), serde_json::json!("b")),
+                ("[\"key.with.dots\"]".to_owned(), serde_json::Value::Null),
+            ]
+        );
+
+        for (path, value) in config.iter_leaves() {
+            let segments = parse_config_value_path(&path).unwrap();
+            assert_eq!(*config.get(&segments).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn apply_defaults_fills_missing_keys() {
+        let mut config = make_config(serde_json::json!({
+            "server": { "port": 8080 },
+            "peers": ["a"],
+            "metrics": null,
+        }));
+
+        let added = config.apply_defaults(&serde_json::json!({
+            "server": { "port": 1, "max_connections": 10 },
+            "peers": ["b", "c"],
+            "metrics": { "enabled": true },
+            "storage": { "path": "db" },
+        }));
+        assert_eq!(added, [".server.max_connections", ".storage"]);
+
+        assert_eq!(
+            config.as_object().unwrap(),
+            *serde_json::json!({
+                "server": { "port": 8080, "max_connections": 10 },
+                "peers": ["a"],
+                "metrics": null,
+                "storage": { "path": "db" },
+            })
+            .as_object()
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn find_matching_values() {
+        let config = make_config(serde_json::json!({
+            "server": { "port": 8080, "host": "LOCALHOST" },
+            "peers": ["localhost:8080", "8080"],
+            "enabled": true,
+        }));
+
+        let paths = |query: &str| {
+            config
+                .find(value_matcher(query))
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(paths("\"8080\""), [".server.port", ".peers[1]"]);
+        assert_eq!(paths("8080"), [".server.port", ".peers[1]"]);
+        assert_eq!(paths("localhost"), [".server.host"]);
+        assert_eq!(paths("true"), [".enabled"]);
+        assert!(paths("missing").is_empty());
+    }
+
+    #[test]
+    fn toml_roundtrip() {
+        let content = r#"
+port = 8080
+ratio = 0.5
+created_at = 1979-05-27T07:32:00Z
+
+[storage]
+path = "db"
+peers = ["a", "b"]
+"#;
+
+        let mut config = Config::from_toml_str("config.toml", content).unwrap();
+        assert_eq!(
+            config.as_object().unwrap(),
+            *serde_json::json!({
+                "port": 8080,
+                "ratio": 0.5,
+                "created_at": "1979-05-27T07:32:00Z",
+                "storage": { "path": "db", "peers": ["a", "b"] },
+            })
+            .as_object()
+            .unwrap()
+        );
+
+        let path = parse_config_value_path(".storage.limit").unwrap();
+        config.set(&path, serde_json::json!(u64::MAX)).unwrap();
+        let path = parse_config_value_path(".storage.extra").unwrap();
+        config.set(&path, serde_json::Value::Null).unwrap();
+
+        let toml = config.to_toml_str().unwrap();
+        let value = toml::from_str::<toml::Value>(&toml).unwrap();
+        assert_eq!(value["port"].as_integer(), Some(8080));
+        assert_eq!(value["created_at"].as_str(), Some("1979-05-27T07:32:00Z"));
+        assert_eq!(
+            value["storage"]["limit"].as_str(),
+            Some("18446744073709551615")
+        );
+        assert_eq!(value["storage"]["extra"].as_str(), Some("null"));
+    }
+
+    #[test]
+    fn toml_config_file() {
+        let file = TempFile::new("toml");
+        std::fs::write(file.path(), "[server]\nport = 8080\n").unwrap();
+        let path = file.path();
+
+        let mut config = Config::from_file(path).unwrap();
+        let port = parse_config_value_path(".server.port").unwrap();
+        assert_eq!(*config.get(&port).unwrap(), serde_json::json!(8080));
+
+        config.set(&port, serde_json::json!(9090)).unwrap();
+        assert!(config.diff_with_disk().unwrap().new.contains("9090"));
+        let diff = config.save().unwrap();
+        assert!(diff.new.contains("\"port\": 9090"), "{}", diff.new);
+
+        let content = std::fs::read_to_string(path).unwrap();
+        assert_eq!(content, "[server]\nport = 9090\n");
+    }
+}
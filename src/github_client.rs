@@ -1,10 +1,15 @@
-use std::sync::Arc;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use reqwest::{header, Url};
-use serde::Deserialize;
+use lru::LruCache;
+use reqwest::{header, StatusCode, Url};
+use serde::{Deserialize, Serialize};
 
 const USER_AGENT: &str = "tychonet-bot/1.0";
+const DEFAULT_API_URL: &str = "https://api.github.com";
 
 #[derive(Clone)]
 #[repr(transparent)]
@@ -13,15 +18,36 @@ pub struct GithubClient {
 }
 
 impl GithubClient {
-    pub fn new(token: &str, org: &str, repo: &str) -> Result<Self> {
-        let base_url = format!("https://api.github.com/repos/{org}/{repo}/").parse()?;
+    /// Creates a client for the public GitHub or an Enterprise server when
+    /// `api_url` is set, e.g. `https://github.example.com/api/v3`.
+    ///
+    /// NOTE: Enterprise servers may have different token scopes, the tokens must
+    /// have at least read access to the repository (and `repo:status` for statuses).
+    pub fn new<T: AsRef<str>>(
+        tokens: &[T],
+        api_url: Option<&str>,
+        org: &str,
+        repo: &str,
+    ) -> Result<Self> {
+        anyhow::ensure!(!tokens.is_empty(), "at least one github token is required");
 
-        let mut headers = header::HeaderMap::new();
+        let api_url = parse_api_url(api_url.unwrap_or(DEFAULT_API_URL))?;
+        let base_url = repo_url(&api_url, org, repo)?;
 
-        let mut bearer_header = reqwest::header::HeaderValue::try_from(format!("Bearer {token}"))?;
-        bearer_header.set_sensitive(true);
+        let tokens = tokens
+            .iter()
+            .map(|token| {
+                let mut bearer_header =
+                    header::HeaderValue::try_from(format!("Bearer {}", token.as_ref()))?;
+                bearer_header.set_sensitive(true);
+                Ok(TokenSlot {
+                    bearer_header,
+                    rate_limited: AtomicBool::new(false),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        headers.insert(header::AUTHORIZATION, bearer_header);
+        let mut headers = header::HeaderMap::new();
         headers.insert(
             header::USER_AGENT,
             header::HeaderValue::from_static(USER_AGENT),
@@ -37,38 +63,117 @@ impl GithubClient {
             .context("failed to build github client")?;
 
         Ok(Self {
-            inner: Arc::new(Inner { client, base_url }),
+            inner: Arc::new(Inner {
+                client,
+                api_url,
+                base_url,
+                repo_name: format!("{org}/{repo}"),
+                tokens: Arc::new(tokens),
+                current_token: Arc::new(AtomicUsize::new(0)),
+                commit_info_cache: CommitCache::new(0, Duration::ZERO),
+                commit_branches_cache: CommitCache::new(0, Duration::ZERO),
+            }),
+        })
+    }
+
+    /// Caches commit info and branches by full SHA. Zero `size` disables the cache.
+    pub fn with_commit_cache(self, size: usize, ttl: Duration) -> Self {
+        let this = &self.inner;
+        Self {
+            inner: Arc::new(Inner {
+                client: this.client.clone(),
+                api_url: this.api_url.clone(),
+                base_url: this.base_url.clone(),
+                repo_name: this.repo_name.clone(),
+                tokens: this.tokens.clone(),
+                current_token: this.current_token.clone(),
+                commit_info_cache: CommitCache::new(size, ttl),
+                commit_branches_cache: CommitCache::new(size, ttl),
+            }),
+        }
+    }
+
+    /// Creates a client for another repository with the same tokens.
+    ///
+    /// NOTE: Token rate limits are shared between both clients.
+    pub fn with_repo(&self, org: &str, repo: &str) -> Result<Self> {
+        let this = &self.inner;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                client: this.client.clone(),
+                api_url: this.api_url.clone(),
+                base_url: repo_url(&this.api_url, org, repo)?,
+                repo_name: format!("{org}/{repo}"),
+                tokens: this.tokens.clone(),
+                current_token: this.current_token.clone(),
+                commit_info_cache: this.commit_info_cache.empty_copy(),
+                commit_branches_cache: this.commit_branches_cache.empty_copy(),
+            }),
         })
     }
 
+    /// Repository in the `<org>/<repo>` form.
+    pub fn repo_name(&self) -> &str {
+        &self.inner.repo_name
+    }
+
+    /// Git clone URL of the repository, e.g. `https://github.com/broxus/tycho.git`.
+    pub fn clone_url(&self) -> String {
+        let mut url = self.inner.api_url.clone();
+        if url.host_str() == Some("api.github.com") {
+            url.set_host(Some("github.com")).ok();
+        }
+        url.set_path(&format!("{}.git", self.inner.repo_name));
+        url.to_string()
+    }
+
+    /// Returns `(slot, healthy)` for each configured token.
+    pub fn token_status(&self) -> Vec<(usize, bool)> {
+        self.inner
+            .tokens
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| (i, !slot.rate_limited.load(Ordering::Relaxed)))
+            .collect()
+    }
+
     pub async fn get_commit_sha(&self, branch: &str) -> Result<String> {
         let this = &self.inner;
 
         let url = this.base_url.join(&format!("commits/{branch}"))?;
         let response = this
-            .client
-            .get(url)
-            .header(header::ACCEPT, "application/vnd.github.sha")
-            .send()
-            .await?
-            .error_for_status()?;
+            .send(|client| {
+                client
+                    .get(url.clone())
+                    .header(header::ACCEPT, "application/vnd.github.sha")
+            })
+            .await?;
 
         response.text().await.context("failed to get commit sha")
     }
 
     pub async fn get_commit_info(&self, commit_sha: &str) -> Result<CommitInfo> {
         let this = &self.inner;
+        if let Some(info) = this.commit_info_cache.get(commit_sha) {
+            tracing::debug!(commit_sha, "commit info cache hit");
+            return Ok(info);
+        }
 
         let url = this.base_url.join(&format!("git/commits/{commit_sha}"))?;
         let response = this
-            .client
-            .get(url)
-            .header(header::ACCEPT, "application/vnd.github+json")
-            .send()
-            .await?
-            .error_for_status()?;
+            .send(|client| {
+                client
+                    .get(url.clone())
+                    .header(header::ACCEPT, "application/vnd.github+json")
+            })
+            .await?;
 
-        response.json().await.context("failed to get commit info")
+        let info = response
+            .json::<CommitInfo>()
+            .await
+            .context("failed to get commit info")?;
+        this.commit_info_cache.insert(commit_sha, info.clone());
+        Ok(info)
     }
 
     pub async fn get_commit_branches(&self, commit_sha: &str) -> Result<Vec<String>> {
@@ -78,23 +183,214 @@ impl GithubClient {
         }
 
         let this = &self.inner;
+        if let Some(branches) = this.commit_branches_cache.get(commit_sha) {
+            tracing::debug!(commit_sha, "commit branches cache hit");
+            return Ok(branches);
+        }
 
         let url = this
             .base_url
             .join(&format!("commits/{commit_sha}/branches-where-head"))?;
         let response = this
-            .client
-            .get(url)
-            .header(header::ACCEPT, "application/vnd.github+json")
-            .send()
-            .await?
-            .error_for_status()?;
+            .send(|client| {
+                client
+                    .get(url.clone())
+                    .header(header::ACCEPT, "application/vnd.github+json")
+            })
+            .await?;
 
-        response
+        let branches = response
             .json::<Vec<BranchInfo>>()
             .await
-            .map(|res| res.into_iter().map(|info| info.name).collect())
-            .context("failed to get commit info")
+            .map(|res| res.into_iter().map(|info| info.name).collect::<Vec<_>>())
+            .context("failed to get commit info")?;
+        this.commit_branches_cache
+            .insert(commit_sha, branches.clone());
+        Ok(branches)
+    }
+
+    pub async fn list_open_prs(&self, limit: u8) -> Result<Vec<PrInfo>> {
+        let this = &self.inner;
+
+        let mut url = this.base_url.join("pulls")?;
+        url.query_pairs_mut()
+            .append_pair("state", "open")
+            .append_pair("per_page", &limit.to_string());
+
+        let response = this
+            .send(|client| {
+                client
+                    .get(url.clone())
+                    .header(header::ACCEPT, "application/vnd.github+json")
+            })
+            .await?;
+
+        response
+            .json::<Vec<PullRequest>>()
+            .await
+            .map(|res| res.into_iter().map(PrInfo::from).collect())
+            .context("failed to get pull requests")
+    }
+
+    pub async fn get_pull_request(&self, number: u64) -> Result<PrInfo> {
+        let this = &self.inner;
+
+        let url = this.base_url.join(&format!("pulls/{number}"))?;
+        let response = this
+            .send(|client| {
+                client
+                    .get(url.clone())
+                    .header(header::ACCEPT, "application/vnd.github+json")
+            })
+            .await?;
+
+        response
+            .json::<PullRequest>()
+            .await
+            .map(PrInfo::from)
+            .context("failed to get pull request")
+    }
+
+    pub async fn compare_commits(&self, base: &str, head: &str) -> Result<CompareResult> {
+        let this = &self.inner;
+        check_ref_name(base)?;
+        check_ref_name(head)?;
+
+        let url = this.base_url.join(&format!("compare/{base}...{head}"))?;
+        let response = this
+            .send(|client| {
+                client
+                    .get(url.clone())
+                    .header(header::ACCEPT, "application/vnd.github+json")
+            })
+            .await?;
+
+        response
+            .json::<Comparison>()
+            .await
+            .map(CompareResult::from)
+            .context("failed to compare commits")
+    }
+
+    pub async fn get_workflow_runs(&self, sha: &str) -> Result<Vec<WorkflowRun>> {
+        #[derive(Deserialize)]
+        struct WorkflowRuns {
+            workflow_runs: Vec<WorkflowRun>,
+        }
+
+        let this = &self.inner;
+
+        let mut url = this.base_url.join("actions/runs")?;
+        url.query_pairs_mut().append_pair("head_sha", sha);
+
+        let response = this
+            .send(|client| {
+                client
+                    .get(url.clone())
+                    .header(header::ACCEPT, "application/vnd.github+json")
+            })
+            .await?;
+
+        response
+            .json::<WorkflowRuns>()
+            .await
+            .map(|res| res.workflow_runs)
+            .context("failed to get workflow runs")
+    }
+
+    pub async fn post_commit_status(
+        &self,
+        sha: &str,
+        state: CommitStatusState,
+        description: &str,
+        context: &str,
+    ) -> Result<()> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            state: CommitStatusState,
+            description: &'a str,
+            context: &'a str,
+        }
+
+        let this = &self.inner;
+        this.commit_info_cache.invalidate(sha);
+        this.commit_branches_cache.invalidate(sha);
+
+        let url = this.base_url.join(&format!("statuses/{sha}"))?;
+        let payload = Payload {
+            state,
+            description,
+            context,
+        };
+        this.send(|client| {
+            client
+                .post(url.clone())
+                .header(header::ACCEPT, "application/vnd.github+json")
+                .json(&payload)
+        })
+        .await
+        .context("failed to post commit status")?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitStatusState {
+    Pending,
+    Success,
+    Failure,
+    Error,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkflowRun {
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub html_url: String,
+}
+
+impl WorkflowRun {
+    pub fn ci_status(&self) -> CiStatus {
+        match self.conclusion.as_deref() {
+            Some("success" | "skipped" | "neutral") => CiStatus::Success,
+            Some(_) => CiStatus::Failure,
+            None => CiStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CiStatus {
+    Success,
+    Failure,
+    Pending,
+}
+
+impl CiStatus {
+    /// Combined status of all runs, `None` if there are no runs.
+    pub fn from_runs(runs: &[WorkflowRun]) -> Option<Self> {
+        let mut status = None;
+        for run in runs {
+            status = match (status, run.ci_status()) {
+                (Some(Self::Failure), _) | (_, Self::Failure) => Some(Self::Failure),
+                (Some(Self::Pending), _) | (_, Self::Pending) => Some(Self::Pending),
+                _ => Some(Self::Success),
+            };
+        }
+        status
+    }
+
+    pub fn as_emoji(&self) -> &'static str {
+        match self {
+            Self::Success => "🟢",
+            Self::Failure => "🔴",
+            Self::Pending => "🟡",
+        }
     }
 }
 
@@ -104,15 +400,447 @@ pub struct CommitInfo {
     pub message: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct PrInfo {
+    pub number: u64,
+    pub title: String,
+    pub head_branch: String,
+    pub head_sha: String,
+    pub author: String,
+    /// Whether the head branch is in another repository.
+    pub is_fork: bool,
+    /// Clone url of the head repository, `None` if it was deleted.
+    pub head_clone_url: Option<String>,
+}
+
+impl From<PullRequest> for PrInfo {
+    fn from(pr: PullRequest) -> Self {
+        let is_fork =
+            pr.head.repo.as_ref().map(|repo| &repo.full_name) != Some(&pr.base.repo.full_name);
+        Self {
+            number: pr.number,
+            title: pr.title,
+            head_branch: pr.head.branch,
+            head_sha: pr.head.sha,
+            author: pr.user.login,
+            is_fork,
+            head_clone_url: pr.head.repo.map(|repo| repo.clone_url),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PullRequest {
+    number: u64,
+    title: String,
+    head: PullRequestHead,
+    base: PullRequestBase,
+    user: PullRequestUser,
+}
+
+#[derive(Deserialize)]
+struct PullRequestHead {
+    #[serde(rename = "ref")]
+    branch: String,
+    sha: String,
+    repo: Option<PullRequestRepo>,
+}
+
+#[derive(Deserialize)]
+struct PullRequestBase {
+    repo: PullRequestRepo,
+}
+
+#[derive(Deserialize)]
+struct PullRequestRepo {
+    full_name: String,
+    clone_url: String,
+}
+
+#[derive(Deserialize)]
+struct PullRequestUser {
+    login: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompareResult {
+    pub html_url: String,
+    pub ahead_by: u32,
+    pub behind_by: u32,
+    pub commits: Vec<SimpleCommit>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimpleCommit {
+    pub sha: String,
+    pub message: String,
+}
+
+impl From<Comparison> for CompareResult {
+    fn from(comparison: Comparison) -> Self {
+        Self {
+            html_url: comparison.html_url,
+            ahead_by: comparison.ahead_by,
+            behind_by: comparison.behind_by,
+            commits: comparison
+                .commits
+                .into_iter()
+                .map(|c| SimpleCommit {
+                    sha: c.sha,
+                    message: c.commit.message,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Comparison {
+    html_url: String,
+    ahead_by: u32,
+    behind_by: u32,
+    commits: Vec<ComparisonCommit>,
+}
+
+#[derive(Deserialize)]
+struct ComparisonCommit {
+    sha: String,
+    commit: ComparisonCommitData,
+}
+
+#[derive(Deserialize)]
+struct ComparisonCommitData {
+    message: String,
+}
+
 struct Inner {
     client: reqwest::Client,
+    /// API root, either the public GitHub or an Enterprise server.
+    api_url: Url,
+    /// Repository API root.
     base_url: Url,
+    repo_name: String,
+    tokens: Arc<Vec<TokenSlot>>,
+    current_token: Arc<AtomicUsize>,
+    commit_info_cache: CommitCache<CommitInfo>,
+    commit_branches_cache: CommitCache<Vec<String>>,
+}
+
+impl Inner {
+    /// Sends a request built by `f`, rotating to the next token
+    /// when GitHub responds with `403` or `429`.
+    async fn send<F>(&self, f: F) -> Result<reqwest::Response>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder,
+    {
+        let mut last_error = None;
+        for _ in 0..self.tokens.len() {
+            let index = self.current_token.load(Ordering::Acquire) % self.tokens.len();
+            let slot = &self.tokens[index];
+
+            let response = f(&self.client)
+                .header(header::AUTHORIZATION, slot.bearer_header.clone())
+                .send()
+                .await?;
+
+            match response.status() {
+                StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                    tracing::warn!(index, "github token is rate limited, rotating");
+                    slot.rate_limited.store(true, Ordering::Relaxed);
+
+                    let next = (index + 1) % self.tokens.len();
+                    _ = self.current_token.compare_exchange(
+                        index,
+                        next,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    );
+
+                    last_error = Some(response.error_for_status().unwrap_err());
+                }
+                _ => {
+                    slot.rate_limited.store(false, Ordering::Relaxed);
+                    return response.error_for_status().map_err(Into::into);
+                }
+            }
+        }
+
+        Err(last_error
+            .map(Into::into)
+            .unwrap_or_else(|| anyhow::anyhow!("no github tokens available")))
+    }
+}
+
+/// LRU cache of per-commit responses with a TTL.
+///
+/// Only full SHAs are cached since branch names point to different commits over time.
+struct CommitCache<V> {
+    entries: Option<Mutex<LruCache<String, (V, Instant)>>>,
+    ttl: Duration,
+}
+
+impl<V: Clone> CommitCache<V> {
+    fn new(size: usize, ttl: Duration) -> Self {
+        Self {
+            entries: NonZeroUsize::new(size).map(|size| Mutex::new(LruCache::new(size))),
+            ttl,
+        }
+    }
+
+    /// Creates an empty cache with the same limits.
+    fn empty_copy(&self) -> Self {
+        let size = self
+            .entries
+            .as_ref()
+            .map(|entries| entries.lock().unwrap().cap().get())
+            .unwrap_or_default();
+        Self::new(size, self.ttl)
+    }
+
+    fn get(&self, sha: &str) -> Option<V> {
+        let mut entries = self.entries.as_ref()?.lock().unwrap();
+        let (value, cached_at) = entries.get(sha)?;
+        if cached_at.elapsed() < self.ttl {
+            return Some(value.clone());
+        }
+        entries.pop(sha);
+        None
+    }
+
+    fn insert(&self, sha: &str, value: V) {
+        if let Some(entries) = &self.entries {
+            if is_full_sha(sha) {
+                let mut entries = entries.lock().unwrap();
+                entries.put(sha.to_owned(), (value, Instant::now()));
+            }
+        }
+    }
+
+    fn invalidate(&self, sha: &str) {
+        if let Some(entries) = &self.entries {
+            entries.lock().unwrap().pop(sha);
+        }
+    }
+}
+
+fn is_full_sha(s: &str) -> bool {
+    s.len() == 40 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Parses the API root keeping its path when joining relative URLs.
+fn parse_api_url(s: &str) -> Result<Url> {
+    let mut url = Url::parse(s).with_context(|| format!("invalid github api url `{s}`"))?;
+    anyhow::ensure!(
+        matches!(url.scheme(), "http" | "https"),
+        "invalid github api url `{s}`"
+    );
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{}/", url.path()));
+    }
+    Ok(url)
+}
+
+fn repo_url(api_url: &Url, org: &str, repo: &str) -> Result<Url> {
+    let is_valid_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    anyhow::ensure!(
+        is_valid_name(org) && is_valid_name(repo),
+        "invalid github repository `{org}/{repo}`"
+    );
+
+    api_url
+        .join(&format!("repos/{org}/{repo}/"))
+        .map_err(Into::into)
+}
+
+/// Rejects refs which are not valid git ref names or could change the meaning
+/// of the request url (`..`, `?`, `#`, `%`).
+fn check_ref_name(name: &str) -> Result<()> {
+    let is_valid = !name.is_empty()
+        && !name.starts_with(['/', '-', '.'])
+        && !name.ends_with(['/', '.'])
+        && !name.ends_with(".lock")
+        && !name.contains("..")
+        && !name.contains("//")
+        && !name.contains("@{")
+        && !name.contains("/.")
+        && name.chars().all(|c| {
+            !c.is_ascii_control()
+                && !c.is_whitespace()
+                && !matches!(c, '?' | '#' | '%' | '\\' | '~' | '^' | ':' | '*' | '[')
+        });
+    anyhow::ensure!(is_valid, "invalid git ref `{name}`");
+    Ok(())
+}
+
+struct TokenSlot {
+    bearer_header: header::HeaderValue,
+    rate_limited: AtomicBool,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn with_repo_changes_base_url() -> Result<()> {
+        let client = GithubClient::new(&["token"], None, "broxus", "tycho")?;
+        let fork = client.with_repo("someone", "tycho-fork")?;
+
+        assert_eq!(client.repo_name(), "broxus/tycho");
+        assert_eq!(fork.repo_name(), "someone/tycho-fork");
+        assert_eq!(
+            fork.inner.base_url.as_str(),
+            "https://api.github.com/repos/someone/tycho-fork/"
+        );
+        assert!(Arc::ptr_eq(&client.inner.tokens, &fork.inner.tokens));
+
+        assert!(client.with_repo("broxus", "").is_err());
+        assert!(client.with_repo("broxus/tycho", "x").is_err());
+        assert_eq!(
+            fork.clone_url(),
+            "https://github.com/someone/tycho-fork.git"
+        );
+
+        let enterprise = GithubClient::new(
+            &["token"],
+            Some("https://github.example.com/api/v3"),
+            "broxus",
+            "tycho",
+        )?;
+        assert_eq!(
+            enterprise.inner.base_url.as_str(),
+            "https://github.example.com/api/v3/repos/broxus/tycho/"
+        );
+        assert_eq!(
+            enterprise
+                .with_repo("someone", "tycho-fork")?
+                .inner
+                .base_url
+                .as_str(),
+            "https://github.example.com/api/v3/repos/someone/tycho-fork/"
+        );
+        assert_eq!(
+            enterprise.clone_url(),
+            "https://github.example.com/broxus/tycho.git"
+        );
+        assert!(
+            GithubClient::new(&["token"], Some("ftp://example.com"), "broxus", "tycho").is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ref_names() {
+        for name in [
+            "master",
+            "feature/new-api",
+            "v1.2.3",
+            "0123abc",
+            "release-1.0_rc",
+        ] {
+            assert!(check_ref_name(name).is_ok(), "{name}");
+        }
+        for name in [
+            "", "a..b", "../pulls", "a?b=1", "a#b", "a%2e", "a b", "/a", "a/", "a//b", "a.lock",
+            "a@{1}", "a~1", "a:b", "a\\b",
+        ] {
+            assert!(check_ref_name(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn pr_info_from_fork() {
+        let pr = |head_repo: serde_json::Value| {
+            let pr = serde_json::from_value::<PullRequest>(serde_json::json!({
+                "number": 1,
+                "title": "Fix",
+                "head": { "ref": "fix", "sha": "abc", "repo": head_repo },
+                "base": { "repo": {
+                    "full_name": "broxus/tycho",
+                    "clone_url": "https://github.com/broxus/tycho.git",
+                } },
+                "user": { "login": "someone" },
+            }))
+            .unwrap();
+            PrInfo::from(pr)
+        };
+
+        let info = pr(serde_json::json!({
+            "full_name": "broxus/tycho",
+            "clone_url": "https://github.com/broxus/tycho.git",
+        }));
+        assert!(!info.is_fork);
+
+        let info = pr(serde_json::json!({
+            "full_name": "someone/tycho",
+            "clone_url": "https://github.com/someone/tycho.git",
+        }));
+        assert!(info.is_fork);
+        assert_eq!(
+            info.head_clone_url.as_deref(),
+            Some("https://github.com/someone/tycho.git")
+        );
+
+        let info = pr(serde_json::Value::Null);
+        assert!(info.is_fork);
+        assert_eq!(info.head_clone_url, None);
+    }
+
+    #[test]
+    fn commit_cache_only_full_shas() {
+        let sha = "0123456789abcdef0123456789abcdef01234567";
+
+        let cache = CommitCache::new(2, Duration::from_secs(60));
+        cache.insert("master", 1);
+        cache.insert(sha, 2);
+        assert_eq!(cache.get("master"), None);
+        assert_eq!(cache.get(sha), Some(2));
+
+        cache.invalidate(sha);
+        assert_eq!(cache.get(sha), None);
+
+        let expired = CommitCache::new(2, Duration::ZERO);
+        expired.insert(sha, 1);
+        assert_eq!(expired.get(sha), None);
+
+        let disabled = CommitCache::new(0, Duration::from_secs(60));
+        disabled.insert(sha, 1);
+        assert_eq!(disabled.get(sha), None);
+    }
+
+    #[test]
+    fn ci_status_from_runs() {
+        let run = |status: &str, conclusion: Option<&str>| WorkflowRun {
+            id: 1,
+            name: "CI".to_owned(),
+            status: status.to_owned(),
+            conclusion: conclusion.map(str::to_owned),
+            html_url: String::new(),
+        };
+        let success = run("completed", Some("success"));
+        let failure = run("completed", Some("failure"));
+        let pending = run("in_progress", None);
+
+        assert_eq!(CiStatus::from_runs(&[]), None);
+        assert_eq!(
+            CiStatus::from_runs(&[success.clone(), run("completed", Some("skipped"))]),
+            Some(CiStatus::Success)
+        );
+        assert_eq!(
+            CiStatus::from_runs(&[success.clone(), pending.clone()]),
+            Some(CiStatus::Pending)
+        );
+        assert_eq!(
+            CiStatus::from_runs(&[failure, pending, success]),
+            Some(CiStatus::Failure)
+        );
+    }
+
     #[ignore]
     #[tokio::test]
     async fn test_client() -> Result<()> {
@@ -120,7 +848,9 @@ mod tests {
 
         const TOKEN: &str = "";
 
-        let client = GithubClient::new(TOKEN, "broxus", "tycho")?;
+        // Uses the public GitHub unless an Enterprise server is configured
+        let api_url = std::env::var("TYCHONET_GITHUB_API_URL").ok();
+        let client = GithubClient::new(&[TOKEN], api_url.as_deref(), "broxus", "tycho")?;
 
         let sha = client.get_commit_sha("master").await?;
         println!("SHA: {sha}");
@@ -0,0 +1,105 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use ed25519_dalek::{Signer, SigningKey};
+use everscale_types::cell::{Cell, CellBuilder, HashBytes};
+use everscale_types::models::{
+    Account, AccountState, CurrencyCollection, ExtInMsgInfo, IntMsgInfo, Message, MsgInfo, StdAddr,
+};
+use everscale_types::num::Tokens;
+
+use crate::util::now_sec;
+
+/// Wallet v3 used as a token giver.
+pub struct GiverWallet {
+    address: StdAddr,
+    signing_key: SigningKey,
+}
+
+impl GiverWallet {
+    const WALLET_ID: u32 = 0x29a9a317;
+    const SEND_MODE: u8 = 3;
+    const MESSAGE_TTL: u32 = 60;
+
+    pub fn new(address: &str, private_key: &str) -> Result<Self> {
+        let address = StdAddr::from_str(address.trim()).context("invalid giver address")?;
+        let private_key =
+            HashBytes::from_str(private_key.trim()).context("invalid giver private key")?;
+
+        Ok(Self {
+            address,
+            signing_key: SigningKey::from_bytes(&private_key.0),
+        })
+    }
+
+    pub fn address(&self) -> &StdAddr {
+        &self.address
+    }
+
+    /// Builds a signed external message which transfers `amount` to `dst`.
+    pub fn build_transfer(&self, seqno: u32, dst: &StdAddr, amount: Tokens) -> Result<Cell> {
+        let expire_at = now_sec() as u32 + Self::MESSAGE_TTL;
+
+        let internal = CellBuilder::build_from(Message {
+            info: MsgInfo::Int(IntMsgInfo {
+                ihr_disabled: true,
+                bounce: false,
+                bounced: false,
+                src: self.address.clone().into(),
+                dst: dst.clone().into(),
+                value: CurrencyCollection::new(amount.into_inner()),
+                ihr_fee: Tokens::ZERO,
+                fwd_fee: Tokens::ZERO,
+                created_lt: 0,
+                created_at: 0,
+            }),
+            init: None,
+            body: Cell::empty_cell_ref().as_slice()?,
+            layout: None,
+        })?;
+
+        let build_body = |signature: Option<&[u8; 64]>| {
+            let mut builder = CellBuilder::new();
+            if let Some(signature) = signature {
+                builder.store_raw(signature, 512)?;
+            }
+            builder.store_u32(Self::WALLET_ID)?;
+            builder.store_u32(expire_at)?;
+            builder.store_u32(seqno)?;
+            builder.store_u8(Self::SEND_MODE)?;
+            builder.store_reference(internal.clone())?;
+            builder.build()
+        };
+
+        let unsigned_body = build_body(None)?;
+        let signature = self
+            .signing_key
+            .sign(unsigned_body.repr_hash().as_slice())
+            .to_bytes();
+        let body = build_body(Some(&signature))?;
+
+        CellBuilder::build_from(Message {
+            info: MsgInfo::ExtIn(ExtInMsgInfo {
+                src: None,
+                dst: self.address.clone().into(),
+                import_fee: Tokens::ZERO,
+            }),
+            init: None,
+            body: body.as_slice()?,
+            layout: None,
+        })
+        .map_err(Into::into)
+    }
+}
+
+/// Reads the wallet v3 seqno from the account data.
+pub fn parse_seqno(account: &Account) -> Result<u32> {
+    let AccountState::Active(state_init) = &account.state else {
+        anyhow::bail!("giver wallet is not active");
+    };
+    let data = state_init
+        .data
+        .as_ref()
+        .context("giver wallet has no data")?;
+    data.as_slice()?.load_u32().map_err(Into::into)
+}
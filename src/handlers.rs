@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
 use teloxide::prelude::*;
+use teloxide::types::{InputFile, ReplyParameters};
 use teloxide::utils::command::BotCommands;
 
 use crate::commands::Command;
-use crate::state::State;
-use crate::util::{SendMessageExt, WithLinkPreview};
+use crate::help;
+use crate::state::{CloneWorkspaceParams, CreateFromTemplateParams, Reply, State};
+use crate::util::{escaped_len, SendMessageExt, WithLinkPreview};
 
 pub async fn handle_command(
     bot: Bot,
@@ -13,6 +15,20 @@ pub async fn handle_command(
     cmd: Command,
     state: Arc<State>,
 ) -> ResponseResult<()> {
+    // NOTE: Redelivered updates must not run the command twice
+    if state.is_duplicate_command(&msg) {
+        return Ok(());
+    }
+
+    let unhealthy_network = cmd
+        .uses_network()
+        .then(|| state.current_unhealthy_network())
+        .flatten();
+
+    if cmd.manages_network() {
+        state.register_network_thread(&msg, None);
+    }
+
     let response = match cmd {
         Command::Start => {
             bot.send_message(msg.chat.id, Command::descriptions().to_string())
@@ -20,6 +36,24 @@ pub async fn handle_command(
                 .await?;
             return Ok(());
         }
+        Command::Help(expr) if expr.trim().is_empty() => {
+            bot.send_message(msg.chat.id, Command::descriptions().to_string())
+                .reply_to(&msg)
+                .await?;
+            return Ok(());
+        }
+        Command::Help(expr) => match help::extended_help(&expr) {
+            Some(text) => {
+                for chunk in help::split_message(text) {
+                    bot.send_message(msg.chat.id, chunk)
+                        .reply_to(&msg)
+                        .markdown()
+                        .await?;
+                }
+                return Ok(());
+            }
+            None => Err(anyhow::anyhow!("unknown command `{}`", expr.trim())),
+        },
         Command::GetChatId => {
             let chat_id = msg.chat.id;
             bot.send_message(chat_id, format!("Chat ID: {}", chat_id))
@@ -27,59 +61,267 @@ pub async fn handle_command(
                 .await?;
             return Ok(());
         }
+        Command::Info => state.get_info(),
         Command::Status => state.get_status().await,
+        Command::Shards => state.get_shards().await,
+        Command::GetSeqno => state.get_block_seqno().await.map(Reply::Seqno),
+        Command::HealthReport => state.get_health_report().await,
+        Command::RecheckNetwork(expr) => state.recheck_network(&expr).await,
+        Command::CheckNetwork(expr) => state.check_network(&expr).await,
+        Command::SetRpcUrl { network, url } => state.set_rpc_url(&msg, &network, &url).await,
+        Command::Pause(expr) => match expr.trim() {
+            "" => state.pause_monitoring(&msg, None),
+            duration => humantime::parse_duration(duration)
+                .map_err(Into::into)
+                .and_then(|duration| state.pause_monitoring(&msg, Some(duration))),
+        },
+        Command::Resume => state.resume_monitoring(&msg),
         Command::Freeze(expr) => state.freeze(&bot, &msg, &expr),
         Command::Unfreeze => state.unfreeze(&msg),
-        Command::Reset(commit) => match commit.parse() {
+        Command::ListFrozen => state.list_all_frozen(),
+        Command::GetResetStatus => state.get_reset_status(),
+        Command::Reset(commit) => match state.parse_and_validate_reset_params(&commit) {
             Ok(params) => {
+                state.register_network_thread(&msg, params.network.as_deref());
                 tokio::spawn(async move {
                     if let Err(e) = state.reset_network(bot.clone(), &msg, params).await {
                         tracing::error!("request failed: {e:?}");
-
-                        let reply = format!("Failed to handle reset:\n```\n{e}\n```");
-                        _ = bot
-                            .send_message(msg.chat.id, reply)
-                            .reply_to(&msg)
-                            .markdown()
-                            .await;
+                        _ = send_error(&bot, &msg, "Failed to handle reset", &e).await;
                     }
                 });
                 return Ok(());
             }
             Err(e) => Err(e),
         },
-        Command::GetCommit => state.get_saved_commit(),
-        Command::GetWorkspace => state.get_workspace(),
+        Command::RunPlaybook(expr) => {
+            tokio::spawn(async move {
+                let expr = expr.trim();
+                let (alias, extra_vars) = match expr.split_once(char::is_whitespace) {
+                    Some((alias, extra_vars)) => (alias, Some(extra_vars.trim())),
+                    None => (expr, None),
+                };
+
+                if let Err(e) = state
+                    .run_playbook(bot.clone(), &msg, alias, extra_vars)
+                    .await
+                {
+                    tracing::error!("request failed: {e:?}");
+                    _ = send_error(&bot, &msg, "Failed to run playbook", &e).await;
+                }
+            });
+            return Ok(());
+        }
+        Command::ListPrs(expr) => match state.list_open_prs(&expr).await {
+            Ok(keyboard) => {
+                bot.send_message(
+                    msg.chat.id,
+                    "Select a pull request to reset the network to:",
+                )
+                .reply_to(&msg)
+                .reply_markup(keyboard)
+                .await?;
+                return Ok(());
+            }
+            Err(e) => Err(e),
+        },
+        Command::CompareCommits(expr) => state.compare_commits(&expr).await,
+        Command::GetCommit => state.get_saved_commit().await,
+        Command::CiStatus(expr) => state.get_ci_status(&expr).await,
+        Command::RefreshCommits => state.refresh_commits(&msg).await,
+        Command::GetWorkspace(expr) => {
+            let name = Some(expr.trim()).filter(|name| !name.is_empty());
+            state.get_workspace_info(name)
+        }
+        Command::ListWorkspaces => state.list_workspaces_detailed(),
+        Command::SetWorkspace(expr) if expr.trim().is_empty() => {
+            bot.send_message(msg.chat.id, "Select a workspace:")
+                .reply_to(&msg)
+                .reply_markup(state.workspace_keyboard())
+                .await?;
+            return Ok(());
+        }
         Command::SetWorkspace(expr) => state.set_workspace(&msg, &expr),
+        Command::CloneWorkspace(expr) => expr.parse().and_then(|params: CloneWorkspaceParams| {
+            state.clone_workspace(&msg, &params.source, &params.dest, params.overwrite)
+        }),
+        Command::CreateFromTemplate(expr) => expr
+            .parse()
+            .and_then(|params: CreateFromTemplateParams| state.create_from_template(&msg, &params)),
+        Command::ListTemplates => Ok(state.list_templates()),
         Command::DelWorkspace(expr) => state.delete_workspace(&msg, &expr),
-        Command::GetNetwork => state.get_network(),
-        Command::SetNetwork(expr) => state.set_network(&msg, &expr),
+        Command::GetNetwork | Command::ListNetworks => state.list_networks_with_status().await,
+        Command::SetNetwork(expr) => state.set_network(&msg, &expr).await,
         Command::SetResetType(expr) => state.set_reset_type(&msg, &expr),
+        Command::SetEnvOverride(expr) => state.set_env_override(&msg, &expr),
         Command::GetResetType => state.get_reset_type(),
         Command::SetNodeConfig(expr) => state.set_node_config(&msg, &expr),
         Command::GetNodeConfig(expr) => state.get_node_config(&expr),
         Command::SetLoggerConfig(expr) => state.set_logger_config(&msg, &expr),
         Command::GetLoggerConfig(expr) => state.get_logger_config(&expr),
+        Command::Pending(expr) => expr
+            .trim()
+            .parse()
+            .and_then(|ty| state.get_pending_changes(ty)),
+        Command::GenerateSchema(expr) => match expr
+            .trim()
+            .parse()
+            .and_then(|ty| state.generate_schema(&msg, ty))
+        {
+            Ok(Reply::ConfigSchema {
+                path,
+                file_name,
+                schema,
+            }) => {
+                let document = InputFile::memory(schema).file_name(file_name);
+                bot.send_document(msg.chat.id, document)
+                    .caption(format!("Schema saved to {path}"))
+                    .reply_parameters(ReplyParameters {
+                        message_id: msg.id,
+                        ..Default::default()
+                    })
+                    .await?;
+                return Ok(());
+            }
+            res => res,
+        },
+        Command::FindConfig(expr) => state.find_config(&expr),
+        Command::MigrateConfig(expr) => state.migrate_config(&bot, &msg, &expr).await,
+        Command::CheckConfig(expr) => expr
+            .trim()
+            .parse()
+            .and_then(|ty| state.check_config_types(ty)),
+        Command::AuditConfig => state.audit_config_overrides(),
         Command::SetZeroState(expr) => state.set_zerostate(&msg, &expr),
         Command::GetZeroState(expr) => state.get_zerostate(&expr),
         Command::Give { address, amount } => {
-            // TODO
-            tracing::info!("{}{}", address, amount);
+            tokio::spawn(async move {
+                if let Err(e) = state.give_tokens(bot.clone(), &msg, &address, amount).await {
+                    tracing::error!("request failed: {e:?}");
+                    _ = send_error(&bot, &msg, "Failed to give tokens", &e).await;
+                }
+            });
             return Ok(());
         }
+        Command::TestWallet => state.get_wallet_status().await,
         Command::Account { address } => state.get_account(&address).await,
-        Command::GetParam { param } => state.get_param(param).await,
+        Command::GetAccounts { addresses } => state.get_all_accounts(&addresses.0).await,
+        Command::GetProof { address } => state.get_account_proof(&address).await,
+        Command::VerifyAccount { address } => state.verify_account(&address).await,
+        Command::GetElections => state.get_elections().await,
+        Command::GetParam { params } => state.get_params(&params.0).await,
+        Command::ConfigChanges => state.compare_blockchain_config().await,
+        Command::NodeMetric(name) => state.get_node_metrics(&name).await,
+        Command::GithubTokenStatus => state.get_github_token_status(),
+        Command::SetGithub(repo) => state.set_github_repo(&msg, &repo),
+        Command::Watch(expr) => match state.watch(&bot, &msg, &expr) {
+            Ok(()) => return Ok(()),
+            Err(e) => Err(e),
+        },
+        Command::Unwatch => state.unwatch(&msg),
+        Command::ResetStats => state.get_reset_stats(),
+        Command::GetBackups => state.get_backups(),
+        Command::Compact => state.compact_state(&msg),
+        Command::RestoreBackup(expr) => state.restore_backup(&bot, &msg, &expr),
+        Command::ExportState => match state.export_state(&msg) {
+            Ok(Reply::StateExported { data }) => {
+                let timestamp = humantime::format_rfc3339_seconds(std::time::SystemTime::now())
+                    .to_string()
+                    .replace(':', "-");
+                let document =
+                    InputFile::memory(data).file_name(format!("state_export_{timestamp}.json"));
+                bot.send_document(msg.chat.id, document)
+                    .reply_parameters(ReplyParameters {
+                        message_id: msg.id,
+                        ..Default::default()
+                    })
+                    .await?;
+                return Ok(());
+            }
+            res => res,
+        },
+        Command::ImportState => match state.import_state(&bot, &msg).await {
+            Ok(()) => return Ok(()),
+            Err(e) => Err(e),
+        },
+    };
+
+    let response = match unhealthy_network {
+        Some(network) => response.map(|reply| Reply::UnhealthyNetwork {
+            network,
+            reply: Box::new(reply),
+        }),
+        None => response,
     };
 
+    send_response(bot, &msg, response).await
+}
+
+pub async fn handle_message(bot: Bot, msg: Message, state: Arc<State>) -> ResponseResult<()> {
+    match state.handle_pending_input(&msg) {
+        Some(response) => send_response(bot, &msg, response).await,
+        None => Ok(()),
+    }
+}
+
+pub async fn handle_callback_query(
+    bot: Bot,
+    query: CallbackQuery,
+    state: Arc<State>,
+) -> ResponseResult<()> {
+    if let Err(e) = state.handle_callback(&bot, &query).await {
+        tracing::error!("callback failed: {e:?}");
+    }
+    Ok(())
+}
+
+async fn send_response(
+    bot: Bot,
+    msg: &Message,
+    response: anyhow::Result<Reply>,
+) -> ResponseResult<()> {
+    const MAX_TEXT_LEN: usize = 3000;
+    /// Telegram message length limit.
+    const MAX_MESSAGE_LEN: usize = 4096;
+
     let mut link_preview_options = None;
     let reply_text = match response {
         Ok(reply) => {
             link_preview_options = reply.link_preview_options();
-            reply.to_string()
+            let text = reply.to_string();
+
+            if let Some(name) = reply.document_name().filter(|_| text.len() > MAX_TEXT_LEN) {
+                let document = InputFile::memory(text).file_name(name);
+                bot.send_document(msg.chat.id, document)
+                    .reply_parameters(ReplyParameters {
+                        message_id: msg.id,
+                        ..Default::default()
+                    })
+                    .await?;
+                return Ok(());
+            }
+
+            match reply {
+                // Send the full config separately to keep the diff readable
+                Reply::ConfigUpdatedVerbose {
+                    ty,
+                    updated,
+                    config,
+                } if escaped_len(&text) > MAX_MESSAGE_LEN => {
+                    let document = InputFile::memory(config).file_name(format!("{ty}_config.json"));
+                    bot.send_document(msg.chat.id, document)
+                        .reply_parameters(ReplyParameters {
+                            message_id: msg.id,
+                            ..Default::default()
+                        })
+                        .await?;
+                    updated.to_string()
+                }
+                _ => text,
+            }
         }
         Err(err) => {
             tracing::error!("request failed: {err:?}");
-            format!("Failed to handle command:\n```\n{err}\n```")
+            return send_error(&bot, msg, "Failed to handle command", &err).await;
         }
     };
 
@@ -89,9 +331,37 @@ pub async fn handle_command(
     };
 
     teloxide::requests::JsonRequest::new(bot, req)
-        .reply_to(&msg)
+        .reply_to(msg)
         .markdown()
         .await?;
 
     Ok(())
 }
+
+/// Replies with the error, the full text is sent as a document if it doesn't fit.
+async fn send_error(
+    bot: &Bot,
+    msg: &Message,
+    context: &str,
+    err: &anyhow::Error,
+) -> ResponseResult<()> {
+    let reply = Reply::from_error(context, err);
+    let sent = bot
+        .send_message(msg.chat.id, reply.to_string())
+        .reply_to(msg)
+        .markdown()
+        .await;
+
+    // NOTE: The full error is still sent if the message itself was rejected
+    if let Some(cause) = reply.truncated_error() {
+        let document = InputFile::memory(cause.to_owned()).file_name("error.txt");
+        bot.send_document(msg.chat.id, document)
+            .reply_parameters(ReplyParameters {
+                message_id: msg.id,
+                ..Default::default()
+            })
+            .await?;
+    }
+    sent?;
+    Ok(())
+}
@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Max length of a single help message (Telegram allows up to 4096 chars).
+const MAX_MESSAGE_LEN: usize = 4000;
+
+/// Returns an extended help text for the command name (with or without `/`).
+pub fn extended_help(command: &str) -> Option<&'static str> {
+    let name = command.trim().trim_start_matches('/').to_lowercase();
+    help_texts().get(name.as_str()).copied()
+}
+
+/// Splits the text into messages which fit into the Telegram limit.
+pub fn split_message(text: &str) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        if !current.is_empty() && current.len() + line.len() + 1 > MAX_MESSAGE_LEN {
+            messages.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        messages.push(current);
+    }
+    messages
+}
+
+fn help_texts() -> &'static HashMap<&'static str, &'static str> {
+    static TEXTS: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    TEXTS.get_or_init(|| {
+        HashMap::from([
+            ("start", "/start\n\nDisplays the list of all supported commands."),
+            (
+                "help",
+                "/help [command]\n\n\
+                Without arguments displays the list of all supported commands.\n\
+                With a command name displays its extended help, e.g. `/help reset`.",
+            ),
+            ("getchatid", "/getchatid\n\nDisplays the ID of the current chat."),
+            (
+                "info",
+                "/info\n\n\
+                Displays the current workspace, network, reset type, deployed commit, \
+                frozen networks and the last reset time. Does not query the network.",
+            ),
+            (
+                "status",
+                "/status\n\n\
+                Displays masterchain timings of the current network \
+                and a summary of shard synchronization.",
+            ),
+            (
+                "healthreport",
+                "/healthreport\n\n\
+                Displays timings of all networks, JRPC health, freezes, running resets, \
+                the last reset outcome, the giver balance and paused alerts. \
+                Long reports are sent as a document.",
+            ),
+            (
+                "pause",
+                "/pause [duration]\n\n\
+                Stops sending health alerts during manual maintenance, e.g. `/pause 30min`. \
+                Without a duration alerts stay paused until /resume. \
+                The pause is not kept after the bot restarts.",
+            ),
+            ("resume", "/resume\n\nResumes paused health alerts."),
+            (
+                "rechecknetwork",
+                "/rechecknetwork [network]\n\n\
+                Retries JRPC requests to the specified network (or all networks) \
+                and updates the list of unhealthy networks.",
+            ),
+            (
+                "checknetwork",
+                "/checknetwork [network]\n\n\
+                Pings all inventory hosts of the network (the current one by default) \
+                with ansible and lists unreachable ones. Takes up to 30 seconds.",
+            ),
+            (
+                "setrpcurl",
+                "/setrpcurl <network> <url>\n\n\
+                Switches the network to a new JRPC endpoint without restarting the bot. \
+                The endpoint must respond within 5 seconds. The url is stored in the bot state \
+                and takes precedence over `RPC_URLS` from settings.",
+            ),
+            (
+                "shards",
+                "/shards\n\n\
+                Displays the last seqno and time diff for each shard of the current network.",
+            ),
+            (
+                "getseqno",
+                "/getseqno\n\n\
+                Displays only the last masterchain block seqno of the current network \
+                as a plain number, e.g. for dashboards.",
+            ),
+            (
+                "freeze",
+                "/freeze <duration>[: reason]\n\n\
+                Prevents network resets for the specified duration.\n\n\
+                Examples:\n\
+                `/freeze 2h`\n\
+                `/freeze 30m: running load tests`",
+            ),
+            ("unfreeze", "/unfreeze\n\nAllows network resets again."),
+            (
+                "listfrozen",
+                "/listfrozen\n\n\
+                Lists all networks with frozen resets sorted by the remaining time.",
+            ),
+            (
+                "getresetstatus",
+                "/getresetstatus\n\n\
+                Shows which networks have a reset in progress and for how long, \
+                so you can check it before switching networks or editing configs.",
+            ),
+            (
+                "reset",
+                "/reset [commit][; param=value]...\n\n\
+                Resets the network to the specified commit hash or branch name.\n\n\
+                Params:\n\
+                `commit` - commit hash or branch name \
+                (default: the workspace tracked branch or `master`)\n\
+                `repo=<url>` - repository to build from (default: the `/setgithub` one)\n\
+                `nodes=<count>` - number of nodes (default: `13`)\n\
+                `profile=<name>` - cargo build profile (default: `release`)\n\
+                `type=<full|restart>` - reset type (default: see /getresettype)\n\
+                `network=<name>` - network to reset (default: the workspace network)\n\
+                `tags=<tag>[,<tag>]...` - run only these tags of the reset playbook\n\
+                `skip_tags=<tag>[,<tag>]...` - skip these tags of the setup playbook\n\n\
+                Warnings about a lagging network, a changed node count or a debug build \
+                of a production network are shown before the reset starts but don't block it.\n\n\
+                Examples:\n\
+                `/reset`\n\
+                `/reset feature/new-consensus`\n\
+                `/reset 1a2b3c4; nodes=7; profile=debug`\n\
+                `/reset master; type=restart; network=devnet2`",
+            ),
+            (
+                "runplaybook",
+                "/runplaybook <alias> [extra_vars]\n\n\
+                Runs one of the allowed maintenance playbooks on the current network.\n\n\
+                Example:\n\
+                `/runplaybook cleanup keep_logs=true`",
+            ),
+            (
+                "listprs",
+                "/listprs [count]\n\n\
+                Lists open pull requests (default: `10`). \
+                Pressing a button resets the network to the PR head commit.",
+            ),
+            ("getcommit", "/getcommit\n\nDisplays the currently deployed commit with its current CI status."),
+            (
+                "cistatus",
+                "/cistatus [commit]\n\n\
+                Displays GitHub Actions workflow runs of the commit or branch \
+                (the deployed commit by default): 🟢 passed, 🔴 failed, 🟡 in progress.",
+            ),
+            (
+                "comparecommits",
+                "/comparecommits [base] <head>\n\n\
+                Displays the GitHub compare link with ahead/behind counts and the latest commits. \
+                The base defaults to the currently deployed commit.\n\n\
+                Examples:\n\
+                `/comparecommits master`\n\
+                `/comparecommits 1a2b3c4 feature/new-consensus`",
+            ),
+            (
+                "refreshcommits",
+                "/refreshcommits\n\n\
+                Fetches the head commits of branches tracked by workspaces \
+                (see `branch` in /setworkspace) and lists the updated ones.",
+            ),
+            (
+                "getworkspace",
+                "/getworkspace [name]\n\n\
+                Displays the network, reset type, config sources and last modification time \
+                of the specified workspace (or the current one).",
+            ),
+            (
+                "listworkspaces",
+                "/listworkspaces\n\n\
+                Displays all workspaces with their networks, config overrides \
+                and last modification time.",
+            ),
+            (
+                "setworkspace",
+                "/setworkspace [name][; copy_from=<workspace>][; copy_keys=<paths>][; branch=<name>]\n\n\
+                Switches to the specified workspace, creating it if needed. \
+                Without arguments displays a workspace selection keyboard.\n\n\
+                `copy_from` replaces all configs with the ones from another workspace. \
+                With `copy_keys` only the listed comma separated paths are copied, \
+                each path starts with the config type (`node`, `logger` or `zerostate`).\n\n\
+                `branch` sets the branch used by /reset without an explicit commit, \
+                an empty value stops tracking.\n\n\
+                Examples:\n\
+                `/setworkspace my-test`\n\
+                `/setworkspace my-test; copy_from=default`\n\
+                `/setworkspace my-test; copy_from=prod; copy_keys=node.mempool,logger.outputs`\n\
+                `/setworkspace staging; branch=staging`",
+            ),
+            (
+                "cloneworkspace",
+                "/cloneworkspace <source> <dest> [--overwrite]\n\n\
+                Copies the source workspace with all its configs without switching to it. \
+                An existing destination is only replaced with `--overwrite`.\n\n\
+                Example:\n\
+                `/cloneworkspace default my-test`",
+            ),
+            (
+                "createfromtemplate",
+                "/createfromtemplate <name>; template=<template>\n\n\
+                Creates a new workspace from the configs on disk and applies the template on top. \
+                Only values present in the template are overridden.\n\n\
+                Example:\n\
+                `/createfromtemplate my-debug; template=debug`",
+            ),
+            (
+                "listtemplates",
+                "/listtemplates\n\nDisplays workspace templates available for /createfromtemplate.",
+            ),
+            (
+                "delworkspace",
+                "/delworkspace <name>\n\n\
+                Removes the specified workspace. The default workspace cannot be removed.",
+            ),
+            (
+                "getnetwork",
+                "/getnetwork\n\nSame as /listnetworks.",
+            ),
+            (
+                "listnetworks",
+                "/listnetworks\n\n\
+                Displays all networks with the current one marked. For each network shows \
+                the sync status and the last mc block seqno (unreachable after 5 seconds), \
+                whether resets are frozen and whether a reset is running.",
+            ),
+            (
+                "setnetwork",
+                "/setnetwork <name> [--force]\n\n\
+                Sets the network of the current workspace. \
+                Unreachable networks are rejected unless `--force` is specified.",
+            ),
+            (
+                "setresettype",
+                "/setresettype <full|restart>\n/setresettype --list\n\n\
+                Sets the default reset type of the current workspace. \
+                Without arguments (or with `--list`) displays all reset types.\n\n\
+                `full` - tears down all data and redeploys from scratch\n\
+                `restart` - restarts nodes without wiping chain data",
+            ),
+            (
+                "getresettype",
+                "/getresettype\n\n\
+                Displays the reset type of the current workspace \
+                (falls back to the global one if not set).",
+            ),
+            (
+                "setenvoverride",
+                "/setenvoverride <path> [env_var]\n\n\
+                Fills the config value at `path` (e.g. `node.api.key`) from the environment \
+                variable each time workspace configs are applied, so secrets are not stored \
+                in the bot state. Strings are used as is, other values are parsed as JSON. \
+                Only `<CONFIG_ENV_OVERRIDE_PREFIX>_SECRET_*` variables can be used. \
+                Without `env_var` removes the override.",
+            ),
+            (
+                "setnodeconfig",
+                "/setnodeconfig [--verbose] <path> = <json>\n\
+                /setnodeconfig [--verbose] delete <path>[; <path>]...\n\
+                /setnodeconfig [--verbose] rename <path> <new_key>\n\n\
+                Updates the node config of the current workspace. \
+                Multiple paths are deleted together or not at all. \
+                With `--verbose` the full resulting config is shown after the diff, \
+                a long one is sent as a separate document.\n\n\
+                Examples:\n\
+                `/setnodeconfig .server.port = 8080`\n\
+                `/setnodeconfig .[\"key.with.dots\"] = true`\n\
+                `/setnodeconfig .peers[-1].port = 30000`\n\
+                `/setnodeconfig delete .metrics`\n\
+                `/setnodeconfig delete .legacy_port; .old.timeout`\n\
+                `/setnodeconfig rename .server.maxConnections max_connections`\n\
+                `/setnodeconfig --verbose .server.port = 8080`",
+            ),
+            (
+                "getnodeconfig",
+                "/getnodeconfig [path] [--toml]\n\n\
+                Displays the node config value at the specified path, e.g. `.peers[*].address`. \
+                Negative indices count from the end, e.g. `.peers[-1]` is the last peer. \
+                With `--toml` the value is shown as TOML unless it contains `null`s \
+                or integers out of the `i64` range.",
+            ),
+            (
+                "setloggerconfig",
+                "/setloggerconfig <path> = <json>\n/setloggerconfig delete <path>[; <path>]...\n\
+                /setloggerconfig rename <path> <new_key>\n\n\
+                Updates the logger config of the current workspace.",
+            ),
+            (
+                "getloggerconfig",
+                "/getloggerconfig [path]\n\nDisplays the logger config value at the specified path.",
+            ),
+            (
+                "pending",
+                "/pending <node|logger|zerostate>\n\n\
+                Displays the difference between the workspace config and the file on disk.",
+            ),
+            (
+                "findconfig",
+                "/findconfig <node|logger|zerostate> <value>\n\n\
+                Lists all config paths with the specified value. \
+                Strings are compared ignoring case, numbers also match their string form.\n\n\
+                Examples:\n\
+                `/findconfig node 8080`\n\
+                `/findconfig logger \"debug\"`",
+            ),
+            (
+                "checkconfig",
+                "/checkconfig <node|logger|zerostate>\n\n\
+                Lists values of the current workspace config whose type differs from \
+                the config file, e.g. `\"100\"` in the workspace and `100` on disk. \
+                Useful after upgrading the node when the config schema changes.",
+            ),
+            (
+                "migrateconfig",
+                "/migrateconfig [json]\n\n\
+                Renames keys in all configs of the current workspace, e.g. after a node \
+                upgrade changes the config schema. Takes a JSON map of source paths to \
+                new key names as the argument or from the replied document. \
+                Missing source paths are skipped, other failures leave configs unchanged.\n\n\
+                Example:\n\
+                `/migrateconfig {\"server.maxConnections\": \"max_connections\"}`",
+            ),
+            (
+                "generateschema",
+                "/generateschema <node|logger|zerostate>\n\n\
+                Generates a JSON Schema with the types of all current config values, \
+                saves it as `<config file>.schema.json` and sends it as a document. \
+                Constraints like `minimum`/`maximum` can then be added manually.",
+            ),
+            (
+                "auditconfig",
+                "/auditconfig\n\nDisplays config overrides of all workspaces.",
+            ),
+            (
+                "setzerostate",
+                "/setzerostate <path> = <json>\n/setzerostate delete <path>[; <path>]...\n\
+                /setzerostate rename <path> <new_key>\n\n\
+                Updates the zerostate config of the current workspace.",
+            ),
+            (
+                "getzerostate",
+                "/getzerostate [path]\n\n\
+                Displays the zerostate config value at the specified path. \
+                Missing values are displayed as `null` (using default).",
+            ),
+            (
+                "give",
+                "/give <address> <amount>\n\n\
+                Sends tokens from the giver wallet.\n\n\
+                Example:\n\
+                `/give 0:1111111111111111111111111111111111111111111111111111111111111111 10.5`",
+            ),
+            (
+                "testwallet",
+                "/testwallet\n\nDisplays the giver wallet balance and whether it is funded.",
+            ),
+            (
+                "account",
+                "/account <address>\n\nDisplays the account status and balance.",
+            ),
+            (
+                "getaccounts",
+                "/getaccounts <addresses>\n\n\
+                Displays statuses and balances of up to 50 accounts. \
+                Addresses are separated by newlines or commas.\n\n\
+                Example:\n\
+                `/getaccounts 0:1111111111111111111111111111111111111111111111111111111111111111, \
+                -1:3333333333333333333333333333333333333333333333333333333333333333`",
+            ),
+            (
+                "getproof",
+                "/getproof <address>\n\nDisplays hashes of the account state and its Merkle proof.",
+            ),
+            (
+                "getelections",
+                "/getelections\n\n\
+                Displays whether validator elections are open, \
+                the number of candidates and the frozen stake.",
+            ),
+            (
+                "verifyaccount",
+                "/verifyaccount <address>\n\n\
+                Requests the account state with a Merkle proof and checks \
+                that the proof is well-formed and includes the account state. \
+                The proof is not checked against the chain.",
+            ),
+            (
+                "getparam",
+                "/getparam <param>[,<param>]...\n\n\
+                Displays the blockchain config params, the config is fetched once.\n\n\
+                Examples:\n\
+                `/getparam 34`\n\
+                `/getparam 15,16,17`",
+            ),
+            (
+                "nodemetric",
+                "/nodemetric <name>\n\n\
+                Fetches the prometheus metrics of the current network from `METRICS_URLS` \
+                and displays the first sample of the metric with its labels. \
+                Counters can be queried without the `_total` suffix.",
+            ),
+            (
+                "configchanges",
+                "/configchanges\n\n\
+                Compares the blockchain config of the last reset network with the one \
+                saved right before the reset and displays the diff.",
+            ),
+            (
+                "githubtokenstatus",
+                "/githubtokenstatus\n\nDisplays whether each GitHub token is rate limited.",
+            ),
+            (
+                "setgithub",
+                "/setgithub [<org>/<repo>|default]\n\n\
+                Points commit lookups, PRs and commit statuses to another repository, \
+                e.g. a fork of tycho. The change is kept until the bot restarts. \
+                Without arguments shows the current repository.\n\n\
+                Examples:\n\
+                `/setgithub someone/tycho`\n\
+                `/setgithub default`",
+            ),
+            (
+                "watch",
+                "/watch <interval> <command>\n\n\
+                Repeats a read-only command and updates the reply in place. \
+                Supported commands: /status, /shards, /account, /getparam, /getcommit.\n\n\
+                Example:\n\
+                `/watch 30s /status`",
+            ),
+            ("unwatch", "/unwatch\n\nStops watching a command in this chat."),
+            (
+                "resetstats",
+                "/resetstats\n\n\
+                Displays the reset success rate, average duration and most used commits.",
+            ),
+            (
+                "exportstate",
+                "/exportstate\n\n\
+                Sends the bot state as a document. \
+                It is encrypted if the export key is configured.",
+            ),
+            (
+                "importstate",
+                "/importstate\n\n\
+                Send it as a reply to a document produced by /exportstate. \
+                The state is replaced after a confirmation.",
+            ),
+            ("getbackups", "/getbackups\n\nLists state backups."),
+            (
+                "compact",
+                "/compact\n\nKeeps only the newest reset history entries \
+                (`TYCHONET_COMPACT_MAX_HISTORY`) and reports the freed space.",
+            ),
+            (
+                "restorebackup",
+                "/restorebackup <name>\n\nRestores the state from the specified backup.",
+            ),
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use teloxide::utils::command::BotCommands;
+
+    use super::*;
+    use crate::commands::Command;
+
+    #[test]
+    fn all_commands_have_help() {
+        for command in Command::bot_commands() {
+            let name = command.command.trim_start_matches('/');
+            assert!(
+                extended_help(name).is_some(),
+                "missing extended help for /{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn split_long_message() {
+        let line = "a".repeat(100);
+        let text = vec![line.as_str(); 100].join("\n");
+
+        let messages = split_message(&text);
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(|m| m.len() <= MAX_MESSAGE_LEN));
+        assert_eq!(messages.join("\n"), text);
+    }
+}
@@ -1,13 +1,19 @@
+use std::collections::HashSet;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use everscale_types::boc::{Boc, BocRepr};
 use everscale_types::cell::{Cell, HashBytes};
-use everscale_types::models::{Account, BlockchainConfig, StdAddr, Transaction};
+use everscale_types::merkle::MerkleProof;
+use everscale_types::models::{Account, BlockchainConfig, OptionalAccount, StdAddr, Transaction};
 use reqwest::{IntoUrl, Url};
+use serde::de::DeserializeSeed;
 use serde::{Deserialize, Serialize};
 
+use crate::jrpc_log::JrpcLogger;
 use crate::util::{serde_option_string, serde_string};
 
 #[derive(Clone)]
@@ -17,7 +23,7 @@ pub struct JrpcClient {
 }
 
 impl JrpcClient {
-    pub fn new<U: IntoUrl>(endpoint: U) -> Result<Self> {
+    pub fn new<U: IntoUrl>(endpoint: U, logger: Option<JrpcLogger>) -> Result<Self> {
         let base_url = endpoint.into_url()?;
 
         let mut headers = reqwest::header::HeaderMap::new();
@@ -32,7 +38,12 @@ impl JrpcClient {
             .context("failed to build http client")?;
 
         Ok(Self {
-            inner: Arc::new(Inner { client, base_url }),
+            inner: Arc::new(Inner {
+                client,
+                base_url,
+                logger,
+                next_id: AtomicU64::new(1),
+            }),
         })
     }
 
@@ -45,6 +56,15 @@ impl JrpcClient {
             .await
     }
 
+    pub async fn get_shard_client_info(&self) -> Result<ShardClientInfo> {
+        self.inner
+            .post(&JrpcRequest {
+                method: "getShardClientInfo",
+                params: &(),
+            })
+            .await
+    }
+
     pub async fn get_account(&self, address: &StdAddr) -> Result<AccountStateResponse> {
         #[derive(Serialize)]
         struct Params<'a> {
@@ -59,7 +79,86 @@ impl JrpcClient {
             .await
     }
 
-    #[allow(unused)]
+    /// Queries all accounts in one batch request.
+    pub async fn get_accounts_batch(
+        &self,
+        addresses: &[StdAddr],
+    ) -> Result<Vec<AccountStateResponse>> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            address: &'a StdAddr,
+        }
+
+        let params = addresses
+            .iter()
+            .map(|address| Params { address })
+            .collect::<Vec<_>>();
+        let requests = params
+            .iter()
+            .map(|params| JrpcRequest {
+                method: "getContractState",
+                params,
+            })
+            .collect::<Vec<_>>();
+
+        self.inner.post_batch(&requests).await
+    }
+
+    pub async fn get_account_proof(&self, address: &StdAddr) -> Result<AccountProofResponse> {
+        #[derive(Serialize)]
+        struct Params<'a> {
+            address: &'a StdAddr,
+        }
+
+        self.inner
+            .post(&JrpcRequest {
+                method: "getContractStateWithProof",
+                params: &Params { address },
+            })
+            .await
+    }
+
+    /// Returns the account state along with the block proof it was taken from.
+    pub async fn get_account_with_proof(&self, address: &StdAddr) -> Result<AccountWithProof> {
+        let res = self.get_account_proof(address).await?;
+
+        let proof = res
+            .proof_boc
+            .parse_exotic::<MerkleProof>()
+            .context("invalid Merkle proof")?;
+        let account = res
+            .account
+            .parse::<OptionalAccount>()
+            .context("invalid account state")?
+            .0
+            .map(Box::new);
+
+        Ok(AccountWithProof {
+            account,
+            account_hash: *res.account.repr_hash(),
+            proof_root_hash: proof.hash,
+            proof: res.proof_boc,
+        })
+    }
+
+    pub async fn send_external_message(&self, message: Cell) -> Result<HashBytes> {
+        #[derive(Serialize)]
+        struct Params {
+            message: String,
+        }
+
+        self.inner
+            .post::<_, ()>(&JrpcRequest {
+                method: "sendMessage",
+                params: &Params {
+                    message: Boc::encode_base64(&message),
+                },
+            })
+            .await?;
+
+        Ok(*message.repr_hash())
+    }
+
     pub async fn get_dst_transaction(
         &self,
         message_hash: &HashBytes,
@@ -84,6 +183,34 @@ impl JrpcClient {
         Ok(Some(BocRepr::decode_base64(&tx)?))
     }
 
+    /// Polls `get_dst_transaction` until the transaction appears or the timeout expires.
+    ///
+    /// Returns `Ok(None)` on timeout. `on_poll` receives the elapsed seconds
+    /// before each retry. Dropping the future at any point is safe.
+    pub async fn poll_transaction<F: Fn(u32)>(
+        &self,
+        message_hash: &HashBytes,
+        timeout: Duration,
+        poll_interval: Duration,
+        on_poll: Option<F>,
+    ) -> Result<Option<Box<Transaction>>> {
+        let started_at = Instant::now();
+        loop {
+            if let Some(tx) = self.get_dst_transaction(message_hash).await? {
+                return Ok(Some(tx));
+            }
+
+            let elapsed = started_at.elapsed();
+            if elapsed >= timeout {
+                return Ok(None);
+            }
+            if let Some(on_poll) = &on_poll {
+                on_poll(elapsed.as_secs() as u32);
+            }
+            tokio::time::sleep(poll_interval.min(timeout - elapsed)).await;
+        }
+    }
+
     #[allow(unused)]
     pub async fn get_transactions(
         &self,
@@ -126,34 +253,145 @@ impl JrpcClient {
             })
             .await
     }
+
+    pub async fn get_elections(&self) -> Result<ElectionStatus> {
+        self.inner
+            .post(&JrpcRequest {
+                method: "getElections",
+                params: &(),
+            })
+            .await
+    }
 }
 
 struct Inner {
     client: reqwest::Client,
     base_url: Url,
+    logger: Option<JrpcLogger>,
+    next_id: AtomicU64,
 }
 
 impl Inner {
-    async fn post<Q, R>(&self, data: &Q) -> Result<R>
+    async fn post<P, R>(&self, data: &JrpcRequest<'_, P>) -> Result<R>
     where
-        Q: Serialize,
+        P: Serialize,
         for<'de> R: Deserialize<'de>,
     {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
         let response = self
             .client
             .post(self.base_url.clone())
-            .json(data)
+            .json(&JrpcRequestWithId { id, request: data })
             .send()
             .await?;
 
+        let status = response.status();
         let res = response.text().await?;
         tracing::info!(res);
 
-        match serde_json::from_str(&res)? {
+        if let Some(logger) = &self.logger {
+            logger.log(data.method, data.params, status.as_u16(), &res);
+        }
+
+        let mut de = serde_json::Deserializer::from_str(&res);
+        let response = JrpcResponseSeed::new(id).deserialize(&mut de)?;
+        de.end()?;
+
+        match response {
             JrpcResponse::Success(res) => Ok(res),
             JrpcResponse::Err(err) => anyhow::bail!(err),
         }
     }
+
+    /// Sends all requests as a JSON array in one HTTP request.
+    ///
+    /// Fails if any of the requests fails.
+    async fn post_batch<P, R>(&self, requests: &[JrpcRequest<'_, P>]) -> Result<Vec<R>>
+    where
+        P: Serialize,
+        for<'de> R: Deserialize<'de>,
+    {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let first_id = self
+            .next_id
+            .fetch_add(requests.len() as u64, Ordering::Relaxed);
+        let batch = requests
+            .iter()
+            .zip(first_id..)
+            .map(|(request, id)| JrpcRequestWithId { id, request })
+            .collect::<Vec<_>>();
+
+        let response = self
+            .client
+            .post(self.base_url.clone())
+            .json(&batch)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let res = response.text().await?;
+        tracing::info!(res);
+
+        if let Some(logger) = &self.logger {
+            for request in requests {
+                logger.log(request.method, request.params, status.as_u16(), &res);
+            }
+        }
+
+        parse_batch_response(&res, first_id, requests.len())
+    }
+}
+
+/// Matches batch responses to requests with ids `first_id..first_id + len`.
+///
+/// NOTE: Responses can be in any order.
+fn parse_batch_response<R>(res: &str, first_id: u64, len: usize) -> Result<Vec<R>>
+where
+    for<'de> R: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    struct ResponseId {
+        #[serde(default)]
+        id: Option<u64>,
+    }
+
+    let items = serde_json::from_str::<Vec<&serde_json::value::RawValue>>(res)
+        .context("invalid batch response")?;
+
+    let mut results = std::iter::repeat_with(|| None)
+        .take(len)
+        .collect::<Vec<Option<R>>>();
+    for item in items {
+        let ResponseId { id } = serde_json::from_str(item.get())?;
+        let index = id
+            .and_then(|id| id.checked_sub(first_id))
+            .map(|index| index as usize)
+            .filter(|index| *index < len);
+        let Some(index) = index else {
+            if let Ok(JrpcResponse::Err(err)) =
+                serde_json::from_str::<JrpcResponse<serde::de::IgnoredAny>>(item.get())
+            {
+                anyhow::bail!(err);
+            }
+            anyhow::bail!("unexpected response id {id:?}");
+        };
+        anyhow::ensure!(results[index].is_none(), "duplicate response id {id:?}");
+
+        let mut de = serde_json::Deserializer::from_str(item.get());
+        match JrpcResponseSeed::new(first_id + index as u64).deserialize(&mut de)? {
+            JrpcResponse::Success(res) => results[index] = Some(res),
+            JrpcResponse::Err(err) => anyhow::bail!(err),
+        }
+    }
+
+    results
+        .into_iter()
+        .zip(first_id..)
+        .map(|(res, id)| res.with_context(|| format!("missing response for request {id}")))
+        .collect()
 }
 
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -162,9 +400,50 @@ pub struct StateTimings {
     pub last_mc_block_seqno: u32,
     pub last_mc_utime: u32,
     pub mc_time_diff: i64,
+    #[serde(default, with = "serde_option_string")]
     pub smallest_known_lt: Option<u64>,
 }
 
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct ShardClientInfo {
+    pub shards: Vec<ShardStatus>,
+}
+
+impl ShardClientInfo {
+    pub fn lagging(&self) -> impl Iterator<Item = &ShardStatus> {
+        self.shards.iter().filter(|shard| !shard.is_healthy())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElectionStatus {
+    /// Id of the current elections, `None` if there are no elections.
+    pub election_id: Option<u32>,
+    pub election_open: bool,
+    pub candidates_count: u32,
+    #[serde(with = "serde_string")]
+    pub frozen_stake: u128,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardStatus {
+    #[serde(with = "serde_string")]
+    pub shard_id: u64,
+    pub last_seqno: u32,
+    pub time_diff: i64,
+}
+
+impl ShardStatus {
+    /// Max allowed lag (in seconds) behind the masterchain.
+    pub const MAX_TIME_DIFF: i64 = 30;
+
+    pub fn is_healthy(&self) -> bool {
+        self.time_diff.abs() <= Self::MAX_TIME_DIFF
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LatestBlockchainConfig {
@@ -193,6 +472,54 @@ pub enum AccountStateResponse {
     },
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountProofResponse {
+    #[serde(with = "Boc")]
+    pub account: Cell,
+    #[serde(with = "Boc")]
+    pub proof_boc: Cell,
+}
+
+pub struct AccountWithProof {
+    pub account: Option<Box<Account>>,
+    pub account_hash: HashBytes,
+    /// Root hash claimed by the proof, not checked against the chain.
+    pub proof_root_hash: HashBytes,
+    /// Merkle proof cell of the block.
+    pub proof: Cell,
+}
+
+impl AccountWithProof {
+    /// Checks that the proof is well-formed and includes a cell with the account hash.
+    ///
+    /// NOTE: This is only a consistency check of the response. The root hash
+    /// is taken from the proof itself and the account cell is not looked up
+    /// by its address, so it doesn't prove that the account is in the chain.
+    pub fn is_consistent(&self) -> bool {
+        let Ok(proof) = self.proof.parse_exotic::<MerkleProof>() else {
+            return false;
+        };
+        if *proof.cell.hash(0) != proof.hash {
+            return false;
+        }
+
+        // The account cell is either included as is or as a pruned branch,
+        // both keep the original hash at level 0
+        let mut visited = HashSet::new();
+        let mut stack = vec![proof.cell.as_ref()];
+        while let Some(cell) = stack.pop() {
+            if *cell.hash(0) == self.account_hash {
+                return true;
+            }
+            if visited.insert(*cell.repr_hash()) {
+                stack.extend(cell.references());
+            }
+        }
+        false
+    }
+}
+
 fn deserialize_account<'de, D>(deserializer: D) -> Result<Box<Account>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -240,7 +567,13 @@ struct JrpcRequest<'a, T> {
     params: &'a T,
 }
 
-impl<T: Serialize> Serialize for JrpcRequest<'_, T> {
+/// Request with a unique id assigned on each [`Inner::post`].
+struct JrpcRequestWithId<'a, T> {
+    id: u64,
+    request: &'a JrpcRequest<'a, T>,
+}
+
+impl<T: Serialize> Serialize for JrpcRequestWithId<'_, T> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
@@ -249,9 +582,9 @@ impl<T: Serialize> Serialize for JrpcRequest<'_, T> {
 
         let mut ser = serializer.serialize_struct("JrpcRequest", 4)?;
         ser.serialize_field("jsonrpc", "2.0")?;
-        ser.serialize_field("id", &1)?;
-        ser.serialize_field("method", self.method)?;
-        ser.serialize_field("params", self.params)?;
+        ser.serialize_field("id", &self.id)?;
+        ser.serialize_field("method", self.request.method)?;
+        ser.serialize_field("params", self.request.params)?;
         ser.end()
     }
 }
@@ -266,12 +599,49 @@ where
     T: Deserialize<'de>,
 {
     fn deserialize<D>(de: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        JrpcResponseSeed {
+            expected_id: None,
+            _marker: PhantomData,
+        }
+        .deserialize(de)
+    }
+}
+
+/// Deserializes a response checking that its `id` matches the request.
+///
+/// NOTE: Errors without an `id` are accepted since the server can't always
+/// parse it from the request.
+struct JrpcResponseSeed<T> {
+    expected_id: Option<u64>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> JrpcResponseSeed<T> {
+    fn new(expected_id: u64) -> Self {
+        Self {
+            expected_id: Some(expected_id),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for JrpcResponseSeed<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = JrpcResponse<T>;
+
+    fn deserialize<D>(self, de: D) -> std::result::Result<Self::Value, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
         #[derive(Debug, Deserialize)]
         #[serde(rename_all = "lowercase")]
         enum Field {
+            Id,
             Result,
             Error,
             #[serde(other)]
@@ -283,7 +653,10 @@ where
             Error(Box<serde_json::value::RawValue>),
         }
 
-        struct ResponseVisitor<T>(PhantomData<T>);
+        struct ResponseVisitor<T> {
+            expected_id: Option<u64>,
+            _marker: PhantomData<T>,
+        }
 
         impl<'de, T> serde::de::Visitor<'de> for ResponseVisitor<T>
         where
@@ -299,10 +672,14 @@ where
             where
                 A: serde::de::MapAccess<'de>,
             {
+                let mut id = None::<u64>;
                 let mut result = None::<ResponseData<T>>;
 
                 while let Some(key) = map.next_key()? {
                     match key {
+                        Field::Id => {
+                            id = map.next_value()?;
+                        }
                         Field::Result if result.is_none() => {
                             result = Some(map.next_value().map(ResponseData::Result)?);
                         }
@@ -317,11 +694,30 @@ where
                     }
                 }
 
-                result.ok_or_else(|| serde::de::Error::missing_field("result or error"))
+                let result =
+                    result.ok_or_else(|| serde::de::Error::missing_field("result or error"))?;
+
+                if let Some(expected_id) = self.expected_id {
+                    match (id, &result) {
+                        (Some(id), _) if id == expected_id => {}
+                        (None, ResponseData::Error(_)) => {}
+                        (id, _) => {
+                            return Err(serde::de::Error::custom(format_args!(
+                                "response id mismatch: expected {expected_id}, got {id:?}"
+                            )));
+                        }
+                    }
+                }
+
+                Ok(result)
             }
         }
 
-        Ok(match de.deserialize_map(ResponseVisitor(PhantomData))? {
+        let visitor = ResponseVisitor {
+            expected_id: self.expected_id,
+            _marker: PhantomData,
+        };
+        Ok(match de.deserialize_map(visitor)? {
             ResponseData::Result(result) => JrpcResponse::Success(result),
             ResponseData::Error(error) => JrpcResponse::Err(error),
         })
@@ -343,4 +739,103 @@ mod tests {
         let json = "{\"jsonrpc\":\"2.0\",\"result\":42,\"id\":1}";
         serde_json::from_str::<JrpcResponse<i32>>(json).unwrap();
     }
+
+    #[test]
+    fn shard_client_info() {
+        let json = "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"shards\":[{\"shardId\":\"9223372036854775808\",\"lastSeqno\":10,\"timeDiff\":-2}]}}";
+        let JrpcResponse::Success(info) =
+            serde_json::from_str::<JrpcResponse<ShardClientInfo>>(json).unwrap()
+        else {
+            panic!("expected success");
+        };
+        assert_eq!(info.shards.len(), 1);
+        assert_eq!(info.shards[0].shard_id, 0x8000000000000000);
+        assert!(info.shards[0].is_healthy());
+    }
+
+    #[test]
+    fn smallest_known_lt() {
+        let timings = |lt: &str| {
+            let json = format!("{{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{{\"lastMcBlockSeqno\":1,\"lastMcUtime\":2,\"mcTimeDiff\":-3{lt}}}}}");
+            let JrpcResponse::Success(timings) =
+                serde_json::from_str::<JrpcResponse<StateTimings>>(&json).unwrap()
+            else {
+                panic!("expected success");
+            };
+            timings.smallest_known_lt
+        };
+        assert_eq!(timings(",\"smallestKnownLt\":\"123\""), Some(123));
+        assert_eq!(timings(",\"smallestKnownLt\":123"), Some(123));
+        assert_eq!(timings(",\"smallestKnownLt\":null"), None);
+        assert_eq!(timings(""), None);
+    }
+
+    #[test]
+    fn election_status() {
+        let json = "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"electionId\":null,\"electionOpen\":false,\"candidatesCount\":0,\"frozenStake\":\"340282366920938463463374607431768211455\"}}";
+        let JrpcResponse::Success(elections) =
+            serde_json::from_str::<JrpcResponse<ElectionStatus>>(json).unwrap()
+        else {
+            panic!("expected success");
+        };
+        assert_eq!(elections.election_id, None);
+        assert_eq!(elections.frozen_stake, u128::MAX);
+    }
+
+    #[test]
+    fn request_ids() {
+        let request = |id| {
+            serde_json::to_value(JrpcRequestWithId {
+                id,
+                request: &JrpcRequest {
+                    method: "getTimings",
+                    params: &(),
+                },
+            })
+            .unwrap()
+        };
+        assert_eq!(request(1)["id"], 1);
+        assert_eq!(request(42)["id"], 42);
+
+        let response = |json: &str, id| {
+            let mut de = serde_json::Deserializer::from_str(json);
+            JrpcResponseSeed::<i32>::new(id).deserialize(&mut de)
+        };
+        let json = "{\"jsonrpc\":\"2.0\",\"id\":7,\"result\":42}";
+        assert!(matches!(response(json, 7), Ok(JrpcResponse::Success(42))));
+        assert!(response(json, 8).is_err());
+
+        let json = "{\"jsonrpc\":\"2.0\",\"result\":42}";
+        assert!(response(json, 7).is_err());
+
+        let json = "{\"jsonrpc\":\"2.0\",\"id\":null,\"error\":{\"code\":-32700,\"message\":\"parse error\"}}";
+        assert!(matches!(response(json, 7), Ok(JrpcResponse::Err(_))));
+    }
+
+    #[test]
+    fn batch_response() {
+        let json = r#"[
+            {"jsonrpc":"2.0","id":11,"result":2},
+            {"jsonrpc":"2.0","id":10,"result":1}
+        ]"#;
+        assert_eq!(parse_batch_response::<i32>(json, 10, 2).unwrap(), [1, 2]);
+        assert!(parse_batch_response::<i32>(json, 10, 3).is_err());
+        assert!(parse_batch_response::<i32>(json, 11, 2).is_err());
+
+        let json = r#"[
+            {"jsonrpc":"2.0","id":10,"result":1},
+            {"jsonrpc":"2.0","id":10,"result":1}
+        ]"#;
+        assert!(parse_batch_response::<i32>(json, 10, 2).is_err());
+
+        let json = r#"[
+            {"jsonrpc":"2.0","id":10,"result":1},
+            {"jsonrpc":"2.0","id":11,"error":{"code":-32601,"message":"unknown method"}}
+        ]"#;
+        assert!(parse_batch_response::<i32>(json, 10, 2).is_err());
+
+        let json =
+            r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32600,"message":"invalid request"}}"#;
+        assert!(parse_batch_response::<i32>(json, 10, 1).is_err());
+    }
 }
@@ -0,0 +1,200 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+use crate::util::now_sec;
+
+/// Max size of a single log file before it is rotated.
+const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+/// Total number of log files including the current one.
+const MAX_FILES: usize = 3;
+/// Max length of the logged response body.
+const MAX_RESPONSE_PREVIEW_LEN: usize = 1024;
+/// Entries are dropped when the writer falls behind this much.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Appends JRPC requests and responses to a rotating log file.
+///
+/// Entries are written by a background task so logging never blocks requests.
+#[derive(Clone)]
+pub struct JrpcLogger {
+    tx: mpsc::Sender<JrpcLogEntry>,
+}
+
+impl JrpcLogger {
+    pub fn spawn(path: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(writer_task(path, rx));
+        Self { tx }
+    }
+
+    pub fn log(&self, method: &str, params: &impl Serialize, status: u16, response: &str) {
+        let entry = JrpcLogEntry {
+            timestamp: now_sec(),
+            method: method.to_owned(),
+            params_hash: params_hash(params),
+            status,
+            response_preview: truncate(response, MAX_RESPONSE_PREVIEW_LEN).to_owned(),
+        };
+
+        if self.tx.try_send(entry).is_err() {
+            tracing::warn!("JRPC log queue is full, entry dropped");
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JrpcLogEntry {
+    timestamp: u64,
+    method: String,
+    params_hash: String,
+    status: u16,
+    response_preview: String,
+}
+
+async fn writer_task(path: PathBuf, mut rx: mpsc::Receiver<JrpcLogEntry>) {
+    let mut file = None::<RotatingFile>;
+    while let Some(entry) = rx.recv().await {
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                tracing::error!("failed to serialize JRPC log entry: {e}");
+                continue;
+            }
+        };
+        line.push('\n');
+
+        if file.is_none() {
+            match RotatingFile::open(path.clone()).await {
+                Ok(f) => file = Some(f),
+                Err(e) => {
+                    tracing::error!("failed to open JRPC log file: {e:?}");
+                    continue;
+                }
+            }
+        }
+
+        if let Some(f) = &mut file {
+            if let Err(e) = f.write_line(line.as_bytes()).await {
+                tracing::error!("failed to write JRPC log entry: {e:?}");
+                file = None;
+            }
+        }
+    }
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: tokio::fs::File,
+    size: u64,
+}
+
+impl RotatingFile {
+    async fn open(path: PathBuf) -> Result<Self> {
+        let file = open_append(&path).await?;
+        let size = file.metadata().await?.len();
+        Ok(Self { path, file, size })
+    }
+
+    async fn write_line(&mut self, line: &[u8]) -> Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > MAX_FILE_SIZE {
+            self.rotate().await?;
+        }
+
+        self.file.write_all(line).await?;
+        self.file.flush().await?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shifts `log.N` to `log.N+1` dropping the oldest file and starts a new one.
+    async fn rotate(&mut self) -> Result<()> {
+        for i in (1..MAX_FILES).rev() {
+            let from = if i == 1 {
+                self.path.clone()
+            } else {
+                rotated_path(&self.path, i - 1)
+            };
+            if tokio::fs::try_exists(&from).await? {
+                tokio::fs::rename(&from, rotated_path(&self.path, i))
+                    .await
+                    .context("failed to rotate JRPC log file")?;
+            }
+        }
+
+        self.file = open_append(&self.path).await?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+async fn open_append(path: &Path) -> Result<tokio::fs::File> {
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("failed to open {}", path.display()))
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{index}"));
+    PathBuf::from(path)
+}
+
+fn params_hash(params: &impl Serialize) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(params)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn truncate(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+    let mut end = max_len;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_on_char_boundary() {
+        assert_eq!(truncate("hello", 10), "hello");
+        assert_eq!(truncate("hello", 3), "hel");
+        assert_eq!(truncate("ф", 1), "");
+    }
+
+    #[tokio::test]
+    async fn rotate_keeps_max_files() {
+        let dir = std::env::temp_dir().join(format!("tychonet-jrpc-log-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("jrpc.log");
+
+        let mut file = RotatingFile::open(path.clone()).await.unwrap();
+        for _ in 0..MAX_FILES + 1 {
+            file.write_line(b"entry\n").await.unwrap();
+            file.rotate().await.unwrap();
+        }
+
+        assert!(path.exists());
+        for i in 1..MAX_FILES {
+            assert!(rotated_path(&path, i).exists());
+        }
+        assert!(!rotated_path(&path, MAX_FILES).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
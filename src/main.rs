@@ -4,17 +4,22 @@ use anyhow::Result;
 use teloxide::net;
 use teloxide::prelude::*;
 use teloxide::utils::command::BotCommands;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
 
 use crate::commands::Command;
-use crate::handlers::handle_command;
-use crate::settings::load_settings;
+use crate::handlers::{handle_callback_query, handle_command, handle_message};
+use crate::settings::{load_settings, watch_settings, ENV_FILE};
 use crate::state::State;
 
 mod commands;
 mod config;
 mod github_client;
+mod giver;
 mod handlers;
+mod help;
 mod jrpc_client;
+mod jrpc_log;
 mod settings;
 mod state;
 mod util;
@@ -34,12 +39,49 @@ async fn main() -> Result<()> {
 
     tracing::info!("bot started");
 
-    let state = State::new(bot.clone(), &settings).await?;
+    let (settings_tx, settings_rx) = watch::channel(settings);
+    match std::fs::canonicalize(ENV_FILE) {
+        Ok(path) => {
+            tokio::spawn(watch_settings(path, settings_tx));
+        }
+        Err(e) => tracing::warn!("settings hot-reload is disabled: {e}"),
+    }
 
-    Command::repl(bot, move |bot, msg, cmd| {
-        handle_command(bot, msg, cmd, state.clone())
-    })
-    .await;
+    let state = State::new(bot.clone(), settings_rx).await?;
 
+    let mut sigterm = signal(SignalKind::terminate())?;
+
+    let handler = dptree::entry()
+        .branch(
+            Update::filter_message()
+                .filter_command::<Command>()
+                .endpoint(handle_command),
+        )
+        .branch(Update::filter_message().endpoint(handle_message))
+        .branch(Update::filter_callback_query().endpoint(handle_callback_query));
+
+    let mut dispatcher = Dispatcher::builder(bot, handler)
+        .dependencies(dptree::deps![state.clone()])
+        .enable_ctrlc_handler()
+        .build();
+
+    tokio::select! {
+        _ = dispatcher.dispatch() => tracing::info!("dispatcher stopped"),
+        _ = sigterm.recv() => tracing::info!("received SIGTERM"),
+    }
+
+    state.shutdown_token().trigger();
+
+    tracing::info!("waiting for active resets to finish");
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, state.wait_for_resets())
+        .await
+        .is_err()
+    {
+        tracing::warn!("active resets did not finish in time");
+    }
+
+    tracing::info!("bot stopped");
     Ok(())
 }
+
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(60);
@@ -1,51 +1,361 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
 
 use anyhow::{Context, Result};
 use dotenvy::dotenv;
 use serde::Deserialize;
+use tokio::sync::watch;
 
-#[derive(Debug, Deserialize)]
+pub const ENV_FILE: &str = ".env";
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct Settings {
     pub bot_token: String,
     pub rpc_urls: HashMap<String, String>,
+    /// Prometheus endpoints of the networks used by `/nodemetric`.
+    pub metrics_urls: HashMap<String, String>,
     pub default_network: String,
     pub inventory_files: HashMap<String, String>,
-    pub ansible_config_file: String,
+    pub ansible_config_files: HashMap<String, String>,
+    pub default_ansible_config_file: String,
+    pub ansible_timeouts: HashMap<String, u64>,
     pub node_config_file: String,
+    pub node_config_defaults_file: Option<String>,
     pub logger_config_file: String,
     pub zerostate_file: String,
-    pub github_token: String,
+    pub github_tokens: Vec<String>,
+    /// GitHub Enterprise API root, the public GitHub is used by default.
+    pub github_api_url: Option<String>,
+    pub github_cache_size: usize,
+    pub github_cache_ttl_secs: u64,
     pub reset_playbook: String,
     pub setup_playbook: String,
+    pub post_reset_check_script: Option<String>,
+    pub allowed_playbooks: HashMap<String, String>,
+    pub workspace_templates: HashMap<String, String>,
+    pub ansible_vault_password_file: Option<String>,
+    pub ansible_vault_password: Option<String>,
     pub allowed_groups: Vec<i64>,
+    pub production_networks: Vec<String>,
     pub authentication_enabled: bool,
     pub state_file: String,
+    pub skip_inventory_validation: bool,
+    pub require_all_inventories: bool,
+    pub github_commit_status_enabled: bool,
+    pub config_validators_file: Option<String>,
+    pub state_backup_dir: Option<String>,
+    pub state_backup_max_count: usize,
+    /// Number of reset history entries kept by `/compact`.
+    pub compact_max_history: usize,
+    pub config_env_override_prefix: Option<String>,
+    pub config_schema_dir: Option<String>,
+    pub giver_address: Option<String>,
+    pub giver_private_key: Option<String>,
+    /// Lower balances are shown as unfunded and alerted by the giver monitor.
+    pub min_giver_balance_tokens: u128,
+    pub giver_poll_interval_secs: u64,
+    /// Interval between JRPC health checks of all networks.
+    pub network_poll_interval_secs: u64,
+    pub state_export_key: Option<String>,
+    pub jrpc_log_file: Option<String>,
 }
 
+/// Environment variables by name.
+type Env = HashMap<String, String>;
+
+/// Process environment before the env file was loaded.
+static PROCESS_ENV: OnceLock<Env> = OnceLock::new();
+
 pub fn load_settings() -> Result<Settings> {
+    PROCESS_ENV.get_or_init(process_env);
     dotenv().ok();
+    read_valid_settings(&process_env())
+}
+
+/// Reloads settings from the env file on top of the initial process environment,
+/// file values take precedence.
+///
+/// The process environment itself is not changed, so variables removed
+/// from the file fall back to their defaults.
+pub fn reload_settings(path: &Path) -> Result<Settings> {
+    let mut env = PROCESS_ENV.get_or_init(process_env).clone();
+    for item in dotenvy::from_path_iter(path).context("failed to load env file")? {
+        let (name, value) = item.context("failed to parse env file")?;
+        env.insert(name, value);
+    }
+    read_valid_settings(&env)
+}
+
+fn process_env() -> Env {
+    std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
+
+/// Checks settings consistency and returns the list of all problems found.
+///
+/// Only files read by the bot itself are checked, paths passed to ansible
+/// are resolved by it.
+pub fn validate_settings(s: &Settings) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let token_id = s.bot_token.split_once(':').map(|(id, _)| id);
+    if !matches!(token_id, Some(id) if !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())) {
+        errors.push(format!(
+            "{PREFIX}_BOT_TOKEN must have the `<bot_id>:<secret>` format"
+        ));
+    }
+
+    let mut rpc_urls = s.rpc_urls.iter().collect::<Vec<_>>();
+    rpc_urls.sort_unstable();
+    for (network, url) in rpc_urls {
+        if let Err(e) = reqwest::Url::parse(url) {
+            errors.push(format!(
+                "{PREFIX}_RPC_URLS has an invalid url for `{network}`: {e}"
+            ));
+        }
+    }
+
+    let mut metrics_urls = s.metrics_urls.iter().collect::<Vec<_>>();
+    metrics_urls.sort_unstable();
+    for (network, url) in metrics_urls {
+        if let Err(e) = reqwest::Url::parse(url) {
+            errors.push(format!(
+                "{PREFIX}_METRICS_URLS has an invalid url for `{network}`: {e}"
+            ));
+        }
+    }
+
+    if !s.rpc_urls.contains_key(&s.default_network) {
+        errors.push(format!(
+            "default network `{}` is missing in {PREFIX}_RPC_URLS",
+            s.default_network
+        ));
+    }
+    if !s.inventory_files.contains_key(&s.default_network) {
+        errors.push(format!(
+            "default network `{}` is missing in {PREFIX}_INVENTORY_FILES",
+            s.default_network
+        ));
+    }
 
-    Ok(Settings {
-        bot_token: get_env("BOT_TOKEN")?,
-        rpc_urls: get_env::<List<Named<String>>>("RPC_URLS")?.into_dict(),
-        default_network: get_env("DEFAULT_NETWORK")?,
-        inventory_files: get_env::<List<Named<String>>>("INVENTORY_FILES")?.into_dict(),
-        ansible_config_file: get_env("ANSIBLE_CONFIG_FILE")?,
-        node_config_file: get_env("NODE_CONFIG_FILE")?,
-        logger_config_file: get_env("LOGGER_CONFIG_FILE")?,
-        zerostate_file: get_env("ZEROSTATE_FILE")?,
-        github_token: get_env("GITHUB_TOKEN")?,
-        reset_playbook: get_env("RESET_PLAYBOOK")?,
-        setup_playbook: get_env("SETUP_PLAYBOOK")?,
-        allowed_groups: get_env::<List<i64>>("ALLOWED_GROUPS")?.0,
-        authentication_enabled: get_env("AUTHENTICATION_ENABLED")?,
-        state_file: get_env("STATE_FILE")?,
-    })
+    let mut timeouts = s.ansible_timeouts.iter().collect::<Vec<_>>();
+    timeouts.sort_unstable();
+    for (network, timeout) in timeouts {
+        if !s.inventory_files.contains_key(network) {
+            errors.push(format!(
+                "{PREFIX}_ANSIBLE_TIMEOUTS has an unknown network `{network}`"
+            ));
+        }
+        if *timeout == 0 {
+            errors.push(format!(
+                "{PREFIX}_ANSIBLE_TIMEOUTS must be positive for `{network}`"
+            ));
+        }
+    }
+
+    let files = [
+        ("NODE_CONFIG_FILE", Some(&s.node_config_file)),
+        (
+            "NODE_CONFIG_DEFAULTS_FILE",
+            s.node_config_defaults_file.as_ref(),
+        ),
+        ("LOGGER_CONFIG_FILE", Some(&s.logger_config_file)),
+        ("ZEROSTATE_FILE", Some(&s.zerostate_file)),
+        ("CONFIG_VALIDATORS_FILE", s.config_validators_file.as_ref()),
+        (
+            "POST_RESET_CHECK_SCRIPT",
+            s.post_reset_check_script.as_ref(),
+        ),
+        (
+            "ANSIBLE_VAULT_PASSWORD_FILE",
+            s.ansible_vault_password_file.as_ref(),
+        ),
+    ];
+    for (name, path) in files {
+        if let Some(path) = path {
+            if !Path::new(path).is_file() {
+                errors.push(format!("{PREFIX}_{name} file not found: {path}"));
+            }
+        }
+    }
+
+    let mut templates = s.workspace_templates.iter().collect::<Vec<_>>();
+    templates.sort_unstable();
+    for (name, path) in templates {
+        if !Path::new(path).is_file() {
+            errors.push(format!(
+                "{PREFIX}_WORKSPACE_TEMPLATES file not found for `{name}`: {path}"
+            ));
+        }
+    }
+
+    if s.authentication_enabled && s.allowed_groups.is_empty() {
+        errors.push(format!(
+            "{PREFIX}_ALLOWED_GROUPS must not be empty when authentication is enabled"
+        ));
+    }
+
+    errors
+}
+
+/// Reads and validates settings, all read and validation errors are reported at once.
+fn read_valid_settings(env: &Env) -> Result<Settings> {
+    let mut reader = EnvReader::new(env);
+    let settings = read_settings(&mut reader);
+    let mut errors = reader.errors;
+    errors.extend(validate_settings(&settings));
+    anyhow::ensure!(
+        errors.is_empty(),
+        "invalid settings:\n- {}",
+        errors.join("\n- ")
+    );
+    Ok(settings)
+}
+
+/// Polls the env file and publishes reloaded settings on every change.
+pub async fn watch_settings(path: PathBuf, tx: watch::Sender<Settings>) {
+    const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+    async fn modified_at(path: &Path) -> Option<SystemTime> {
+        let metadata = tokio::fs::metadata(path).await.ok()?;
+        metadata.modified().ok()
+    }
+
+    let mut last_modified = modified_at(&path).await;
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let modified = modified_at(&path).await;
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        let mut settings = match reload_settings(&path) {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::error!("failed to reload settings: {e:?}");
+                continue;
+            }
+        };
+
+        {
+            let current = tx.borrow();
+            if settings.bot_token != current.bot_token {
+                tracing::warn!("bot token changed, restart is required to apply it");
+                settings.bot_token.clone_from(&current.bot_token);
+            }
+            if settings.rpc_urls != current.rpc_urls {
+                tracing::warn!("RPC urls changed, restart or /setrpcurl is required to apply them");
+                settings.rpc_urls.clone_from(&current.rpc_urls);
+            }
+            if settings.github_tokens != current.github_tokens
+                || settings.github_api_url != current.github_api_url
+            {
+                tracing::warn!("GitHub settings changed, restart is required to apply them");
+                settings.github_tokens.clone_from(&current.github_tokens);
+                settings.github_api_url.clone_from(&current.github_api_url);
+            }
+            if settings.ansible_timeouts != current.ansible_timeouts {
+                tracing::warn!("ansible timeouts changed, restart is required to apply them");
+                settings
+                    .ansible_timeouts
+                    .clone_from(&current.ansible_timeouts);
+            }
+        }
+
+        tx.send_replace(settings);
+        tracing::info!("settings reloaded");
+    }
+}
+
+fn read_settings(env: &mut EnvReader<'_>) -> Settings {
+    Settings {
+        bot_token: env.get("BOT_TOKEN"),
+        rpc_urls: env.get::<List<Named<String>>>("RPC_URLS").into_dict(),
+        metrics_urls: env
+            .get_opt::<List<Named<String>>>("METRICS_URLS")
+            .map(List::into_dict)
+            .unwrap_or_default(),
+        default_network: env.get("DEFAULT_NETWORK"),
+        inventory_files: env
+            .get::<List<Named<String>>>("INVENTORY_FILES")
+            .into_dict(),
+        ansible_config_files: env
+            .get_opt::<List<Named<String>>>("ANSIBLE_CONFIG_FILES")
+            .map(List::into_dict)
+            .unwrap_or_default(),
+        default_ansible_config_file: match env.get_opt("DEFAULT_ANSIBLE_CONFIG_FILE") {
+            Some(path) => path,
+            // Fallback to the legacy single config for all networks
+            None => env.get("ANSIBLE_CONFIG_FILE"),
+        },
+        ansible_timeouts: env
+            .get_opt::<List<Named<u64>>>("ANSIBLE_TIMEOUTS")
+            .map(List::into_dict)
+            .unwrap_or_default(),
+        node_config_file: env.get("NODE_CONFIG_FILE"),
+        node_config_defaults_file: env.get_opt("NODE_CONFIG_DEFAULTS_FILE"),
+        logger_config_file: env.get("LOGGER_CONFIG_FILE"),
+        zerostate_file: env.get("ZEROSTATE_FILE"),
+        github_tokens: env.get::<List<String>>("GITHUB_TOKENS").0,
+        github_api_url: env.get_opt("GITHUB_API_URL"),
+        github_cache_size: env.get_opt("GITHUB_CACHE_SIZE").unwrap_or(100),
+        github_cache_ttl_secs: env.get_opt("GITHUB_CACHE_TTL_SECS").unwrap_or(3600),
+        reset_playbook: env.get("RESET_PLAYBOOK"),
+        setup_playbook: env.get("SETUP_PLAYBOOK"),
+        post_reset_check_script: env.get_opt("POST_RESET_CHECK_SCRIPT"),
+        allowed_playbooks: env
+            .get_opt::<List<Named<String>>>("ALLOWED_PLAYBOOKS")
+            .map(List::into_dict)
+            .unwrap_or_default(),
+        workspace_templates: env
+            .get_opt::<List<Named<String>>>("WORKSPACE_TEMPLATES")
+            .map(List::into_dict)
+            .unwrap_or_default(),
+        ansible_vault_password_file: env.get_opt("ANSIBLE_VAULT_PASSWORD_FILE"),
+        ansible_vault_password: env.get_opt("ANSIBLE_VAULT_PASSWORD"),
+        allowed_groups: env.get::<List<i64>>("ALLOWED_GROUPS").0,
+        production_networks: env
+            .get_opt::<List<String>>("PRODUCTION_NETWORKS")
+            .map(|list| list.0)
+            .unwrap_or_default(),
+        authentication_enabled: env.get("AUTHENTICATION_ENABLED"),
+        state_file: env.get("STATE_FILE"),
+        skip_inventory_validation: env.get_opt("SKIP_INVENTORY_VALIDATION").unwrap_or(false),
+        require_all_inventories: env.get_opt("REQUIRE_ALL_INVENTORIES").unwrap_or(true),
+        github_commit_status_enabled: env.get_opt("GITHUB_COMMIT_STATUS_ENABLED").unwrap_or(false),
+        config_validators_file: env.get_opt("CONFIG_VALIDATORS_FILE"),
+        state_backup_dir: env.get_opt("STATE_BACKUP_DIR"),
+        state_backup_max_count: env.get_opt("STATE_BACKUP_MAX_COUNT").unwrap_or(50),
+        compact_max_history: env.get_opt("COMPACT_MAX_HISTORY").unwrap_or(100),
+        config_env_override_prefix: env.get_opt("CONFIG_ENV_OVERRIDE_PREFIX"),
+        config_schema_dir: env.get_opt("CONFIG_SCHEMA_DIR"),
+        giver_address: env.get_opt("GIVER_ADDRESS"),
+        giver_private_key: env.get_opt("GIVER_PRIVATE_KEY"),
+        min_giver_balance_tokens: env
+            .get_opt("MIN_GIVER_BALANCE_TOKENS")
+            .unwrap_or(1_000_000_000_000),
+        giver_poll_interval_secs: env.get_opt("GIVER_POLL_INTERVAL_SECS").unwrap_or(300),
+        network_poll_interval_secs: env.get_opt("NETWORK_POLL_INTERVAL_SECS").unwrap_or(60),
+        state_export_key: env.get_opt("STATE_EXPORT_KEY"),
+        jrpc_log_file: env.get_opt("JRPC_LOG_FILE"),
+    }
 }
 
 struct List<T>(Vec<T>);
 
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
 impl<T: FromStr> FromStr for List<T> {
     type Err = T::Err;
 
@@ -91,13 +401,160 @@ where
     }
 }
 
-fn get_env<T: FromStr<Err: Into<anyhow::Error>>>(name: &str) -> Result<T> {
+/// Reads settings variables, errors are collected instead of returned
+/// and the value defaults are used in their place.
+struct EnvReader<'a> {
+    env: &'a Env,
+    errors: Vec<String>,
+}
+
+impl<'a> EnvReader<'a> {
+    fn new(env: &'a Env) -> Self {
+        Self {
+            env,
+            errors: Vec::new(),
+        }
+    }
+
+    fn get<T: FromStr<Err: Into<anyhow::Error>> + Default>(&mut self, name: &str) -> T {
+        get_env(self.env, name).unwrap_or_else(|e| {
+            self.errors.push(format!("{e:#}"));
+            T::default()
+        })
+    }
+
+    fn get_opt<T: FromStr<Err: Into<anyhow::Error>>>(&mut self, name: &str) -> Option<T> {
+        get_env_opt(self.env, name).unwrap_or_else(|e| {
+            self.errors.push(format!("{e:#}"));
+            None
+        })
+    }
+}
+
+fn get_env<T: FromStr<Err: Into<anyhow::Error>>>(env: &Env, name: &str) -> Result<T> {
     let key = format!("{PREFIX}_{name}");
-    let value = std::env::var(&key).with_context(|| format!("{key} not set in .env"))?;
+    let value = env
+        .get(&key)
+        .with_context(|| format!("{key} not set in .env"))?;
     value
         .parse()
         .map_err(Into::into)
         .with_context(|| format!("Failed to parse {key}"))
 }
 
+fn get_env_opt<T: FromStr<Err: Into<anyhow::Error>>>(env: &Env, name: &str) -> Result<Option<T>> {
+    let key = format!("{PREFIX}_{name}");
+    match env.get(&key) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(Into::into)
+            .with_context(|| format!("Failed to parse {key}")),
+        None => Ok(None),
+    }
+}
+
+/// Whether the variable is one of the bot settings. The `SECRET_*` names
+/// under the settings prefix are left for config env overrides.
+pub fn is_settings_var(name: &str) -> bool {
+    name.strip_prefix(PREFIX)
+        .and_then(|name| name.strip_prefix('_'))
+        .is_some_and(|name| !name.starts_with("SECRET_"))
+}
+
 const PREFIX: &str = "TYCHONET";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_env(vars: &[(&str, &str)]) -> Env {
+        vars.iter()
+            .map(|(name, value)| (format!("{PREFIX}_{name}"), (*value).to_owned()))
+            .collect()
+    }
+
+    fn valid_env() -> Env {
+        make_env(&[
+            ("BOT_TOKEN", "123:secret"),
+            ("RPC_URLS", "[devnet1=http://127.0.0.1:8080]"),
+            ("DEFAULT_NETWORK", "devnet1"),
+            ("INVENTORY_FILES", "[devnet1=inventory.yaml]"),
+            ("DEFAULT_ANSIBLE_CONFIG_FILE", "ansible.cfg"),
+            ("NODE_CONFIG_FILE", "Cargo.toml"),
+            ("LOGGER_CONFIG_FILE", "Cargo.toml"),
+            ("ZEROSTATE_FILE", "Cargo.toml"),
+            ("GITHUB_TOKENS", "[token]"),
+            ("RESET_PLAYBOOK", "reset.yaml"),
+            ("SETUP_PLAYBOOK", "setup.yaml"),
+            ("ALLOWED_GROUPS", "[-100]"),
+            ("AUTHENTICATION_ENABLED", "true"),
+            ("STATE_FILE", "state.json"),
+        ])
+    }
+
+    #[test]
+    fn read_settings_collects_all_errors() {
+        let env = make_env(&[
+            ("BOT_TOKEN", "123:secret"),
+            ("ALLOWED_GROUPS", "[a]"),
+            ("AUTHENTICATION_ENABLED", "maybe"),
+            ("GITHUB_CACHE_SIZE", "-1"),
+        ]);
+        let mut reader = EnvReader::new(&env);
+        read_settings(&mut reader);
+        assert_eq!(
+            reader.errors,
+            [
+                "TYCHONET_RPC_URLS not set in .env",
+                "TYCHONET_DEFAULT_NETWORK not set in .env",
+                "TYCHONET_INVENTORY_FILES not set in .env",
+                "TYCHONET_ANSIBLE_CONFIG_FILE not set in .env",
+                "TYCHONET_NODE_CONFIG_FILE not set in .env",
+                "TYCHONET_LOGGER_CONFIG_FILE not set in .env",
+                "TYCHONET_ZEROSTATE_FILE not set in .env",
+                "TYCHONET_GITHUB_TOKENS not set in .env",
+                "Failed to parse TYCHONET_GITHUB_CACHE_SIZE: invalid digit found in string",
+                "TYCHONET_RESET_PLAYBOOK not set in .env",
+                "TYCHONET_SETUP_PLAYBOOK not set in .env",
+                "Failed to parse TYCHONET_ALLOWED_GROUPS: invalid digit found in string",
+                "Failed to parse TYCHONET_AUTHENTICATION_ENABLED: \
+                provided string was not `true` or `false`",
+                "TYCHONET_STATE_FILE not set in .env",
+            ]
+        );
+
+        let e = read_valid_settings(&env).unwrap_err().to_string();
+        assert!(e.contains("TYCHONET_STATE_FILE not set"), "{e}");
+        assert!(!e.contains("TYCHONET_BOT_TOKEN"), "{e}");
+    }
+
+    #[test]
+    fn validate_settings_reports_all_problems() {
+        let mut settings = read_valid_settings(&valid_env()).unwrap();
+        assert!(validate_settings(&settings).is_empty());
+
+        settings.bot_token = "secret".to_owned();
+        settings
+            .rpc_urls
+            .insert("bad".to_owned(), "not a url".to_owned());
+        settings.default_network = "devnet2".to_owned();
+        settings.ansible_timeouts.insert("unknown".to_owned(), 0);
+        settings.logger_config_file = "missing.json".to_owned();
+        settings.allowed_groups.clear();
+
+        assert_eq!(
+            validate_settings(&settings),
+            [
+                "TYCHONET_BOT_TOKEN must have the `<bot_id>:<secret>` format",
+                "TYCHONET_RPC_URLS has an invalid url for `bad`: relative URL without a base",
+                "default network `devnet2` is missing in TYCHONET_RPC_URLS",
+                "default network `devnet2` is missing in TYCHONET_INVENTORY_FILES",
+                "TYCHONET_ANSIBLE_TIMEOUTS has an unknown network `unknown`",
+                "TYCHONET_ANSIBLE_TIMEOUTS must be positive for `unknown`",
+                "TYCHONET_LOGGER_CONFIG_FILE file not found: missing.json",
+                "TYCHONET_ALLOWED_GROUPS must not be empty when authentication is enabled",
+            ]
+        );
+    }
+}
@@ -3,58 +3,116 @@ use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
 use anyhow::{Context, Result};
+use everscale_types::cell::{CellBuilder, HashBytes};
 use everscale_types::models::{AccountState, AccountStatus, StdAddr};
+use everscale_types::num::Tokens;
+use futures::stream::{FuturesUnordered, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use teloxide::net::Download;
 use teloxide::prelude::*;
 use teloxide::requests::{JsonRequest, MultipartRequest};
-use teloxide::types::{ChatId, MessageId, ReplyParameters, ThreadId};
+use teloxide::types::{
+    ChatId, InlineKeyboardButton, InlineKeyboardMarkup, MessageId, ReplyParameters, ThreadId,
+    UserId,
+};
+use teloxide::utils::command::BotCommands;
+use tokio::sync::watch;
 use tokio::task::AbortHandle;
 
-use crate::commands::{Currency, DecimalTokens};
-use crate::config::{Config, ConfigDiff};
-use crate::github_client::GithubClient;
+use crate::commands::{Command, Currency, DecimalTokens};
+use crate::config::{
+    json_type_name, parse_config_value_path, to_toml_display, value_matcher, Config, ConfigDiff,
+    IntegerRange, PathSegment, TypeMismatch,
+};
+use crate::github_client::{CiStatus, CommitStatusState, CompareResult, GithubClient, WorkflowRun};
+use crate::giver::{self, GiverWallet};
 use crate::jrpc_client;
-use crate::jrpc_client::{JrpcClient, StateTimings};
-use crate::settings::Settings;
+use crate::jrpc_client::{ElectionStatus, JrpcClient, ShardClientInfo, StateTimings};
+use crate::jrpc_log::JrpcLogger;
+use crate::settings::{self, Settings};
 use crate::util::{
-    now_sec, Emoji, LinkPreviewOptions, ReactionType, SendMessageExt, SetMessageReaction,
-    WithLinkPreview, WithLinkPreviewSetters,
+    now_sec, truncate_middle, Emoji, LinkPreviewOptions, ReactionType, SendMessageExt,
+    SetMessageReaction, WithLinkPreview, WithLinkPreviewSetters,
 };
 
 const DEFAULT_BRANCH: &str = "master";
 
 struct NetworkDescr {
-    jrpc_client: JrpcClient,
+    /// Replaced by `/setrpcurl` without restarting the bot.
+    jrpc_client: RwLock<JrpcClient>,
     inventory: String,
+    ansible_config: String,
+    ansible_timeout_secs: Option<u64>,
     reset_running: AtomicBool,
+    reset_started_at: Mutex<Option<Instant>>,
+}
+
+impl NetworkDescr {
+    fn jrpc_client(&self) -> JrpcClient {
+        self.jrpc_client.read().unwrap().clone()
+    }
 }
 
 pub struct State {
-    github_client: GithubClient,
+    /// Client for the repository from settings.
+    default_github: GithubClient,
+    current_github: RwLock<GithubClient>,
+    github_commit_status_enabled: bool,
     default_network: String,
     networks: HashMap<String, NetworkDescr>,
-    ansible_config_file: String,
+    unhealthy_networks: Mutex<HashSet<String>>,
+    /// Chat threads where networks were last managed, used to route alerts.
+    network_threads: Mutex<HashMap<String, (ChatId, Option<ThreadId>)>>,
+    /// Unix timestamp until which health alerts are not sent,
+    /// `u64::MAX` if paused until `/resume`. Not persisted on purpose.
+    monitoring_paused_until: Mutex<Option<u64>>,
+    jrpc_logger: Option<JrpcLogger>,
     node_config_file: String,
     logger_config_file: String,
     zerostate_file: String,
-    reset_playbook: String,
-    setup_playbook: String,
-    allowed_groups: HashSet<i64>,
-    authentication_enabled: bool,
+    node_config_defaults: Option<serde_json::Value>,
+    allowed_playbooks: HashMap<String, String>,
+    ansible_vault: Option<AnsibleVault>,
+    settings: watch::Receiver<Settings>,
+    config_validators: Vec<ConfigValidatorRule>,
+    giver: Option<GiverWallet>,
+    state_export_cipher: Option<StateCipher>,
     state_file: Mutex<StateFile>,
     unfreeze_notifies: Mutex<HashMap<String, AbortHandle>>,
+    watches: Mutex<HashMap<ChatId, AbortHandle>>,
+    pending_inputs: Mutex<HashMap<(ChatId, UserId), PendingInput>>,
+    pending_imports: Mutex<HashMap<(ChatId, UserId), StateFileData>>,
+    sent_replies: Mutex<SentReplies>,
+    shutdown: ShutdownToken,
 }
 
 impl State {
-    pub async fn new(bot: Bot, settings: &Settings) -> Result<Arc<Self>> {
-        let github_client = GithubClient::new(&settings.github_token, "broxus", "tycho")?;
+    pub async fn new(bot: Bot, settings_rx: watch::Receiver<Settings>) -> Result<Arc<Self>> {
+        let settings = settings_rx.borrow().clone();
+        let settings = &settings;
+
+        let github_client = GithubClient::new(
+            &settings.github_tokens,
+            settings.github_api_url.as_deref(),
+            "broxus",
+            "tycho",
+        )?
+        .with_commit_cache(
+            settings.github_cache_size,
+            Duration::from_secs(settings.github_cache_ttl_secs),
+        );
 
         let mut state_file = StateFile::load(&settings.state_file)?;
+        if let Some(dir) = &settings.state_backup_dir {
+            state_file = state_file.with_backups(dir, settings.state_backup_max_count);
+        }
         if state_file.latest_data.last_commit_info.is_none() {
             let latest_commit = github_client.get_commit_sha(DEFAULT_BRANCH).await?;
             let commit_info = github_client.get_commit_info(&latest_commit).await?;
@@ -63,6 +121,7 @@ impl State {
                 html_url: commit_info.html_url,
                 message: commit_info.message,
                 branches: vec![DEFAULT_BRANCH.to_owned()],
+                ci_status: None,
             });
             state_file.save()?;
         }
@@ -82,41 +141,139 @@ impl State {
             settings.default_network
         );
 
+        let jrpc_logger = settings
+            .jrpc_log_file
+            .as_ref()
+            .map(|path| JrpcLogger::spawn(PathBuf::from(path)));
+
         let networks = settings
             .inventory_files
             .iter()
             .map(|(network, inventory)| {
-                let Some(jrpc_url) = settings.rpc_urls.get(network) else {
+                let jrpc_url = state_file
+                    .latest_data
+                    .rpc_url_overrides
+                    .get(network)
+                    .or_else(|| settings.rpc_urls.get(network));
+                let Some(jrpc_url) = jrpc_url else {
                     anyhow::bail!("no JRPC url found for network `{network}`");
                 };
-                let jrpc_client = JrpcClient::new(jrpc_url)
+                let jrpc_client = JrpcClient::new(jrpc_url, jrpc_logger.clone())
                     .with_context(|| format!("failed to create JRPC client for {network}"))?;
 
+                let ansible_config = settings
+                    .ansible_config_files
+                    .get(network)
+                    .unwrap_or(&settings.default_ansible_config_file)
+                    .clone();
+
                 let descr = NetworkDescr {
-                    jrpc_client,
+                    jrpc_client: RwLock::new(jrpc_client),
                     inventory: inventory.clone(),
+                    ansible_config,
+                    ansible_timeout_secs: settings.ansible_timeouts.get(network).copied(),
                     reset_running: AtomicBool::new(false),
+                    reset_started_at: Mutex::new(None),
                 };
                 Ok::<_, anyhow::Error>((network.clone(), descr))
             })
             .collect::<Result<HashMap<_, _>>>()?;
 
+        let mut config_validators = ConfigValidatorRule::defaults();
+        if let Some(path) = &settings.config_validators_file {
+            config_validators.extend(ConfigValidatorRule::load(path)?);
+        }
+
+        let giver = match (&settings.giver_address, &settings.giver_private_key) {
+            (Some(address), Some(private_key)) => Some(GiverWallet::new(address, private_key)?),
+            (None, None) => None,
+            _ => anyhow::bail!("both giver address and private key must be specified"),
+        };
+
+        let ansible_vault = match (
+            &settings.ansible_vault_password_file,
+            &settings.ansible_vault_password,
+        ) {
+            (Some(path), None) => Some(AnsibleVault::PasswordFile(PathBuf::from(path))),
+            (None, Some(password)) => Some(AnsibleVault::Password(password.clone())),
+            (None, None) => None,
+            _ => anyhow::bail!("only one of vault password file or password must be specified"),
+        };
+
+        let node_config_defaults = settings
+            .node_config_defaults_file
+            .as_deref()
+            .map(|path| {
+                let data =
+                    std::fs::read_to_string(path).context("failed to read node config defaults")?;
+                serde_json::from_str::<serde_json::Value>(&data)
+                    .context("failed to parse node config defaults")
+            })
+            .transpose()?;
+
+        let state_export_cipher = settings
+            .state_export_key
+            .as_deref()
+            .map(StateCipher::new)
+            .transpose()?;
+
         let state = Arc::new(Self {
-            github_client,
+            current_github: RwLock::new(github_client.clone()),
+            default_github: github_client,
+            github_commit_status_enabled: settings.github_commit_status_enabled,
             default_network: settings.default_network.clone(),
             networks,
-            ansible_config_file: settings.ansible_config_file.clone(),
+            unhealthy_networks: Mutex::new(Default::default()),
+            network_threads: Mutex::new(Default::default()),
+            monitoring_paused_until: Mutex::new(None),
+            jrpc_logger,
             node_config_file: settings.node_config_file.clone(),
             logger_config_file: settings.logger_config_file.clone(),
             zerostate_file: settings.zerostate_file.clone(),
-            reset_playbook: settings.reset_playbook.clone(),
-            setup_playbook: settings.setup_playbook.clone(),
-            allowed_groups: settings.allowed_groups.iter().copied().collect(),
-            authentication_enabled: settings.authentication_enabled,
+            node_config_defaults,
+            allowed_playbooks: settings.allowed_playbooks.clone(),
+            ansible_vault,
+            settings: settings_rx,
+            config_validators,
+            giver,
+            state_export_cipher,
             state_file: Mutex::new(state_file),
             unfreeze_notifies: Mutex::new(Default::default()),
+            watches: Mutex::new(Default::default()),
+            pending_inputs: Mutex::new(Default::default()),
+            pending_imports: Mutex::new(Default::default()),
+            sent_replies: Mutex::new(Default::default()),
+            shutdown: ShutdownToken::new(),
         });
 
+        state.check_networks(None).await?;
+
+        match state.get_wallet_status().await {
+            Ok(Reply::WalletStatus {
+                address,
+                balance,
+                is_funded: false,
+            }) => {
+                tracing::warn!("giver wallet {address} balance is low: {}", balance.0);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("failed to check giver wallet: {e:?}"),
+        }
+
+        if state.giver.is_some() {
+            tokio::spawn(state.clone().giver_monitor_task(bot.clone()));
+        }
+        tokio::spawn(state.clone().network_monitor_task(bot.clone()));
+
+        if !settings.skip_inventory_validation {
+            if let Err(e) = state.validate_inventories().await {
+                if settings.require_all_inventories {
+                    return Err(e);
+                }
+                tracing::error!("inventory validation failed: {e:?}");
+            }
+        }
+
         for (network, at) in unfreeze_timestamps {
             let duration = Duration::from_secs(at.saturating_sub(now_sec()));
             let task = tokio::spawn(state.clone().unfreeze_task(
@@ -136,354 +293,644 @@ impl State {
         Ok(state)
     }
 
-    pub async fn get_status(&self) -> Result<Reply> {
-        self.get_current_jrpc_client()?
-            .get_timings()
-            .await
-            .map(Reply::Timings)
-            .context("Failed to get status")
+    pub fn shutdown_token(&self) -> &ShutdownToken {
+        &self.shutdown
     }
 
-    pub async fn get_account(&self, address: &StdAddr) -> Result<Reply> {
-        let res = self.get_current_jrpc_client()?.get_account(address).await?;
-        let reply = match res {
-            jrpc_client::AccountStateResponse::NotExists { .. } => Reply::Account {
-                address: address.clone(),
-                balance: Default::default(),
-                status: AccountStatus::NotExists,
-            },
-            jrpc_client::AccountStateResponse::Exists { account, .. } => Reply::Account {
-                address: address.clone(),
-                balance: DecimalTokens(account.balance.tokens),
-                status: match account.state {
-                    AccountState::Uninit => AccountStatus::Uninit,
-                    AccountState::Active { .. } => AccountStatus::Active,
-                    AccountState::Frozen { .. } => AccountStatus::Frozen,
-                },
-            },
-            jrpc_client::AccountStateResponse::Unchanged { .. } => {
-                anyhow::bail!("Unexpected response")
-            }
-        };
-        Ok(reply)
+    /// Waits until no network has a reset in progress.
+    pub async fn wait_for_resets(&self) {
+        const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+        while self
+            .networks
+            .values()
+            .any(|descr| descr.reset_running.load(Ordering::Relaxed))
+        {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
     }
 
-    pub async fn get_param(&self, param: i32) -> Result<Reply> {
-        let res = self.get_current_jrpc_client()?.get_config().await?;
-        let value = serde_json::to_value(res.config.params)?;
+    pub async fn validate_inventories(&self) -> Result<()> {
+        let mut networks = self.networks.iter().collect::<Vec<_>>();
+        networks.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut failed = Vec::new();
+        for (network, descr) in networks {
+            if let Err(e) = self
+                .run_ansible_inventory_list(&descr.inventory, &descr.ansible_config)
+                .await
+            {
+                tracing::error!("invalid inventory for network `{network}`: {e:?}");
+                failed.push(format!("{network} ({}): {e}", descr.inventory));
+            }
+        }
 
-        Ok(Reply::ConfigParam {
-            global_id: res.global_id,
-            seqno: res.seqno,
-            value,
-            param,
-        })
+        anyhow::ensure!(
+            failed.is_empty(),
+            "invalid inventories:\n{}",
+            failed.join("\n")
+        );
+        Ok(())
     }
 
-    pub fn freeze(self: &Arc<Self>, bot: &Bot, msg: &Message, expr: &str) -> Result<Reply> {
+    pub async fn recheck_network(&self, expr: &str) -> Result<Reply> {
+        let name = expr.trim();
+        let name = (!name.is_empty()).then_some(name);
+        self.check_networks(name).await.map(Reply::NetworkHealth)
+    }
+
+    /// Replaces the JRPC client of the network once the new endpoint responds.
+    /// The url is stored in the state so it is used after restart.
+    pub async fn set_rpc_url(&self, msg: &Message, network: &str, url: &str) -> Result<Reply> {
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
         if !self.check_auth(msg) {
             return Ok(Reply::AccessDenied);
         }
 
-        let (duration, reason) = match expr.split_once(':') {
-            Some((duration, reason)) => {
-                let duration = humantime::parse_duration(duration.trim())?;
-                (duration, Some(reason.trim().to_owned()))
-            }
-            None => {
-                let duration = humantime::parse_duration(expr.trim())?;
-                (duration, None)
-            }
+        let descr = self
+            .networks
+            .get(network)
+            .with_context(|| format!("no inventory found for the network `{network}`"))?;
+
+        let url = reqwest::Url::parse(url).with_context(|| format!("invalid url `{url}`"))?;
+        anyhow::ensure!(
+            matches!(url.scheme(), "http" | "https"),
+            "unsupported url scheme `{}`",
+            url.scheme()
+        );
+
+        let client = JrpcClient::new(url.clone(), self.jrpc_logger.clone())
+            .with_context(|| format!("failed to create JRPC client for {network}"))?;
+        let timings = match tokio::time::timeout(TIMEOUT, client.get_timings()).await {
+            Ok(res) => res.with_context(|| format!("`{url}` is unreachable"))?,
+            Err(_) => anyhow::bail!(
+                "`{url}` timed out after {}",
+                humantime::format_duration(TIMEOUT)
+            ),
         };
-        // anyhow::ensure!(
-        //     duration <= Duration::from_secs(86400),
-        //     "Cannot freeze for more than 24 hours"
-        // );
+
+        *descr.jrpc_client.write().unwrap() = client;
 
         let mut state_file = self.state_file.lock().unwrap();
-        let network = state_file
+        state_file
             .latest_data
-            .current_network_name(&self.default_network)
-            .to_owned();
-
-        if let Some(frozen) = state_file.latest_data.reset_frozen.get(&network) {
-            return Ok(Reply::ResetFrozen(frozen.clone()));
-        }
-
-        let timestamp_until = now_sec() + duration.as_secs();
-        state_file.latest_data.reset_frozen.insert(
-            network.clone(),
-            ResetFrozen {
-                network: network.clone(),
-                reason,
-                timestamp_until,
-                chat_id: msg.chat.id,
-                message_id: msg.id,
-                message_thread_id: msg.thread_id,
-            },
-        );
+            .rpc_url_overrides
+            .insert(network.to_owned(), url.to_string());
         state_file.save()?;
 
-        {
-            let mut notify = self.unfreeze_notifies.lock().unwrap();
-            if let Some(notify) = notify.remove(&network) {
-                notify.abort();
+        Ok(Reply::RpcUrlChanged {
+            network: network.to_owned(),
+            url: url.to_string(),
+            seqno: timings.last_mc_block_seqno,
+        })
+    }
+
+    pub async fn check_network(&self, expr: &str) -> Result<Reply> {
+        let network = match expr.trim() {
+            "" => {
+                let state_file = self.state_file.lock().unwrap();
+                state_file
+                    .latest_data
+                    .current_network_name(&self.default_network)
+                    .to_owned()
             }
+            name => name.to_owned(),
+        };
 
-            let task = tokio::spawn(self.clone().unfreeze_task(
-                bot.clone(),
-                network.clone(),
-                duration,
-            ))
-            .abort_handle();
-            notify.insert(network.clone(), task);
+        let report = self.verify_ansible_connectivity(&network).await?;
+        Ok(Reply::AnsibleConnectivity { network, report })
+    }
+
+    /// Pings all inventory hosts of the network with ansible.
+    pub async fn verify_ansible_connectivity(
+        &self,
+        network: &str,
+    ) -> Result<AnsibleConnectivityReport> {
+        const TIMEOUT: Duration = Duration::from_secs(30);
+
+        let descr = self
+            .networks
+            .get(network)
+            .with_context(|| format!("no inventory found for the network `{network}`"))?;
+
+        let vault = self.vault_password_file()?;
+
+        let mut command = tokio::process::Command::new("ansible");
+        command
+            .arg("all")
+            .arg("-i")
+            .arg(&descr.inventory)
+            .arg("-m")
+            .arg("ping")
+            .arg("--one-line");
+        if let Some(vault) = &vault {
+            command.arg("--vault-password-file").arg(vault.path());
         }
 
-        Ok(Reply::Freeze { network })
+        let output = tokio::time::timeout(
+            TIMEOUT,
+            command
+                .env(ANSIBLE_CONFIG_ENV, &descr.ansible_config)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .context("ansible ping timed out")?
+        .context("Failed to execute ansible ping")?;
+
+        // NOTE: Ansible exits with an error if some hosts are unreachable
+        let report =
+            AnsibleConnectivityReport::from_ping_output(&String::from_utf8_lossy(&output.stdout));
+        anyhow::ensure!(
+            output.status.success() || !report.is_empty(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        Ok(report)
     }
 
-    pub fn unfreeze(&self, msg: &Message) -> Result<Reply> {
-        if !self.check_auth(msg) {
-            return Ok(Reply::AccessDenied);
-        }
+    /// Checks JRPC endpoints of the specified (or all) networks and updates
+    /// the set of unhealthy networks. Returns `(network, error)` pairs.
+    async fn check_networks(&self, name: Option<&str>) -> Result<Vec<(String, Option<String>)>> {
+        const TIMEOUT: Duration = Duration::from_secs(10);
 
-        let mut state_file = self.state_file.lock().unwrap();
-        let network = state_file
-            .latest_data
-            .current_network_name(&self.default_network)
-            .to_owned();
+        let mut networks = match name {
+            Some(name) => {
+                let descr = self
+                    .networks
+                    .get(name)
+                    .with_context(|| format!("unknown network `{name}`"))?;
+                vec![(name, descr)]
+            }
+            None => self
+                .networks
+                .iter()
+                .map(|(name, descr)| (name.as_str(), descr))
+                .collect(),
+        };
+        networks.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut result = Vec::with_capacity(networks.len());
+        for (network, descr) in networks {
+            let error = match tokio::time::timeout(TIMEOUT, descr.jrpc_client().get_timings()).await
+            {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some(e.to_string()),
+                Err(_) => Some(format!(
+                    "timed out after {}",
+                    humantime::format_duration(TIMEOUT)
+                )),
+            };
 
-        if state_file
-            .latest_data
-            .reset_frozen
-            .remove(&network)
-            .is_some()
-        {
-            state_file.save()?;
-        }
+            {
+                let mut unhealthy = self.unhealthy_networks.lock().unwrap();
+                match &error {
+                    None => {
+                        unhealthy.remove(network);
+                    }
+                    Some(e) => {
+                        tracing::warn!("network `{network}` is unhealthy: {e}");
+                        unhealthy.insert(network.to_owned());
+                    }
+                }
+            }
 
-        if let Some(notify) = self.unfreeze_notifies.lock().unwrap().remove(&network) {
-            notify.abort();
+            result.push((network.to_owned(), error));
         }
-
-        Ok(Reply::Unfreeze { network })
+        Ok(result)
     }
 
-    pub fn get_saved_commit(&self) -> Result<Reply> {
+    /// Returns the current network name if its JRPC endpoint is unhealthy.
+    pub fn current_unhealthy_network(&self) -> Option<String> {
         let state_file = self.state_file.lock().unwrap();
-        state_file
+        let network = state_file
             .latest_data
-            .last_commit_info
-            .clone()
-            .map(Reply::Commit)
-            .context("no commit info saved")
+            .current_network_name(&self.default_network);
+        self.unhealthy_networks
+            .lock()
+            .unwrap()
+            .contains(network)
+            .then(|| network.to_owned())
     }
 
-    pub fn set_workspace(&self, msg: &Message, expr: &str) -> Result<Reply> {
-        use std::collections::hash_map;
-
+    /// Remembers the thread of the message as the place where the network
+    /// (or the current one if not specified) is managed.
+    pub fn register_network_thread(&self, msg: &Message, network: Option<&str>) {
         if !self.check_auth(msg) {
-            return Ok(Reply::AccessDenied);
+            return;
         }
 
-        let SetWorkspaceParams {
-            workspace,
-            copy_from,
-        } = expr.parse()?;
-
-        let mut state_file = self.state_file.lock().unwrap();
-
-        let prev_workspace = match &copy_from {
-            Some(workspace) if workspace != DEFAULT_WORKSPACE => state_file
-                .latest_data
-                .workspaces
-                .get_mut(workspace)
-                .with_context(|| format!("workspace not found {workspace}"))?,
-            _ => state_file
-                .latest_data
-                .workspaces
-                .entry(DEFAULT_WORKSPACE.to_owned())
-                .or_default(),
-        };
-
-        let compute_prev_source = |object: &Option<JsonObject>| {
-            if object.is_none() {
-                ConfigSource::FromFile
-            } else if copy_from.is_some() {
-                ConfigSource::Copied
-            } else {
-                ConfigSource::Unchanged
+        let network = match network {
+            Some(network) => network.to_owned(),
+            None => {
+                let state_file = self.state_file.lock().unwrap();
+                state_file
+                    .latest_data
+                    .current_network_name(&self.default_network)
+                    .to_owned()
             }
         };
-        let node_source = compute_prev_source(&prev_workspace.node);
-        let logger_source = compute_prev_source(&prev_workspace.logger);
-        let zerostate_source = compute_prev_source(&prev_workspace.zerostate);
+        self.network_threads
+            .lock()
+            .unwrap()
+            .insert(network, (msg.chat.id, msg.thread_id));
+    }
 
-        prev_workspace.preload(
-            &self.node_config_file,
-            &self.logger_config_file,
-            &self.zerostate_file,
-        )?;
-        let prev_workspace = prev_workspace.clone();
+    /// Sends an alert about the network to its registered thread
+    /// or to all allowed groups if there is none.
+    pub async fn send_network_alert(&self, bot: &Bot, network: &str, text: &str) {
+        if self.monitoring_paused_until().is_some() {
+            tracing::info!(network, "health alerts are paused, alert skipped");
+            return;
+        }
 
-        let is_new;
-        let network;
-        match state_file.latest_data.workspaces.entry(workspace.clone()) {
-            hash_map::Entry::Vacant(entry) => {
-                is_new = true;
-                network = prev_workspace.network.clone();
-                entry.insert(prev_workspace);
+        let targets = match self.network_threads.lock().unwrap().get(network) {
+            Some(target) => vec![*target],
+            None => {
+                let settings = self.settings.borrow();
+                settings
+                    .allowed_groups
+                    .iter()
+                    .map(|chat_id| (ChatId(*chat_id), None))
+                    .collect()
             }
-            hash_map::Entry::Occupied(mut entry) => {
-                is_new = false;
-                if copy_from.is_some() {
-                    entry.insert(prev_workspace);
-                }
-                network = entry.get().network.clone();
+        };
+
+        for (chat_id, thread_id) in targets {
+            let mut msg = bot.send_message(chat_id, text);
+            msg.message_thread_id = thread_id;
+            if let Err(e) = msg.markdown().await {
+                tracing::error!("Failed to send `{network}` alert to {chat_id}: {e}");
             }
         }
-
-        state_file.latest_data.current_workspace = Some(workspace);
-        state_file.save()?;
-
-        Ok(Reply::WorkspaceChanged {
-            is_new,
-            network: network.unwrap_or_else(|| self.default_network.clone()),
-            node_source,
-            logger_source,
-            zerostate_source,
-            copy_from,
-        })
     }
 
-    pub fn get_workspace(&self) -> Result<Reply> {
-        let state_file = self.state_file.lock().unwrap();
-
-        let current = state_file
-            .latest_data
-            .current_workspace
-            .clone()
-            .unwrap_or_else(|| DEFAULT_WORKSPACE.to_owned());
-
-        let mut workspaces = Vec::new();
-        if !state_file.latest_data.workspaces.contains_key(&current) {
-            workspaces.push(current.clone());
+    /// Stops sending health alerts for the duration or until `/resume`.
+    pub fn pause_monitoring(&self, msg: &Message, duration: Option<Duration>) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
         }
-        workspaces.extend(state_file.latest_data.workspaces.keys().cloned());
-        workspaces.sort_unstable();
 
-        Ok(Reply::Workspaces {
-            current,
-            workspaces,
-        })
+        let until = match duration {
+            Some(duration) => now_sec().saturating_add(duration.as_secs()),
+            None => u64::MAX,
+        };
+        *self.monitoring_paused_until.lock().unwrap() = Some(until);
+
+        Ok(Reply::MonitoringPaused { duration })
     }
 
-    pub fn delete_workspace(&self, msg: &Message, expr: &str) -> Result<Reply> {
+    pub fn resume_monitoring(&self, msg: &Message) -> Result<Reply> {
         if !self.check_auth(msg) {
             return Ok(Reply::AccessDenied);
         }
 
-        let workspace_name = expr.trim();
-        if workspace_name == DEFAULT_WORKSPACE {
-            anyhow::bail!("cannot remove the default workspace");
-        }
+        let was_paused = self.monitoring_paused_until().is_some();
+        *self.monitoring_paused_until.lock().unwrap() = None;
 
-        let mut state_file = self.state_file.lock().unwrap();
+        Ok(Reply::MonitoringResumed { was_paused })
+    }
 
-        if matches!(
-            &state_file.latest_data.current_workspace,
-            Some(current) if current == workspace_name
-        ) {
-            state_file.latest_data.current_workspace = None;
+    /// Returns the pause deadline, clearing it once it has passed.
+    fn monitoring_paused_until(&self) -> Option<u64> {
+        let mut paused_until = self.monitoring_paused_until.lock().unwrap();
+        if paused_until.is_some_and(|until| until <= now_sec()) {
+            *paused_until = None;
         }
+        *paused_until
+    }
 
-        if state_file
-            .latest_data
-            .workspaces
-            .remove(workspace_name)
-            .is_none()
-        {
-            anyhow::bail!("workspace does not exist: `{workspace_name}`");
-        }
+    pub async fn get_status(&self) -> Result<Reply> {
+        let client = self.get_current_jrpc_client()?;
+        let timings = client.get_timings().await.context("Failed to get status")?;
+
+        // Shard info is optional since older nodes may not support it
+        let shards = match client.get_shard_client_info().await {
+            Ok(shards) => Some(shards),
+            Err(e) => {
+                tracing::warn!("failed to get shard client info: {e:?}");
+                None
+            }
+        };
 
-        state_file.save()?;
-        Ok(Reply::WorkspaceRemoved)
+        Ok(Reply::Timings { timings, shards })
     }
 
-    pub fn get_network(&self) -> Result<Reply> {
-        let state_file = self.state_file.lock().unwrap();
+    /// Returns only the last masterchain block seqno of the current network.
+    pub async fn get_block_seqno(&self) -> Result<u32> {
+        fetch_block_seqno(&self.get_current_jrpc_client()?).await
+    }
 
-        let current = state_file
-            .latest_data
-            .current_network_name(&self.default_network)
-            .to_owned();
+    pub async fn get_shards(&self) -> Result<Reply> {
+        self.get_current_jrpc_client()?
+            .get_shard_client_info()
+            .await
+            .map(Reply::Shards)
+            .context("Failed to get shard client info")
+    }
 
-        let mut networks = self.networks.keys().cloned().collect::<Vec<_>>();
-        networks.sort_unstable();
+    pub async fn get_elections(&self) -> Result<Reply> {
+        self.get_current_jrpc_client()?
+            .get_elections()
+            .await
+            .map(Reply::Elections)
+            .context("Failed to get elections")
+    }
 
-        Ok(Reply::Networks { current, networks })
+    pub async fn get_account(&self, address: &StdAddr) -> Result<Reply> {
+        let jrpc_client = self.get_current_jrpc_client()?;
+        let (balance, status) = query_account(&jrpc_client, address).await?;
+        Ok(Reply::Account {
+            address: address.clone(),
+            balance,
+            status,
+        })
     }
 
-    pub fn set_network(&self, msg: &Message, expr: &str) -> Result<Reply> {
-        if !self.check_auth(msg) {
-            return Ok(Reply::AccessDenied);
+    /// Queries accounts in one batch request. Falls back to concurrent queries
+    /// if the batch fails, so that failed queries are reported per address.
+    pub async fn get_all_accounts(&self, addresses: &[StdAddr]) -> Result<Reply> {
+        let jrpc_client = self.get_current_jrpc_client()?;
+
+        let results = match jrpc_client.get_accounts_batch(addresses).await {
+            Ok(responses) => responses
+                .into_iter()
+                .map(|res| parse_account_state(res).map_err(|e| e.to_string()))
+                .collect(),
+            Err(e) => {
+                tracing::warn!("batch account query failed, retrying one by one: {e:?}");
+                query_accounts_concurrently(&jrpc_client, addresses).await
+            }
+        };
+
+        let accounts = addresses.iter().cloned().zip(results).collect();
+        Ok(Reply::Accounts(accounts))
+    }
+
+    pub async fn get_wallet_status(&self) -> Result<Reply> {
+        let Some(giver) = &self.giver else {
+            return Ok(Reply::WalletNotConfigured);
+        };
+
+        let address = giver.address();
+        let balance = get_giver_balance(&self.get_current_jrpc_client()?, giver).await?;
+
+        Ok(Reply::WalletStatus {
+            address: address.clone(),
+            balance: DecimalTokens(balance),
+            is_funded: balance.into_inner() >= self.settings.borrow().min_giver_balance_tokens,
+        })
+    }
+
+    pub async fn get_account_proof(&self, address: &StdAddr) -> Result<Reply> {
+        let res = self
+            .get_current_jrpc_client()?
+            .get_account_proof(address)
+            .await?;
+
+        Ok(Reply::AccountProof {
+            address: address.clone(),
+            state_hash: *res.account.repr_hash(),
+            proof_hash: *res.proof_boc.repr_hash(),
+        })
+    }
+
+    pub async fn verify_account(&self, address: &StdAddr) -> Result<Reply> {
+        let res = self
+            .get_current_jrpc_client()?
+            .get_account_with_proof(address)
+            .await?;
+
+        Ok(Reply::AccountVerified {
+            address: address.clone(),
+            proof_root_hash: res.proof_root_hash,
+            proof_consistent: res.is_consistent(),
+        })
+    }
+
+    /// Fetches the blockchain config once and extracts all requested params.
+    pub async fn get_params(&self, params: &[i32]) -> Result<Reply> {
+        let res = self.get_current_jrpc_client()?.get_config().await?;
+        let value = serde_json::to_value(res.config.params)?;
+
+        let values = params
+            .iter()
+            .map(|param| {
+                let value = value.get(param.to_string()).cloned();
+                (*param, value.unwrap_or_default())
+            })
+            .collect();
+
+        Ok(Reply::ConfigParams {
+            global_id: res.global_id,
+            seqno: res.seqno,
+            values,
+        })
+    }
+
+    /// Fetches the prometheus metrics of the current network and returns
+    /// the first sample of the metric.
+    pub async fn get_node_metrics(&self, metric_name: &str) -> Result<Reply> {
+        const TIMEOUT: Duration = Duration::from_secs(10);
+
+        let name = metric_name.trim();
+        anyhow::ensure!(!name.is_empty(), "metric name expected");
+
+        let network = {
+            let state_file = self.state_file.lock().unwrap();
+            state_file
+                .latest_data
+                .current_network_name(&self.default_network)
+                .to_owned()
+        };
+        let url = self
+            .settings
+            .borrow()
+            .metrics_urls
+            .get(&network)
+            .cloned()
+            .with_context(|| format!("no metrics url configured for the network `{network}`"))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(TIMEOUT)
+            .build()
+            .context("failed to build http client")?;
+        let text = client
+            .get(&url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .context("failed to fetch metrics")?
+            .text()
+            .await
+            .context("failed to read metrics")?;
+
+        let sample = MetricSample::find(&text, name)
+            .with_context(|| format!("metric `{name}` not found"))?;
+        Ok(Reply::NodeMetric {
+            name: name.to_owned(),
+            value: sample.value,
+            labels: sample.labels,
+        })
+    }
+
+    /// Compares the blockchain config with the one saved before the last reset.
+    pub async fn compare_blockchain_config(&self) -> Result<Reply> {
+        let baseline = {
+            let state_file = self.state_file.lock().unwrap();
+            state_file.latest_data.last_blockchain_config.clone()
+        };
+        let baseline =
+            baseline.context("no blockchain config saved yet, it is saved before each reset")?;
+
+        let descr = self
+            .networks
+            .get(&baseline.network)
+            .with_context(|| format!("unknown network `{}`", baseline.network))?;
+        let res = descr.jrpc_client().get_config().await?;
+        let params = serde_json::to_value(res.config.params)?;
+
+        Ok(Reply::BlockchainConfigChanges {
+            diff: ConfigDiff::from_values(&baseline.params, &params)?,
+            network: baseline.network,
+            baseline_seqno: baseline.seqno,
+            seqno: res.seqno,
+        })
+    }
+
+    /// Saves the blockchain config of the network as a baseline for `/configchanges`.
+    async fn save_blockchain_config(&self, network: &str, client: &JrpcClient) {
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        let res = match tokio::time::timeout(TIMEOUT, client.get_config()).await {
+            Ok(Ok(res)) => res,
+            Ok(Err(e)) => {
+                tracing::warn!(network, "failed to get blockchain config: {e:?}");
+                return;
+            }
+            Err(_) => {
+                tracing::warn!(network, "blockchain config request timed out");
+                return;
+            }
+        };
+        let params = match serde_json::to_value(res.config.params) {
+            Ok(params) => params,
+            Err(e) => {
+                tracing::warn!(network, "failed to serialize blockchain config: {e:?}");
+                return;
+            }
+        };
+
+        let mut state_file = self.state_file.lock().unwrap();
+        state_file.latest_data.last_blockchain_config = Some(BlockchainConfigSnapshot {
+            network: network.to_owned(),
+            seqno: res.seqno,
+            params,
+        });
+        if let Err(e) = state_file.save() {
+            tracing::error!("Failed to save state file: {e}");
         }
+    }
+
+    pub fn watch(self: &Arc<Self>, bot: &Bot, msg: &Message, expr: &str) -> Result<()> {
+        const MIN_INTERVAL: Duration = Duration::from_secs(5);
 
-        let SetNetworkParams { network } = expr.parse()?;
+        let (interval, command) = expr
+            .trim()
+            .split_once(char::is_whitespace)
+            .context("expected an expression: <interval> <command>")?;
+
+        let interval = humantime::parse_duration(interval.trim())?;
         anyhow::ensure!(
-            self.networks.contains_key(&network),
-            "no inventory found for the network `{network}`"
+            interval >= MIN_INTERVAL,
+            "watch interval must be at least {}",
+            humantime::format_duration(MIN_INTERVAL)
         );
 
-        let mut state_file = self.state_file.lock().unwrap();
-        let current_workspace_name = state_file.latest_data.current_workspace_name();
+        let command = Command::parse(command.trim(), "").context("invalid command")?;
+        anyhow::ensure!(
+            matches!(
+                command,
+                Command::Status
+                    | Command::Shards
+                    | Command::Account { .. }
+                    | Command::GetParam { .. }
+                    | Command::GetCommit
+            ),
+            "only `/status`, `/shards`, `/account`, `/getparam` and `/getcommit` can be watched"
+        );
 
-        let current_workspace = state_file
-            .latest_data
-            .workspaces
-            .entry(current_workspace_name.clone())
-            .or_default();
+        let mut watches = self.watches.lock().unwrap();
+        if let Some(prev) = watches.remove(&msg.chat.id) {
+            prev.abort();
+        }
 
-        current_workspace.network = Some(network.clone());
-        state_file.save()?;
+        let task =
+            tokio::spawn(
+                self.clone()
+                    .watch_task(bot.clone(), msg.clone(), interval, command),
+            )
+            .abort_handle();
+        watches.insert(msg.chat.id, task);
 
-        Ok(Reply::WorkspaceChanged {
-            is_new: false,
-            network,
-            node_source: ConfigSource::Unchanged,
-            logger_source: ConfigSource::Unchanged,
-            zerostate_source: ConfigSource::Unchanged,
-            copy_from: None,
-        })
+        Ok(())
     }
 
-    pub fn get_reset_type(&self) -> Result<Reply> {
-        let state_file = self.state_file.lock().unwrap();
-        Ok(Reply::ResetType(state_file.latest_data.reset_type))
+    pub fn unwatch(&self, msg: &Message) -> Result<Reply> {
+        let stopped = match self.watches.lock().unwrap().remove(&msg.chat.id) {
+            Some(task) => {
+                task.abort();
+                true
+            }
+            None => false,
+        };
+        Ok(Reply::Unwatch { stopped })
     }
 
-    pub fn set_reset_type(&self, msg: &Message, expr: &str) -> Result<Reply> {
+    /// Switches GitHub requests to another repository until the restart.
+    ///
+    /// An empty `repo` shows the current one, `default` restores the original.
+    pub fn set_github_repo(&self, msg: &Message, repo: &str) -> Result<Reply> {
         if !self.check_auth(msg) {
             return Ok(Reply::AccessDenied);
         }
 
-        let reset_type = expr.parse()?;
+        let repo = repo.trim();
+        if !repo.is_empty() {
+            let client = if repo == "default" {
+                self.default_github.clone()
+            } else {
+                let (org, repo) = repo
+                    .split_once('/')
+                    .context("expected the `<org>/<repo>` repository")?;
+                self.default_github.with_repo(org.trim(), repo.trim())?
+            };
+            *self.current_github.write().unwrap() = client;
+        }
 
-        let mut state_file = self.state_file.lock().unwrap();
-        state_file.latest_data.reset_type = reset_type;
-        state_file.save()?;
+        let repo = self.github().repo_name().to_owned();
+        let is_default = repo == self.default_github.repo_name();
+        Ok(Reply::GithubRepo { repo, is_default })
+    }
 
-        Ok(Reply::ResetType(reset_type))
+    fn github(&self) -> GithubClient {
+        self.current_github.read().unwrap().clone()
     }
 
-    pub async fn reset_network(&self, bot: Bot, msg: &Message, params: ResetParams) -> Result<()> {
-        struct ResetGuard<'a>(&'a AtomicBool);
+    pub fn get_github_token_status(&self) -> Result<Reply> {
+        Ok(Reply::GithubTokenStatus(self.github().token_status()))
+    }
 
-        impl Drop for ResetGuard<'_> {
-            fn drop(&mut self) {
-                self.0.store(false, Ordering::Relaxed);
-            }
-        }
+    pub async fn give_tokens(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        address: &StdAddr,
+        amount: DecimalTokens,
+    ) -> Result<()> {
+        const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30);
+        const POLL_INTERVAL: Duration = Duration::from_secs(1);
 
         if !self.check_auth(msg) {
             bot.send_message(msg.chat.id, Reply::AccessDenied.to_string())
@@ -492,426 +939,2768 @@ impl State {
             return Ok(());
         }
 
-        let network;
-        let descr;
-        let reset_type;
-        'frozen: {
-            let frozen = {
-                let mut state_file = self.state_file.lock().unwrap();
-                reset_type = params
-                    .reset_type
-                    .unwrap_or(state_file.latest_data.reset_type);
+        let giver = self
+            .giver
+            .as_ref()
+            .context("giver wallet is not configured")?;
+        let jrpc_client = self.get_current_jrpc_client()?;
 
-                network = params.network.clone().unwrap_or_else(|| {
-                    state_file
-                        .latest_data
-                        .current_network_name(&self.default_network)
-                        .to_owned()
-                });
+        let title = format!("💸 Sending {amount} {Currency} to `{address}`");
+        let r = self
+            .begin_long_reply(bot, msg, format!("{title}..."), false)
+            .await?;
 
-                descr = self
-                    .networks
-                    .get(&network)
-                    .with_context(|| format!("no inventory found for the network `{network}`"))?;
+        let seqno = match jrpc_client.get_account(giver.address()).await? {
+            jrpc_client::AccountStateResponse::Exists { account, .. } => {
+                giver::parse_seqno(&account)?
+            }
+            _ => anyhow::bail!("giver wallet does not exist"),
+        };
 
-                let Some(frozen) = state_file.latest_data.reset_frozen.get(&network) else {
-                    state_file.latest_data.apply_workspace_configs(
-                        &self.node_config_file,
-                        &self.logger_config_file,
-                        &self.zerostate_file,
-                    )?;
-                    state_file.save()?;
-                    break 'frozen;
-                };
+        let message = giver.build_transfer(seqno, address, amount.0)?;
+        let message_hash = jrpc_client.send_external_message(message).await?;
+
+        let tx = jrpc_client
+            .poll_transaction(
+                &message_hash,
+                CONFIRMATION_TIMEOUT,
+                POLL_INTERVAL,
+                Some(|elapsed| {
+                    tracing::debug!(%message_hash, elapsed, "waiting for the transaction");
+                }),
+            )
+            .await?;
 
-                if now_sec() >= frozen.timestamp_until {
-                    if let Some(notify) = self.unfreeze_notifies.lock().unwrap().remove(&network) {
-                        notify.abort();
-                    }
+        match tx {
+            Some(tx) => {
+                let tx_hash = *CellBuilder::build_from(&*tx)?.repr_hash();
+                r.update(format!("✅ {title}\n\nTransaction: `{tx_hash}`"))
+                    .await?;
+                r.react(Emoji::Hotdog).await?;
+            }
+            None => {
+                r.update(format!(
+                    "⌛ {title}\n\nTransaction was not confirmed within {}\nMessage: `{message_hash}`",
+                    humantime::format_duration(CONFIRMATION_TIMEOUT)
+                ))
+                .await?;
+                r.react(Emoji::Clown).await?;
+            }
+        }
 
-                    // Unfreeze on timestamp reached
-                    state_file.latest_data.reset_frozen.remove(&network);
-                    state_file.latest_data.apply_workspace_configs(
-                        &self.node_config_file,
-                        &self.logger_config_file,
-                        &self.zerostate_file,
-                    )?;
-                    state_file.save()?;
-                    break 'frozen;
-                }
+        Ok(())
+    }
 
-                frozen.clone()
-            };
+    pub async fn run_playbook(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        alias: &str,
+        extra_vars: Option<&str>,
+    ) -> Result<()> {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt};
 
-            bot.send_message(msg.chat.id, Reply::ResetFrozen(frozen).to_string())
+        const UPDATE_INTERVAL: Duration = Duration::from_secs(3);
+        const MAX_TAIL_LEN: usize = 3000;
+
+        if !self.check_auth(msg) {
+            bot.send_message(msg.chat.id, Reply::AccessDenied.to_string())
                 .reply_to(msg)
                 .await?;
             return Ok(());
         }
 
-        let _guard = {
-            if descr.reset_running.swap(true, Ordering::Relaxed) {
-                bot.send_message(msg.chat.id, "Reset is already running")
-                    .reply_to(msg)
-                    .await?;
-                return Ok(());
-            }
+        let Some(playbook) = self.allowed_playbooks.get(alias) else {
+            let mut available = self.allowed_playbooks.keys().cloned().collect::<Vec<_>>();
+            available.sort_unstable();
+
+            let reply = Reply::UnknownPlaybook {
+                alias: alias.to_owned(),
+                available,
+            };
+            bot.send_message(msg.chat.id, reply.to_string())
+                .reply_to(msg)
+                .markdown()
+                .await?;
+            return Ok(());
+        };
 
-            ResetGuard(&descr.reset_running)
+        let (network, descr) = {
+            let state_file = self.state_file.lock().unwrap();
+            let network = state_file
+                .latest_data
+                .current_network_name(&self.default_network)
+                .to_owned();
+            let descr = self
+                .networks
+                .get(&network)
+                .with_context(|| format!("no inventory found for the network `{network}`"))?;
+            (network, descr)
         };
 
-        #[derive(Clone, Copy)]
-        struct ReplyText<'a> {
-            network: &'a str,
-            commit_info: &'a CommitInfo,
-            reset_type: ResetType,
-            started_at: Instant,
-        }
+        let vault = self.vault_password_file()?;
 
-        impl<'a> ReplyText<'a> {
-            fn with_title<T: std::fmt::Display>(self, title: T) -> ReplyTextWithTitle<'a, T> {
-                ReplyTextWithTitle { title, body: self }
-            }
+        let mut command = tokio::process::Command::new("ansible-playbook");
+        command
+            .arg("-i")
+            .arg(&descr.inventory)
+            .arg(playbook)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .env(ANSIBLE_CONFIG_ENV, &descr.ansible_config);
+        if let Some(extra_vars) = extra_vars {
+            command.arg("--extra-vars").arg(extra_vars);
+        }
+        if let Some(vault) = &vault {
+            command.arg("--vault-password-file").arg(vault.path());
         }
 
-        impl std::fmt::Display for ReplyText<'_> {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                let elapsed_secs = self.started_at.elapsed().as_secs();
-                let duration = humantime::format_duration(Duration::from_secs(elapsed_secs));
-                writeln!(f, "🌐 Network: `{}`", self.network)?;
-                writeln!(f, "⏰ Elapsed: {duration}")?;
-                writeln!(
-                    f,
-                    "{} Reset type: *{}*\n",
-                    self.reset_type.as_emoji(),
-                    self.reset_type
-                )?;
+        let mut child = command
+            .spawn()
+            .context("Failed to execute ansible-playbook")?;
 
-                let info = self.commit_info;
+        let mut stderr = child.stderr.take().context("no stderr")?;
+        let stderr = tokio::spawn(async move {
+            let mut output = String::new();
+            stderr.read_to_string(&mut output).await.map(|_| output)
+        });
 
-                for line in info.message.lines() {
-                    writeln!(f, "> {line}")?;
-                }
-                writeln!(f, "Commit: `{}`\n", info.sha)?;
+        let title = format!("▶️ Running playbook `{alias}`\n🌐 Network: `{network}`");
+        let r = self
+            .begin_long_reply(bot, msg, format!("{title}..."), false)
+            .await?;
 
-                if !info.branches.is_empty() {
-                    let mut first = true;
-                    write!(f, "Branch: ")?;
-                    for name in &info.branches {
-                        write!(
-                            f,
-                            "{}`{name}`",
-                            if std::mem::take(&mut first) { "" } else { ", " }
-                        )?;
-                    }
-                    writeln!(f, "\n")?;
-                }
+        let tail = |log: &str| {
+            let mut start = log.len().saturating_sub(MAX_TAIL_LEN);
+            while !log.is_char_boundary(start) {
+                start += 1;
+            }
+            log[start..].to_owned()
+        };
 
-                f.write_str(&info.html_url)
+        let mut log = String::new();
+        let mut lines =
+            tokio::io::BufReader::new(child.stdout.take().context("no stdout")?).lines();
+        let mut last_update = Instant::now();
+        while let Some(line) = lines.next_line().await? {
+            log.push_str(&line);
+            log.push('\n');
+
+            if last_update.elapsed() >= UPDATE_INTERVAL {
+                last_update = Instant::now();
+                if let Err(e) = r
+                    .update(format!("{title}...\n```\n{}\n```", tail(&log)))
+                    .await
+                {
+                    tracing::warn!("failed to update playbook output: {e:?}");
+                }
             }
         }
 
-        struct ReplyTextWithTitle<'a, T> {
-            title: T,
-            body: ReplyText<'a>,
+        let status = child.wait().await?;
+        let stderr = stderr.await??;
+        if !stderr.is_empty() {
+            log.push_str(&stderr);
         }
 
-        impl<T: std::fmt::Display> std::fmt::Display for ReplyTextWithTitle<'_, T> {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                writeln!(f, "{}", self.title)?;
-                std::fmt::Display::fmt(&self.body, f)
-            }
+        let (title, emoji) = if status.success() {
+            (format!("✅ {title}"), Emoji::Hotdog)
+        } else {
+            (
+                format!("🟥 {title}\n\nPlaybook failed: {status}"),
+                Emoji::Clown,
+            )
+        };
+        r.update(format!("{title}\n```\n{}\n```", tail(&log)))
+            .await?;
+        if log.len() > MAX_TAIL_LEN {
+            r.send_document("playbook.log", log).await?;
         }
+        r.react(emoji).await?;
 
-        trait LongReplyExt {
-            async fn reply_error(
-                &self,
-                body: ReplyText<'_>,
-                title: &str,
-                error: String,
-            ) -> Result<()>;
+        Ok(())
+    }
+
+    pub fn freeze(self: &Arc<Self>, bot: &Bot, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
         }
 
-        impl LongReplyExt for LongReply {
-            async fn reply_error(
-                &self,
-                body: ReplyText<'_>,
-                title: &str,
-                error: String,
-            ) -> Result<()> {
-                let (title, file) = if error.len() <= 256 {
-                    (format!("🟥 {title}:\n```\n{error}\n```\n"), None)
-                } else {
-                    (format!("🟥 {title}"), Some(error))
-                };
-
-                self.update(body.with_title(title)).await?;
-                if let Some(file) = file {
-                    self.send_document("error.txt", file).await?;
-                }
-
-                self.react(Emoji::Clown).await?;
-                Ok(())
+        let (duration, reason) = match expr.split_once(':') {
+            Some((duration, reason)) => {
+                let duration = humantime::parse_duration(duration.trim())?;
+                (duration, Some(reason.trim().to_owned()))
+            }
+            None => {
+                let duration = humantime::parse_duration(expr.trim())?;
+                (duration, None)
             }
-        }
-
-        let commit_info = self.get_commit_info(&params.commit).await?;
-        let reply_body = ReplyText {
-            network: &network,
-            commit_info: &commit_info,
-            reset_type,
-            started_at: Instant::now(),
         };
+        // anyhow::ensure!(
+        //     duration <= Duration::from_secs(86400),
+        //     "Cannot freeze for more than 24 hours"
+        // );
 
-        let r = LongReply::begin(
-            bot,
-            msg,
-            reply_body.with_title("🔄 Starting network reset..."),
-        )
-        .await?;
-
-        r.update(reply_body.with_title("🔄 Updating gate..."))
-            .await?;
-
-        let gate_update_output = self.run_gate_update().await?;
-        if !gate_update_output.status.success() {
-            let e = String::from_utf8_lossy(&gate_update_output.stderr).to_string();
-            tracing::error!("Gate update failed: {e}");
+        let mut state_file = self.state_file.lock().unwrap();
+        let network = state_file
+            .latest_data
+            .current_network_name(&self.default_network)
+            .to_owned();
 
-            r.reply_error(reply_body, "Gate update failed", e).await?;
-            return Ok(());
+        if let Some(frozen) = state_file.latest_data.reset_frozen.get(&network) {
+            return Ok(Reply::ResetFrozen(frozen.clone()));
         }
 
-        r.update(reply_body.with_title("🔄 Gate updated. Running reset playbook..."))
-            .await?;
+        let timestamp_until = now_sec() + duration.as_secs();
+        let workspace = state_file.latest_data.current_workspace_name();
+        state_file.latest_data.reset_frozen.insert(
+            network.clone(),
+            ResetFrozen {
+                network: network.clone(),
+                workspace: Some(workspace),
+                reason,
+                timestamp_until,
+                chat_id: msg.chat.id,
+                message_id: msg.id,
+                message_thread_id: msg.thread_id,
+            },
+        );
+        state_file.save()?;
 
-        let reset_output = self
-            .run_ansible_reset(&descr.inventory, &params.commit, reset_type)
-            .await?;
-        if !reset_output.status.success() {
-            let e = String::from_utf8_lossy(&reset_output.stdout).to_string();
-            tracing::error!("Reset playbook execution failed: {e}");
+        {
+            let mut notify = self.unfreeze_notifies.lock().unwrap();
+            if let Some(notify) = notify.remove(&network) {
+                notify.abort();
+            }
 
-            r.reply_error(reply_body, "Reset playbook execution failed", e)
-                .await?;
-            return Ok(());
+            let task = tokio::spawn(self.clone().unfreeze_task(
+                bot.clone(),
+                network.clone(),
+                duration,
+            ))
+            .abort_handle();
+            notify.insert(network.clone(), task);
         }
 
-        r.update(reply_body.with_title("🔄 Reset completed. Running setup playbook..."))
-            .await?;
-
-        let setup_output = self.run_ansible_setup(&descr.inventory, &params).await?;
-        if !setup_output.status.success() {
-            let e = String::from_utf8_lossy(&setup_output.stdout).to_string();
-            tracing::error!("Setup playbook execution failed: {e}");
+        Ok(Reply::Freeze { network })
+    }
 
-            r.reply_error(reply_body, "Setup playbook execution failed", e)
-                .await?;
-            return Ok(());
+    pub fn unfreeze(&self, msg: &Message) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
         }
 
-        let link_preview = LinkPreviewOptions {
-            url: commit_info.html_url.clone(),
-        };
+        let mut state_file = self.state_file.lock().unwrap();
+        let network = state_file
+            .latest_data
+            .current_network_name(&self.default_network)
+            .to_owned();
 
+        if state_file
+            .latest_data
+            .reset_frozen
+            .remove(&network)
+            .is_some()
         {
-            let mut state_file = self.state_file.lock().unwrap();
-            state_file.latest_data.last_commit_info = Some(commit_info.clone());
             state_file.save()?;
         }
 
-        r.update(reply_body.with_title("✅ Network reset completed successfully!"))
-            .link_preview_options(Some(link_preview))
-            .await?;
-
-        r.react(Emoji::Hotdog).await?;
-        Ok(())
-    }
+        if let Some(notify) = self.unfreeze_notifies.lock().unwrap().remove(&network) {
+            notify.abort();
+        }
 
-    async fn run_gate_update(&self) -> Result<std::process::Output> {
-        tokio::process::Command::new("sh")
-            .arg("-c")
-            .arg("gate update") // Replace with actual gate update command
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .output()
-            .await
-            .context("Failed to execute gate update command")
+        Ok(Reply::Unfreeze { network })
     }
 
-    async fn run_ansible_reset(
-        &self,
-        inventory_path: &str,
-        commit: &str,
-        reset_type: ResetType,
-    ) -> Result<std::process::Output> {
-        let restart_only = matches!(reset_type, ResetType::Restart);
+    /// Lists networks with running resets, useful before switching networks
+    /// or editing configs when several operators share the bot.
+    pub fn get_reset_status(&self) -> Result<Reply> {
+        let mut networks = self
+            .networks
+            .iter()
+            .map(|(name, descr)| {
+                let elapsed = descr.reset_running.load(Ordering::Relaxed).then(|| {
+                    descr
+                        .reset_started_at
+                        .lock()
+                        .unwrap()
+                        .map(|at| at.elapsed())
+                        .unwrap_or_default()
+                });
+                (name.clone(), elapsed)
+            })
+            .collect::<Vec<_>>();
+        networks.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
 
-        tokio::process::Command::new("ansible-playbook")
-            .arg("-i")
-            .arg(inventory_path)
-            .arg(&self.reset_playbook)
-            .arg("--extra-vars")
-            .arg(format!("tycho_commit={commit} restart_only={restart_only}"))
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .env(ANSIBLE_CONFIG_ENV, &self.ansible_config_file)
-            .output()
-            .await
-            .context("Failed to execute reset playbook")
+        Ok(Reply::ResetStatus(networks))
     }
 
-    async fn run_ansible_setup(
-        &self,
-        inventory_path: &str,
-        params: &ResetParams,
-    ) -> Result<std::process::Output> {
-        let mut args = format!(
-            "tycho_commit={} tycho_build_profile={} n_nodes={}",
-            params.commit, params.build_profile, params.node_count,
-        );
+    pub fn list_all_frozen(&self) -> Result<Reply> {
+        let now = now_sec();
+        let state_file = self.state_file.lock().unwrap();
 
-        if let Some(repo) = &params.repo {
-            args = format!("{args} tycho_repo={repo}");
-        }
+        let mut entries = state_file
+            .latest_data
+            .reset_frozen
+            .values()
+            .filter(|frozen| frozen.timestamp_until > now)
+            .map(|frozen| FrozenEntry {
+                network: frozen.network.clone(),
+                workspace: frozen.workspace.clone(),
+                reason: frozen.reason.clone(),
+                remaining_secs: frozen.timestamp_until - now,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| (a.remaining_secs, &a.network).cmp(&(b.remaining_secs, &b.network)));
 
-        tokio::process::Command::new("ansible-playbook")
-            .arg("-i")
-            .arg(inventory_path)
-            .arg(&self.setup_playbook)
-            .arg("--extra-vars")
-            .arg(args)
-            .stdout(std::process::Stdio::inherit())
-            .stderr(std::process::Stdio::inherit())
-            .env(ANSIBLE_CONFIG_ENV, &self.ansible_config_file)
-            .output()
-            .await
-            .context("Failed to execute setup playbook")
+        Ok(Reply::FreezeList { entries })
     }
 
-    pub fn set_node_config(&self, msg: &Message, expr: &str) -> Result<Reply> {
+    pub fn get_reset_stats(&self) -> Result<Reply> {
+        let state_file = self.state_file.lock().unwrap();
+        Ok(Reply::ResetStats(ResetStats::from_history(
+            &state_file.latest_data.reset_history,
+        )))
+    }
+
+    pub fn compact_state(&self, msg: &Message) -> Result<Reply> {
         if !self.check_auth(msg) {
             return Ok(Reply::AccessDenied);
         }
-        self.set_config_impl(ConfigType::Node, &self.node_config_file, expr)
-            .map(Reply::NodeConfigUpdated)
-    }
 
-    pub fn get_node_config(&self, expr: &str) -> Result<Reply> {
-        self.get_config_impl(ConfigType::Node, &self.node_config_file, expr)
-            .map(Reply::NodeConfigParam)
-    }
+        let max_history = self.settings.borrow().compact_max_history;
 
-    pub fn set_logger_config(&self, msg: &Message, expr: &str) -> Result<Reply> {
-        if !self.check_auth(msg) {
-            return Ok(Reply::AccessDenied);
+        let mut state_file = self.state_file.lock().unwrap();
+        let report = state_file.compact(max_history);
+        if report.entries_removed > 0 {
+            state_file.save()?;
         }
-        self.set_config_impl(ConfigType::Logger, &self.logger_config_file, expr)
-            .map(Reply::LoggerConfigUpdated)
+        Ok(Reply::Compacted(report))
     }
 
-    pub fn get_logger_config(&self, expr: &str) -> Result<Reply> {
-        self.get_config_impl(ConfigType::Logger, &self.logger_config_file, expr)
-            .map(Reply::LoggerConfigParam)
+    pub fn get_backups(&self) -> Result<Reply> {
+        let state_file = self.state_file.lock().unwrap();
+        let backups = state_file
+            .backups
+            .as_ref()
+            .context("state backups are not configured")?
+            .list()?;
+        Ok(Reply::Backups(backups))
     }
 
-    pub fn set_zerostate(&self, msg: &Message, expr: &str) -> Result<Reply> {
+    pub fn restore_backup(self: &Arc<Self>, bot: &Bot, msg: &Message, expr: &str) -> Result<Reply> {
         if !self.check_auth(msg) {
             return Ok(Reply::AccessDenied);
         }
-        self.set_config_impl(ConfigType::Zerostate, &self.zerostate_file, expr)
-            .map(Reply::ZerostateUpdated)
-    }
 
-    pub fn get_zerostate(&self, expr: &str) -> Result<Reply> {
-        self.get_config_impl(ConfigType::Zerostate, &self.zerostate_file, expr)
-            .map(Reply::ZerostateParam)
-    }
+        let name = expr.trim();
 
-    pub fn check_auth(&self, msg: &Message) -> bool {
-        !self.authentication_enabled || self.allowed_groups.contains(&msg.chat.id.0)
-    }
+        let mut state_file = self.state_file.lock().unwrap();
+        let data = state_file
+            .backups
+            .as_ref()
+            .context("state backups are not configured")?
+            .load(name)?;
 
-    async fn get_commit_info(&self, commit: &str) -> Result<CommitInfo> {
-        let commit_sha = self.github_client.get_commit_sha(commit).await?;
-        let commit_info = self.github_client.get_commit_info(&commit_sha).await?;
-        let commit_branches = self.github_client.get_commit_branches(&commit_sha).await?;
+        self.replace_state_data(bot, &mut state_file, data)?;
 
-        Ok(CommitInfo {
-            sha: commit_sha,
-            html_url: commit_info.html_url,
-            message: commit_info.message,
-            branches: commit_branches,
+        Ok(Reply::BackupRestored {
+            name: name.to_owned(),
         })
     }
 
-    fn set_config_impl(&self, ty: ConfigType, path: &str, expr: &str) -> Result<ConfigDiff> {
-        let mut state_file = self.state_file.lock().unwrap();
+    pub fn export_state(&self, msg: &Message) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
 
-        let object = state_file.latest_data.get_config_object(ty);
-        let mut config = match object {
-            Some(object) => Config::from_value(path, object.clone())?,
-            None => Config::from_file(path)?,
+        let data = {
+            let state_file = self.state_file.lock().unwrap();
+            serde_json::to_vec_pretty(&state_file.latest_data)
+                .context("failed to serialize state")?
         };
 
-        let expr = expr.trim();
-        match expr.strip_prefix("delete") {
-            Some(path) => {
-                let path = parse_config_value_path(path)?;
-                anyhow::ensure!(!path.is_empty(), "cannot delete the config root");
-                config.remove(&path)?;
-            }
-            None => {
-                let (path, value) = expr
-                    .split_once('=')
-                    .context("expected an expression: (.path)+ = json")?;
+        let data = match &self.state_export_cipher {
+            Some(cipher) => cipher.encrypt(&data)?,
+            None => data,
+        };
+        Ok(Reply::StateExported { data })
+    }
 
-                let path = parse_config_value_path(path)?;
-                let value = serde_json::from_str(value)?;
-                config.set(&path, value)?;
-            }
+    /// Loads the state from the replied document and asks for a confirmation.
+    pub async fn import_state(&self, bot: &Bot, msg: &Message) -> Result<()> {
+        if !self.check_auth(msg) {
+            bot.send_message(msg.chat.id, Reply::AccessDenied.to_string())
+                .reply_to(msg)
+                .await?;
+            return Ok(());
         }
 
-        *object = Some(config.as_object()?);
-        let diff = config.save()?;
-        state_file.save()?;
+        let user = msg.from.as_ref().context("unknown sender")?;
+        let document = msg
+            .reply_to_message()
+            .and_then(|reply| reply.document())
+            .context("reply to an exported state document")?;
 
-        Ok(diff)
+        let file = bot.get_file(document.file.id.clone()).await?;
+        let mut content = Vec::new();
+        bot.download_file(&file.path, &mut content).await?;
+
+        if let Some(cipher) = &self.state_export_cipher {
+            content = cipher.decrypt(&content)?;
+        }
+        let data = StateFileData::from_slice(&content)?;
+
+        let errors = self.validate_imported_workspaces(&data.workspaces)?;
+        if !errors.is_empty() {
+            bot.send_message(
+                msg.chat.id,
+                Reply::WorkspaceImportFailed(errors).to_string(),
+            )
+            .reply_to(msg)
+            .markdown()
+            .await?;
+            return Ok(());
+        }
+
+        let text = format!(
+            "Import state with {} workspaces, {} frozen networks and {} reset history entries?\n\n\
+            ⚠️ The current state will be replaced",
+            data.workspaces.len(),
+            data.reset_frozen.len(),
+            data.reset_history.len(),
+        );
+
+        self.pending_imports
+            .lock()
+            .unwrap()
+            .insert((msg.chat.id, user.id), data);
+
+        let keyboard = InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::callback("✅ Import", IMPORT_CONFIRM_CALLBACK),
+            InlineKeyboardButton::callback("❌ Cancel", IMPORT_CANCEL_CALLBACK),
+        ]]);
+        bot.send_message(msg.chat.id, text)
+            .reply_to(msg)
+            .markdown()
+            .reply_markup(keyboard)
+            .await?;
+        Ok(())
     }
 
-    fn get_config_impl(&self, ty: ConfigType, path: &str, expr: &str) -> Result<String> {
-        let field_path = parse_config_value_path(expr)?;
+    async fn handle_import_callback(
+        self: &Arc<Self>,
+        bot: &Bot,
+        query: &CallbackQuery,
+        confirmed: bool,
+    ) -> Result<()> {
+        bot.answer_callback_query(query.id.clone()).await?;
 
-        let mut state_file = self.state_file.lock().unwrap();
+        let Some(message) = query.regular_message() else {
+            return Ok(());
+        };
 
-        let object = state_file.latest_data.get_config_object(ty);
-        let config = match object {
-            Some(object) => Config::from_value(path, object.clone())?,
-            None => {
-                let config = Config::from_file(path)?;
-                *object = Some(config.as_object()?);
-                state_file.save()?;
-                config
+        let text = if !self.check_auth(message) {
+            Reply::AccessDenied.to_string()
+        } else {
+            let data = self
+                .pending_imports
+                .lock()
+                .unwrap()
+                .remove(&(message.chat.id, query.from.id));
+
+            match data {
+                None => "No pending state import".to_owned(),
+                Some(_) if !confirmed => "State import cancelled".to_owned(),
+                Some(data) => {
+                    let mut state_file = self.state_file.lock().unwrap();
+                    match self.replace_state_data(bot, &mut state_file, data) {
+                        Ok(()) => "✅ State imported".to_owned(),
+                        Err(e) => Reply::from_error("Failed to import state", &e).to_string(),
+                    }
+                }
             }
         };
 
-        let value = serde_json::to_string_pretty(config.get(&field_path)?)?;
-        Ok(value)
-    }
-
-    fn get_current_jrpc_client(&self) -> Result<&JrpcClient> {
-        let state_file = self.state_file.lock().unwrap();
-        let network_name = state_file
-            .latest_data
-            .current_network_name(&self.default_network);
-        self.networks
-            .get(network_name)
-            .map(|descr| &descr.jrpc_client)
-            .with_context(|| format!("no JRPC client found for the network `{network_name}`"))
+        bot.edit_message_text(message.chat.id, message.id, text)
+            .markdown()
+            .await?;
+        Ok(())
     }
 
-    async fn unfreeze_task(self: Arc<Self>, bot: Bot, network: String, duration: Duration) {
-        tokio::time::sleep(duration).await;
+    fn replace_state_data(
+        self: &Arc<Self>,
+        bot: &Bot,
+        state_file: &mut StateFile,
+        data: StateFileData,
+    ) -> Result<()> {
+        state_file.latest_data = data;
+        state_file.save()?;
+
+        // Reschedule unfreeze notifications for the new state
+        let mut notify = self.unfreeze_notifies.lock().unwrap();
+        for (_, task) in notify.drain() {
+            task.abort();
+        }
+        for (network, frozen) in &state_file.latest_data.reset_frozen {
+            let duration = Duration::from_secs(frozen.timestamp_until.saturating_sub(now_sec()));
+            let task = tokio::spawn(self.clone().unfreeze_task(
+                bot.clone(),
+                network.clone(),
+                duration,
+            ))
+            .abort_handle();
+            notify.insert(network.clone(), task);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_info(&self) -> Result<Reply> {
+        let state_file = self.state_file.lock().unwrap();
+        let data = &state_file.latest_data;
+
+        let mut frozen_networks = data.reset_frozen.keys().cloned().collect::<Vec<_>>();
+        frozen_networks.sort_unstable();
+
+        let workspace = data.current_workspace_name();
+        let mut workspace_count = data.workspaces.len();
+        if !data.workspaces.contains_key(&workspace) {
+            workspace_count += 1;
+        }
+
+        Ok(Reply::Info(BotInfo {
+            workspace,
+            network: data.current_network_name(&self.default_network).to_owned(),
+            reset_type: data.current_reset_type(),
+            commit: data.last_commit_info.as_ref().map(|c| c.sha.clone()),
+            frozen_networks,
+            workspace_count,
+            network_count: self.networks.len(),
+            last_reset_at: data.reset_history.iter().map(|e| e.finished_at).max(),
+        }))
+    }
+
+    /// Collects health signals of all networks and the bot itself into one report.
+    pub async fn get_health_report(&self) -> Result<Reply> {
+        let mut names = self.networks.keys().cloned().collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let timings = futures::future::join_all(names.iter().map(|name| async move {
+            self.networks[name]
+                .jrpc_client()
+                .get_timings()
+                .await
+                .map_err(|e| e.to_string())
+        }));
+        let giver_balance = async {
+            let giver = self.giver.as_ref()?;
+            let balance = match self.get_current_jrpc_client() {
+                Ok(jrpc_client) => get_giver_balance(&jrpc_client, giver).await,
+                Err(e) => Err(e),
+            };
+            Some(balance.map(DecimalTokens).map_err(|e| e.to_string()))
+        };
+        let (timings, giver_balance) = tokio::join!(timings, giver_balance);
+
+        let unhealthy = self.unhealthy_networks.lock().unwrap().clone();
+        let state_file = self.state_file.lock().unwrap();
+        let data = &state_file.latest_data;
+        let current = data.current_network_name(&self.default_network);
+        let now = now_sec();
+
+        let networks = names
+            .into_iter()
+            .zip(timings)
+            .map(|(name, timings)| NetworkHealth {
+                is_current: name == current,
+                frozen_until: data
+                    .reset_frozen
+                    .get(&name)
+                    .map(|frozen| frozen.timestamp_until)
+                    .filter(|until| *until > now),
+                jrpc_unhealthy: unhealthy.contains(&name),
+                reset_running: self.networks[&name].reset_running.load(Ordering::Relaxed),
+                timings,
+                name,
+            })
+            .collect();
+
+        Ok(Reply::HealthReport(HealthReport {
+            networks,
+            last_reset: data.reset_history.last().cloned(),
+            giver_balance,
+            min_giver_balance: self.settings.borrow().min_giver_balance_tokens,
+            monitoring_paused_until: self.monitoring_paused_until(),
+        }))
+    }
+
+    /// Compares two commits. The base defaults to the deployed commit.
+    pub async fn compare_commits(&self, expr: &str) -> Result<Reply> {
+        let mut parts = expr.split_whitespace();
+        let (base, head) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(base), Some(head), None) => (base.to_owned(), head.to_owned()),
+            (Some(head), None, None) => {
+                let state_file = self.state_file.lock().unwrap();
+                let base = state_file
+                    .latest_data
+                    .last_commit_info
+                    .as_ref()
+                    .map(|c| c.sha.clone())
+                    .context("no commit info saved")?;
+                (base, head.to_owned())
+            }
+            _ => anyhow::bail!("expected `[base] <head>`"),
+        };
+
+        let result = self.github().compare_commits(&base, &head).await?;
+        Ok(Reply::CommitComparison(result))
+    }
+
+    pub async fn get_saved_commit(&self) -> Result<Reply> {
+        let mut info = {
+            let state_file = self.state_file.lock().unwrap();
+            state_file
+                .latest_data
+                .last_commit_info
+                .clone()
+                .context("no commit info saved")?
+        };
+
+        // NOTE: The saved status is from the reset time, so it is refreshed
+        info.ci_status = self.fetch_ci_status(&info.sha).await;
+        Ok(Reply::Commit(info))
+    }
+
+    /// Displays workflow runs of the specified or the deployed commit.
+    pub async fn get_ci_status(&self, expr: &str) -> Result<Reply> {
+        let commit = match expr.trim() {
+            "" => {
+                let state_file = self.state_file.lock().unwrap();
+                state_file
+                    .latest_data
+                    .last_commit_info
+                    .as_ref()
+                    .map(|info| info.sha.clone())
+                    .context("no commit info saved")?
+            }
+            commit => commit.to_owned(),
+        };
+
+        let sha = self.github().get_commit_sha(&commit).await?;
+        let runs = self.github().get_workflow_runs(&sha).await?;
+        Ok(Reply::CiStatus { sha, runs })
+    }
+
+    pub fn set_workspace(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let SetWorkspaceParams {
+            workspace,
+            copy_from,
+            copy_keys,
+            tracked_branch,
+        } = expr.parse()?;
+
+        let mut state_file = self.state_file.lock().unwrap();
+        // NOTE: Changes are made on a copy so that a failed merge leaves no partial state
+        let mut data = state_file.latest_data.clone();
+
+        // `copy_keys` merges only the specified paths instead of the full copy
+        let full_copy_from = copy_from.as_deref().filter(|_| copy_keys.is_empty());
+        let merge_from = copy_from.as_deref().filter(|_| !copy_keys.is_empty());
+
+        let source = full_copy_from.unwrap_or(DEFAULT_WORKSPACE);
+        let compute_prev_source = |object: &Option<JsonObject>| {
+            if object.is_none() {
+                ConfigSource::FromFile
+            } else if full_copy_from.is_some() {
+                ConfigSource::Copied
+            } else {
+                ConfigSource::Unchanged
+            }
+        };
+        let (node_source, logger_source, zerostate_source) = match data.workspaces.get(source) {
+            Some(prev) => (
+                compute_prev_source(&prev.node),
+                compute_prev_source(&prev.logger),
+                compute_prev_source(&prev.zerostate),
+            ),
+            None => (
+                ConfigSource::FromFile,
+                ConfigSource::FromFile,
+                ConfigSource::FromFile,
+            ),
+        };
+
+        let is_new = !data.workspaces.contains_key(&workspace);
+        if is_new || full_copy_from.is_some() {
+            self.clone_workspace_impl(&mut data, source, &workspace, true)?;
+        }
+        if let Some(merge_from) = merge_from {
+            self.merge_workspace_impl(&mut data, merge_from, &workspace, &copy_keys)?;
+        }
+        if let Some(branch) = tracked_branch {
+            let workspace = data.workspaces.entry(workspace.clone()).or_default();
+            workspace.tracked_branch = Some(branch).filter(|branch| !branch.is_empty());
+            workspace.tracked_commit = None;
+        }
+        let network = data.workspaces[&workspace].network.clone();
+        let env_overrides = sorted_env_overrides(&data.workspaces[&workspace]);
+
+        data.current_workspace = Some(workspace);
+        state_file.latest_data = data;
+        state_file.save()?;
+
+        Ok(Reply::WorkspaceChanged {
+            is_new,
+            network: network.unwrap_or_else(|| self.default_network.clone()),
+            node_source,
+            logger_source,
+            zerostate_source,
+            copy_from,
+            merged_keys: copy_keys.iter().map(|key| format_copy_key(key)).collect(),
+            env_overrides,
+        })
+    }
+
+    /// Copies the source workspace into `dest` without switching to it.
+    pub fn clone_workspace(
+        &self,
+        msg: &Message,
+        source: &str,
+        dest: &str,
+        overwrite: bool,
+    ) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let mut state_file = self.state_file.lock().unwrap();
+        self.clone_workspace_impl(&mut state_file.latest_data, source, dest, overwrite)?;
+        state_file.save()?;
+
+        Ok(Reply::WorkspaceCloned {
+            source: source.to_owned(),
+            dest: dest.to_owned(),
+        })
+    }
+
+    fn clone_workspace_impl(
+        &self,
+        data: &mut StateFileData,
+        source: &str,
+        dest: &str,
+        overwrite: bool,
+    ) -> Result<()> {
+        anyhow::ensure!(
+            overwrite || !data.workspaces.contains_key(dest),
+            "workspace {dest} already exists, use `--overwrite` to replace it"
+        );
+
+        let mut workspace = self.preloaded_workspace(data, source)?;
+        workspace.last_modified = now_sec();
+        data.workspaces.insert(dest.to_owned(), workspace);
+        Ok(())
+    }
+
+    /// Creates a new workspace from the configs on disk with the template applied on top.
+    pub fn create_from_template(
+        &self,
+        msg: &Message,
+        params: &CreateFromTemplateParams,
+    ) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let CreateFromTemplateParams {
+            workspace: name,
+            template: template_name,
+        } = params;
+
+        let path = self
+            .settings
+            .borrow()
+            .workspace_templates
+            .get(template_name)
+            .cloned()
+            .with_context(|| format!("template not found {template_name}"))?;
+        let template = load_workspace_template(&path)
+            .with_context(|| format!("failed to load template {template_name}"))?;
+
+        let errors = self.validate_imported_workspaces([(template_name, &template)])?;
+        if !errors.is_empty() {
+            return Ok(Reply::WorkspaceImportFailed(errors));
+        }
+
+        if let Some(network) = &template.network {
+            anyhow::ensure!(
+                self.networks.contains_key(network),
+                "no inventory found for the template network `{network}`"
+            );
+        }
+
+        let mut state_file = self.state_file.lock().unwrap();
+        anyhow::ensure!(
+            name != DEFAULT_WORKSPACE && !state_file.latest_data.workspaces.contains_key(name),
+            "workspace {name} already exists"
+        );
+
+        let mut workspace = Workspace::default();
+        self.preload_workspace(&mut workspace)?;
+        workspace.apply_node_defaults(self.node_config_defaults.as_ref())?;
+        workspace.merge_from(&template, &template.leaf_keys())?;
+        if template.network.is_some() {
+            workspace.network.clone_from(&template.network);
+        }
+        if template.default_reset_type.is_some() {
+            workspace.default_reset_type = template.default_reset_type;
+        }
+        workspace.last_modified = now_sec();
+
+        state_file
+            .latest_data
+            .workspaces
+            .insert(name.clone(), workspace);
+        state_file.save()?;
+
+        Ok(Reply::WorkspaceFromTemplate {
+            workspace: name.clone(),
+            template: template_name.clone(),
+        })
+    }
+
+    pub fn list_templates(&self) -> Reply {
+        let mut templates = self
+            .settings
+            .borrow()
+            .workspace_templates
+            .iter()
+            .map(|(name, path)| (name.clone(), path.clone()))
+            .collect::<Vec<_>>();
+        templates.sort_unstable();
+        Reply::Templates(templates)
+    }
+
+    /// Copies only the specified config paths from the source workspace into `dest`.
+    fn merge_workspace_impl(
+        &self,
+        data: &mut StateFileData,
+        source: &str,
+        dest: &str,
+        keys: &[Vec<PathSegment>],
+    ) -> Result<()> {
+        let source_workspace = self.preloaded_workspace(data, source)?;
+
+        let mut workspace = data
+            .workspaces
+            .get(dest)
+            .cloned()
+            .with_context(|| format!("workspace not found {dest}"))?;
+        self.preload_workspace(&mut workspace)?;
+        workspace.merge_from(&source_workspace, keys)?;
+        workspace.last_modified = now_sec();
+
+        data.workspaces.insert(dest.to_owned(), workspace);
+        Ok(())
+    }
+
+    /// Validates configs of the imported workspaces, errors are prefixed with
+    /// the workspace name.
+    fn validate_imported_workspaces<'a, I>(&self, workspaces: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = (&'a String, &'a Workspace)>,
+    {
+        let mut errors = Vec::new();
+        for (name, workspace) in workspaces {
+            let workspace_errors = workspace.validate_configs(
+                &self.node_config_file,
+                &self.logger_config_file,
+                &self.zerostate_file,
+                &self.config_validators,
+            )?;
+            errors.extend(
+                workspace_errors
+                    .into_iter()
+                    .map(|e| format!("workspace `{name}`: {e}")),
+            );
+        }
+        errors.sort_unstable();
+        Ok(errors)
+    }
+
+    /// Loads missing workspace configs from files.
+    fn preload_workspace(&self, workspace: &mut Workspace) -> Result<()> {
+        let env_prefix = self.settings.borrow().config_env_override_prefix.clone();
+        workspace.preload(
+            &self.node_config_file,
+            &self.logger_config_file,
+            &self.zerostate_file,
+            env_prefix.as_deref(),
+            env_override_lookup(env_prefix.as_deref()),
+        )
+    }
+
+    /// Returns a copy of the workspace with all configs loaded
+    /// and node config defaults applied to the copy.
+    fn preloaded_workspace(&self, data: &mut StateFileData, name: &str) -> Result<Workspace> {
+        let workspace = if name == DEFAULT_WORKSPACE {
+            data.workspaces
+                .entry(DEFAULT_WORKSPACE.to_owned())
+                .or_default()
+        } else {
+            data.workspaces
+                .get_mut(name)
+                .with_context(|| format!("workspace not found {name}"))?
+        };
+        self.preload_workspace(workspace)?;
+
+        let mut workspace = workspace.clone();
+        workspace.apply_node_defaults(self.node_config_defaults.as_ref())?;
+        Ok(workspace)
+    }
+
+    pub fn list_workspaces_detailed(&self) -> Result<Reply> {
+        let state_file = self.state_file.lock().unwrap();
+        let data = &state_file.latest_data;
+
+        let current = data.current_workspace_name();
+
+        let mut names = data.workspaces.keys().cloned().collect::<Vec<_>>();
+        if !data.workspaces.contains_key(&current) {
+            names.push(current.clone());
+        }
+        names.sort_unstable();
+
+        let workspaces = names
+            .iter()
+            .map(|name| data.workspace_info(name, &self.default_network))
+            .collect();
+
+        Ok(Reply::WorkspacesDetailed(workspaces))
+    }
+
+    /// Fetches heads of tracked branches and returns workspaces whose commit has changed.
+    pub async fn refresh_workspace_commits(&self) -> Result<Vec<(String, CommitInfo)>> {
+        let tracked = {
+            let state_file = self.state_file.lock().unwrap();
+            let mut tracked = state_file
+                .latest_data
+                .workspaces
+                .iter()
+                .filter_map(|(name, w)| Some((name.clone(), w.tracked_branch.clone()?)))
+                .collect::<Vec<_>>();
+            tracked.sort_unstable();
+            tracked
+        };
+
+        let mut commits = Vec::with_capacity(tracked.len());
+        for (name, branch) in tracked {
+            let commit_info = self
+                .get_commit_info(&branch)
+                .await
+                .with_context(|| format!("failed to get the head of `{branch}` for {name}"))?;
+            commits.push((name, commit_info));
+        }
+
+        let mut state_file = self.state_file.lock().unwrap();
+        let mut updated = Vec::new();
+        for (name, commit_info) in commits {
+            // The workspace could have been removed while fetching
+            let Some(workspace) = state_file.latest_data.workspaces.get_mut(&name) else {
+                continue;
+            };
+            if workspace.tracked_commit.as_ref().map(|c| c.sha.as_str())
+                == Some(commit_info.sha.as_str())
+            {
+                continue;
+            }
+            workspace.tracked_commit = Some(commit_info.clone());
+            updated.push((name, commit_info));
+        }
+        state_file.save()?;
+
+        Ok(updated)
+    }
+
+    pub async fn refresh_commits(&self, msg: &Message) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+        self.refresh_workspace_commits()
+            .await
+            .map(Reply::WorkspaceCommitsRefreshed)
+    }
+
+    /// Returns details of the named workspace or the current one.
+    pub fn get_workspace_info(&self, name: Option<&str>) -> Result<Reply> {
+        let state_file = self.state_file.lock().unwrap();
+        let data = &state_file.latest_data;
+
+        let name = match name {
+            Some(name) => {
+                anyhow::ensure!(
+                    name == DEFAULT_WORKSPACE || data.workspaces.contains_key(name),
+                    "workspace not found {name}"
+                );
+                name.to_owned()
+            }
+            None => data.current_workspace_name(),
+        };
+
+        Ok(Reply::WorkspaceInfo(
+            data.workspace_info(&name, &self.default_network),
+        ))
+    }
+
+    pub fn workspace_keyboard(&self) -> InlineKeyboardMarkup {
+        let workspaces = {
+            let state_file = self.state_file.lock().unwrap();
+            let mut workspaces = state_file
+                .latest_data
+                .workspaces
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            if !workspaces.iter().any(|name| name == DEFAULT_WORKSPACE) {
+                workspaces.push(DEFAULT_WORKSPACE.to_owned());
+            }
+            workspaces.sort_unstable();
+            workspaces
+        };
+
+        let mut rows = workspaces
+            .into_iter()
+            .map(|name| {
+                let data = format!("{WORKSPACE_CALLBACK_PREFIX}{name}");
+                vec![InlineKeyboardButton::callback(name, data)]
+            })
+            .collect::<Vec<_>>();
+        rows.push(vec![InlineKeyboardButton::callback(
+            "➕ New workspace",
+            NEW_WORKSPACE_CALLBACK,
+        )]);
+
+        InlineKeyboardMarkup::new(rows)
+    }
+
+    pub async fn list_open_prs(&self, expr: &str) -> Result<InlineKeyboardMarkup> {
+        const DEFAULT_LIMIT: u8 = 10;
+        const MAX_TITLE_LEN: usize = 48;
+
+        let expr = expr.trim();
+        let limit = if expr.is_empty() {
+            DEFAULT_LIMIT
+        } else {
+            expr.parse().context("invalid PR count")?
+        };
+
+        let prs = self.github().list_open_prs(limit).await?;
+        anyhow::ensure!(!prs.is_empty(), "no open pull requests");
+
+        let rows = prs
+            .into_iter()
+            .map(|pr| {
+                let mut title = pr.title;
+                if title.chars().count() > MAX_TITLE_LEN {
+                    title = title.chars().take(MAX_TITLE_LEN).collect::<String>() + "…";
+                }
+                let text = format!("#{} {title} ({})", pr.number, pr.author);
+                let data = format!("{PR_CALLBACK_PREFIX}{}", pr.number);
+                vec![InlineKeyboardButton::callback(text, data)]
+            })
+            .collect::<Vec<_>>();
+
+        Ok(InlineKeyboardMarkup::new(rows))
+    }
+
+    async fn handle_pr_callback(
+        self: &Arc<Self>,
+        bot: &Bot,
+        query: &CallbackQuery,
+        number: &str,
+    ) -> Result<()> {
+        bot.answer_callback_query(query.id.clone()).await?;
+
+        let Some(message) = query.regular_message() else {
+            return Ok(());
+        };
+
+        let number = number.parse::<u64>().context("invalid PR number")?;
+        let pr = self.github().get_pull_request(number).await?;
+        let mut params = pr.head_sha.parse::<ResetParams>()?;
+        if pr.is_fork {
+            // NOTE: The commit is only in the fork, so it must be built from there
+            let clone_url = pr
+                .head_clone_url
+                .with_context(|| format!("the head repository of PR #{number} was deleted"))?;
+            params.repo = Some(clone_url);
+        }
+
+        let this = self.clone();
+        let bot = bot.clone();
+        let message = message.clone();
+        tokio::spawn(async move {
+            if let Err(e) = this.reset_network(bot.clone(), &message, params).await {
+                tracing::error!("request failed: {e:?}");
+
+                let reply = Reply::from_error("Failed to handle reset", &e);
+                _ = bot
+                    .send_message(message.chat.id, reply.to_string())
+                    .reply_to(&message)
+                    .markdown()
+                    .await;
+            }
+        });
+        Ok(())
+    }
+
+    pub async fn handle_callback(self: &Arc<Self>, bot: &Bot, query: &CallbackQuery) -> Result<()> {
+        let Some(data) = query.data.as_deref() else {
+            return Ok(());
+        };
+
+        if let Some(number) = data.strip_prefix(PR_CALLBACK_PREFIX) {
+            self.handle_pr_callback(bot, query, number).await
+        } else if data == IMPORT_CONFIRM_CALLBACK || data == IMPORT_CANCEL_CALLBACK {
+            self.handle_import_callback(bot, query, data == IMPORT_CONFIRM_CALLBACK)
+                .await
+        } else if data == NEW_WORKSPACE_CALLBACK {
+            self.handle_new_workspace_callback(bot, query).await
+        } else if let Some(workspace_name) = data.strip_prefix(WORKSPACE_CALLBACK_PREFIX) {
+            self.handle_workspace_callback(bot, query, workspace_name)
+                .await
+        } else {
+            bot.answer_callback_query(query.id.clone()).await?;
+            Ok(())
+        }
+    }
+
+    pub async fn handle_workspace_callback(
+        &self,
+        bot: &Bot,
+        query: &CallbackQuery,
+        workspace_name: &str,
+    ) -> Result<()> {
+        bot.answer_callback_query(query.id.clone()).await?;
+
+        let Some(message) = query.regular_message() else {
+            return Ok(());
+        };
+
+        let text = match self.set_workspace(message, workspace_name) {
+            Ok(reply) => reply.to_string(),
+            Err(e) => Reply::from_error("Failed to handle command", &e).to_string(),
+        };
+        bot.edit_message_text(message.chat.id, message.id, text)
+            .markdown()
+            .await?;
+        Ok(())
+    }
+
+    async fn handle_new_workspace_callback(&self, bot: &Bot, query: &CallbackQuery) -> Result<()> {
+        bot.answer_callback_query(query.id.clone()).await?;
+
+        let Some(message) = query.regular_message() else {
+            return Ok(());
+        };
+
+        let text = if self.check_auth(message) {
+            self.pending_inputs.lock().unwrap().insert(
+                (message.chat.id, query.from.id),
+                PendingInput::WorkspaceName,
+            );
+            "Send the name of the new workspace".to_owned()
+        } else {
+            Reply::AccessDenied.to_string()
+        };
+
+        bot.edit_message_text(message.chat.id, message.id, text)
+            .await?;
+        Ok(())
+    }
+
+    /// Consumes a follow-up message if the sender was prompted for input.
+    pub fn handle_pending_input(&self, msg: &Message) -> Option<Result<Reply>> {
+        let user = msg.from.as_ref()?;
+        let text = msg.text()?;
+
+        let pending = self
+            .pending_inputs
+            .lock()
+            .unwrap()
+            .remove(&(msg.chat.id, user.id))?;
+
+        Some(match pending {
+            PendingInput::WorkspaceName => self.set_workspace(msg, text),
+        })
+    }
+
+    pub fn delete_workspace(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let workspace_name = expr.trim();
+        if workspace_name == DEFAULT_WORKSPACE {
+            anyhow::bail!("cannot remove the default workspace");
+        }
+
+        let mut state_file = self.state_file.lock().unwrap();
+
+        if matches!(
+            &state_file.latest_data.current_workspace,
+            Some(current) if current == workspace_name
+        ) {
+            state_file.latest_data.current_workspace = None;
+        }
+
+        if state_file
+            .latest_data
+            .workspaces
+            .remove(workspace_name)
+            .is_none()
+        {
+            anyhow::bail!("workspace does not exist: `{workspace_name}`");
+        }
+
+        state_file.save()?;
+        Ok(Reply::WorkspaceRemoved)
+    }
+
+    /// Lists all networks with their sync, freeze and reset status.
+    pub async fn list_networks_with_status(&self) -> Result<Reply> {
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        let mut names = self.networks.keys().cloned().collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let timings = futures::future::join_all(names.iter().map(|name| async move {
+            let client = self.networks[name].jrpc_client();
+            match tokio::time::timeout(TIMEOUT, client.get_timings()).await {
+                Ok(Ok(timings)) => Some(timings),
+                Ok(Err(e)) => {
+                    tracing::warn!("failed to get timings of `{name}`: {e:?}");
+                    None
+                }
+                Err(_) => None,
+            }
+        }))
+        .await;
+
+        let state_file = self.state_file.lock().unwrap();
+        let data = &state_file.latest_data;
+        let current = data.current_network_name(&self.default_network);
+        let now = now_sec();
+
+        let networks = names
+            .into_iter()
+            .zip(timings)
+            .map(|(name, timings)| NetworkStatus {
+                current: name == current,
+                frozen: data
+                    .reset_frozen
+                    .get(&name)
+                    .is_some_and(|frozen| frozen.timestamp_until > now),
+                reset_running: self.networks[&name].reset_running.load(Ordering::Relaxed),
+                timings,
+                name,
+            })
+            .collect();
+
+        Ok(Reply::NetworkSummary(networks))
+    }
+
+    pub async fn set_network(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let SetNetworkParams { network, force } = expr.parse()?;
+        let descr = self
+            .networks
+            .get(&network)
+            .with_context(|| format!("no inventory found for the network `{network}`"))?;
+
+        if !force {
+            let reason =
+                match tokio::time::timeout(TIMEOUT, descr.jrpc_client().get_timings()).await {
+                    Ok(Ok(_)) => None,
+                    Ok(Err(e)) => Some(e.to_string()),
+                    Err(_) => Some(format!(
+                        "timed out after {}",
+                        humantime::format_duration(TIMEOUT)
+                    )),
+                };
+            if let Some(reason) = reason {
+                return Ok(Reply::NetworkUnreachable { network, reason });
+            }
+        }
+
+        let mut state_file = self.state_file.lock().unwrap();
+        let current_workspace_name = state_file.latest_data.current_workspace_name();
+
+        let current_workspace = state_file
+            .latest_data
+            .workspaces
+            .entry(current_workspace_name.clone())
+            .or_default();
+
+        current_workspace.network = Some(network.clone());
+        let env_overrides = sorted_env_overrides(current_workspace);
+        state_file.save()?;
+
+        Ok(Reply::WorkspaceChanged {
+            is_new: false,
+            network,
+            node_source: ConfigSource::Unchanged,
+            logger_source: ConfigSource::Unchanged,
+            zerostate_source: ConfigSource::Unchanged,
+            copy_from: None,
+            merged_keys: Vec::new(),
+            env_overrides,
+        })
+    }
+
+    pub fn get_reset_type(&self) -> Result<Reply> {
+        let state_file = self.state_file.lock().unwrap();
+        Ok(Reply::ResetType(
+            state_file.latest_data.current_reset_type(),
+        ))
+    }
+
+    pub fn set_reset_type(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let expr = expr.trim();
+        if expr.is_empty() || expr == "--list" {
+            return Ok(Reply::ResetTypeList { unknown: None });
+        }
+        let Ok(reset_type) = expr.parse() else {
+            return Ok(Reply::ResetTypeList {
+                unknown: Some(expr.to_owned()),
+            });
+        };
+
+        let mut state_file = self.state_file.lock().unwrap();
+        state_file
+            .latest_data
+            .current_workspace_mut()
+            .default_reset_type = Some(reset_type);
+        state_file.save()?;
+
+        Ok(Reply::ResetType(reset_type))
+    }
+
+    /// Fills the config path from the environment variable on each apply,
+    /// so that secrets are not stored in the state file.
+    /// An omitted variable removes the override.
+    pub fn set_env_override(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let mut parts = expr.split_whitespace();
+        let (Some(path), env_var, None) = (parts.next(), parts.next(), parts.next()) else {
+            anyhow::bail!("expected `<path> [env_var]`");
+        };
+
+        let path = parse_copy_key(path)?;
+        anyhow::ensure!(path.len() > 1, "cannot override the whole config");
+        anyhow::ensure!(
+            !path.contains(&PathSegment::Wildcard),
+            "wildcards are not supported"
+        );
+        let path = format_copy_key(&path);
+
+        if let Some(env_var) = env_var {
+            let settings = self.settings.borrow();
+            check_env_override_var(env_var, settings.config_env_override_prefix.as_deref())?;
+        }
+
+        let mut state_file = self.state_file.lock().unwrap();
+        let workspace = state_file.latest_data.current_workspace_mut();
+        match env_var {
+            Some(env_var) => workspace
+                .env_overrides
+                .insert(path.clone(), env_var.to_owned()),
+            None => workspace.env_overrides.remove(&path),
+        };
+        workspace.last_modified = now_sec();
+        state_file.save()?;
+
+        Ok(Reply::EnvOverride {
+            path,
+            env_var: env_var.map(str::to_owned),
+        })
+    }
+
+    /// Parses reset params and checks that the target network is known
+    /// so that the error is reported before the reset is started.
+    pub fn parse_and_validate_reset_params(&self, s: &str) -> Result<ResetParams> {
+        let params = s.parse::<ResetParams>()?;
+        if let Some(network) = &params.network {
+            anyhow::ensure!(
+                self.networks.contains_key(network),
+                "no inventory found for the network `{network}`"
+            );
+        }
+        Ok(params)
+    }
+
+    pub async fn reset_network(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        mut params: ResetParams,
+    ) -> Result<()> {
+        struct ResetGuard<'a>(&'a NetworkDescr);
+
+        impl Drop for ResetGuard<'_> {
+            fn drop(&mut self) {
+                *self.0.reset_started_at.lock().unwrap() = None;
+                self.0.reset_running.store(false, Ordering::Relaxed);
+            }
+        }
+
+        if !self.check_auth(msg) {
+            bot.send_message(msg.chat.id, Reply::AccessDenied.to_string())
+                .reply_to(msg)
+                .await?;
+            return Ok(());
+        }
+
+        if self.shutdown.is_triggered() {
+            bot.send_message(msg.chat.id, "Bot is shutting down")
+                .reply_to(msg)
+                .await?;
+            return Ok(());
+        }
+
+        let network;
+        let descr;
+        let reset_type;
+        let pre_reset_snapshot;
+        'frozen: {
+            let config_env_prefix = self.settings.borrow().config_env_override_prefix.clone();
+            let frozen = {
+                let mut state_file = self.state_file.lock().unwrap();
+                reset_type = params
+                    .reset_type
+                    .unwrap_or_else(|| state_file.latest_data.current_reset_type());
+
+                if !params.explicit_commit {
+                    let data = &state_file.latest_data;
+                    if let Some(branch) = data
+                        .workspaces
+                        .get(&data.current_workspace_name())
+                        .and_then(|w| w.tracked_branch.clone())
+                    {
+                        params.commit = branch;
+                    }
+                }
+
+                network = params.network.clone().unwrap_or_else(|| {
+                    state_file
+                        .latest_data
+                        .current_network_name(&self.default_network)
+                        .to_owned()
+                });
+
+                descr = self
+                    .networks
+                    .get(&network)
+                    .with_context(|| format!("no inventory found for the network `{network}`"))?;
+
+                let Some(frozen) = state_file.latest_data.reset_frozen.get(&network) else {
+                    pre_reset_snapshot = self.pre_reset_snapshot()?;
+                    state_file.latest_data.apply_workspace_configs(
+                        &self.node_config_file,
+                        &self.logger_config_file,
+                        &self.zerostate_file,
+                        config_env_prefix.as_deref(),
+                    )?;
+                    state_file.save()?;
+                    break 'frozen;
+                };
+
+                if now_sec() >= frozen.timestamp_until {
+                    if let Some(notify) = self.unfreeze_notifies.lock().unwrap().remove(&network) {
+                        notify.abort();
+                    }
+
+                    // Unfreeze on timestamp reached
+                    state_file.latest_data.reset_frozen.remove(&network);
+                    pre_reset_snapshot = self.pre_reset_snapshot()?;
+                    state_file.latest_data.apply_workspace_configs(
+                        &self.node_config_file,
+                        &self.logger_config_file,
+                        &self.zerostate_file,
+                        config_env_prefix.as_deref(),
+                    )?;
+                    state_file.save()?;
+                    break 'frozen;
+                }
+
+                frozen.clone()
+            };
+
+            bot.send_message(msg.chat.id, Reply::ResetFrozen(frozen).to_string())
+                .reply_to(msg)
+                .await?;
+            return Ok(());
+        }
+
+        let _guard = {
+            if descr.reset_running.swap(true, Ordering::Relaxed) {
+                bot.send_message(msg.chat.id, "Reset is already running")
+                    .reply_to(msg)
+                    .await?;
+                return Ok(());
+            }
+
+            *descr.reset_started_at.lock().unwrap() = Some(Instant::now());
+            ResetGuard(descr)
+        };
+
+        #[derive(Clone, Copy)]
+        struct ReplyText<'a> {
+            network: &'a str,
+            commit_info: &'a CommitInfo,
+            reset_type: ResetType,
+            tags: Option<&'a str>,
+            skip_tags: Option<&'a str>,
+            ansible_timeout_secs: Option<u64>,
+            started_at: Instant,
+        }
+
+        impl<'a> ReplyText<'a> {
+            fn with_title<T: std::fmt::Display>(self, title: T) -> ReplyTextWithTitle<'a, T> {
+                ReplyTextWithTitle { title, body: self }
+            }
+        }
+
+        impl std::fmt::Display for ReplyText<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let elapsed_secs = self.started_at.elapsed().as_secs();
+                let duration = humantime::format_duration(Duration::from_secs(elapsed_secs));
+                writeln!(f, "🌐 Network: `{}`", self.network)?;
+                writeln!(f, "⏰ Elapsed: {duration}")?;
+                if let Some(timeout) = self.ansible_timeout_secs {
+                    let timeout = humantime::format_duration(Duration::from_secs(timeout));
+                    writeln!(f, "⏱️ Ansible timeout: {timeout}")?;
+                }
+                if let Some(tags) = self.tags {
+                    writeln!(f, "🏷️ Reset tags: `{tags}`")?;
+                }
+                if let Some(skip_tags) = self.skip_tags {
+                    writeln!(f, "🏷️ Skipped setup tags: `{skip_tags}`")?;
+                }
+                writeln!(
+                    f,
+                    "{} Reset type: *{}*\n",
+                    self.reset_type.as_emoji(),
+                    self.reset_type
+                )?;
+
+                let info = self.commit_info;
+
+                for line in info.message.lines() {
+                    writeln!(f, "> {line}")?;
+                }
+                write!(f, "Commit: `{}`", info.sha)?;
+                if let Some(ci_status) = info.ci_status {
+                    write!(f, " CI: {}", ci_status.as_emoji())?;
+                }
+                writeln!(f, "\n")?;
+
+                if !info.branches.is_empty() {
+                    let mut first = true;
+                    write!(f, "Branch: ")?;
+                    for name in &info.branches {
+                        write!(
+                            f,
+                            "{}`{name}`",
+                            if std::mem::take(&mut first) { "" } else { ", " }
+                        )?;
+                    }
+                    writeln!(f, "\n")?;
+                }
+
+                f.write_str(&info.html_url)
+            }
+        }
+
+        struct StartingTitle<'a>(&'a [ResetWarning]);
+
+        impl std::fmt::Display for StartingTitle<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("🔄 Starting network reset...")?;
+                for warning in self.0 {
+                    write!(f, "\n⚠️ {warning}")?;
+                }
+                Ok(())
+            }
+        }
+
+        struct ReplyTextWithTitle<'a, T> {
+            title: T,
+            body: ReplyText<'a>,
+        }
+
+        impl<T: std::fmt::Display> std::fmt::Display for ReplyTextWithTitle<'_, T> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                writeln!(f, "{}", self.title)?;
+                std::fmt::Display::fmt(&self.body, f)
+            }
+        }
+
+        trait LongReplyExt {
+            async fn reply_error(
+                &self,
+                body: ReplyText<'_>,
+                title: &str,
+                error: String,
+            ) -> Result<()>;
+
+            async fn reply_interrupted(&self, body: ReplyText<'_>, phase: &str) -> Result<()>;
+        }
+
+        impl LongReplyExt for LongReply {
+            async fn reply_error(
+                &self,
+                body: ReplyText<'_>,
+                title: &str,
+                error: String,
+            ) -> Result<()> {
+                // NOTE: Reply::Error escapes and truncates the cause,
+                // the full one is sent as a file
+                let reply = Reply::Error {
+                    context: format!("🟥 {title}"),
+                    cause: error,
+                };
+
+                self.update(body.with_title(format!("{reply}\n"))).await?;
+                if let Some(cause) = reply.truncated_error() {
+                    self.send_document("error.txt", cause.to_owned()).await?;
+                }
+
+                self.react(Emoji::Clown).await?;
+                Ok(())
+            }
+
+            async fn reply_interrupted(&self, body: ReplyText<'_>, phase: &str) -> Result<()> {
+                tracing::warn!("Reset interrupted by shutdown after {phase}");
+
+                let title = format!("🛑 Bot is shutting down. Reset interrupted after {phase}.");
+                self.update(body.with_title(title)).await?;
+                self.react(Emoji::Clown).await?;
+                Ok(())
+            }
+        }
+
+        let warnings = params.dry_validate(self, &network).await?;
+
+        let commit_info = self.get_commit_info(&params.commit).await?;
+        let started_at = now_sec();
+        let reply_body = ReplyText {
+            network: &network,
+            commit_info: &commit_info,
+            reset_type,
+            tags: params.tags.as_deref(),
+            skip_tags: params.skip_tags.as_deref(),
+            ansible_timeout_secs: descr.ansible_timeout_secs,
+            started_at: Instant::now(),
+        };
+
+        let r = self
+            .begin_long_reply(
+                bot,
+                msg,
+                reply_body.with_title(StartingTitle(&warnings)),
+                true,
+            )
+            .await?;
+
+        self.report_commit_status(&commit_info.sha, &network, CommitStatusState::Pending)
+            .await;
+
+        // NOTE: Any early error must not leave the commit status pending
+        let finished = std::cell::Cell::new(false);
+        let finish = |status| {
+            finished.set(true);
+            self.finish_reset(
+                &network,
+                &commit_info,
+                reset_type,
+                params.node_count,
+                started_at,
+                status,
+            )
+        };
+
+        let result: Result<()> = async {
+            // NOTE: The network is still running, so its config is used as a baseline
+            self.save_blockchain_config(&network, &descr.jrpc_client())
+                .await;
+
+            r.update(reply_body.with_title("🔄 Updating gate..."))
+                .await?;
+
+            let gate_update_output = self.run_gate_update().await?;
+            if !gate_update_output.status.success() {
+                let e = String::from_utf8_lossy(&gate_update_output.stderr).to_string();
+                tracing::error!("Gate update failed: {e}");
+
+                finish(CommitStatusState::Failure).await;
+                r.reply_error(reply_body, "Gate update failed", e).await?;
+                return Ok(());
+            }
+            if self.shutdown.is_triggered() {
+                finish(CommitStatusState::Error).await;
+                return r.reply_interrupted(reply_body, "gate update").await;
+            }
+
+            r.update(reply_body.with_title("🔄 Gate updated. Running reset playbook..."))
+                .await?;
+
+            let reset_output = self
+                .run_ansible_reset(
+                    &descr.inventory,
+                    &descr.ansible_config,
+                    &params.commit,
+                    reset_type,
+                    params.tags.as_deref(),
+                    descr.ansible_timeout_secs,
+                )
+                .await;
+            if !matches!(&reset_output, Ok(output) if output.status.success()) {
+                // NOTE: Config files could be left in an inconsistent state
+                if let Err(e) = self.restore_pre_reset_snapshot(&pre_reset_snapshot) {
+                    tracing::error!("Failed to restore config files: {e:?}");
+                }
+            }
+            let reset_output = reset_output?;
+            if !reset_output.status.success() {
+                let e = String::from_utf8_lossy(&reset_output.stdout).to_string();
+                tracing::error!("Reset playbook execution failed: {e}");
+
+                finish(CommitStatusState::Failure).await;
+                r.reply_error(reply_body, "Reset playbook execution failed", e)
+                    .await?;
+                return Ok(());
+            }
+            if self.shutdown.is_triggered() {
+                finish(CommitStatusState::Error).await;
+                return r.reply_interrupted(reply_body, "reset playbook").await;
+            }
+
+            r.update(reply_body.with_title("🔄 Reset completed. Running setup playbook..."))
+                .await?;
+
+            let setup_output = self
+                .run_ansible_setup(
+                    &descr.inventory,
+                    &descr.ansible_config,
+                    &params,
+                    descr.ansible_timeout_secs,
+                )
+                .await?;
+            if !setup_output.status.success() {
+                let e = String::from_utf8_lossy(&setup_output.stdout).to_string();
+                tracing::error!("Setup playbook execution failed: {e}");
+
+                finish(CommitStatusState::Failure).await;
+                r.reply_error(reply_body, "Setup playbook execution failed", e)
+                    .await?;
+                return Ok(());
+            }
+
+            r.update(reply_body.with_title("⏳ Verifying network startup..."))
+                .await?;
+
+            // NOTE: A network which is slow to start is not counted as a failed reset
+            let started = self
+                .verify_post_reset(&network, POST_RESET_VERIFY_TIMEOUT_SECS)
+                .await?;
+
+            // NOTE: Custom checks are informational and don't fail the reset
+            let has_custom_check = self.settings.borrow().post_reset_check_script.is_some();
+            let check_error = if has_custom_check {
+                r.update(reply_body.with_title("⏳ Running post-reset check..."))
+                    .await?;
+
+                match self.run_custom_check(&network).await {
+                    Ok(output) if output.status.success() => None,
+                    Ok(output) => {
+                        let mut log = String::from_utf8_lossy(&output.stdout).into_owned();
+                        log.push_str(&String::from_utf8_lossy(&output.stderr));
+                        tracing::warn!(network, "Post-reset check failed: {log}");
+                        Some(log)
+                    }
+                    Err(e) => {
+                        tracing::error!(network, "Post-reset check failed: {e:?}");
+                        Some(format!("{e:?}"))
+                    }
+                }
+            } else {
+                None
+            };
+
+            let link_preview = LinkPreviewOptions {
+                url: commit_info.html_url.clone(),
+            };
+
+            {
+                let mut state_file = self.state_file.lock().unwrap();
+                state_file.latest_data.last_commit_info = Some(commit_info.clone());
+                state_file.save()?;
+            }
+
+            finish(CommitStatusState::Success).await;
+
+            let mut title = if started {
+                "✅ Network reset completed successfully!".to_owned()
+            } else {
+                format!(
+                    "⚠️ Network reset completed, but the network did not start within {}",
+                    humantime::format_duration(Duration::from_secs(POST_RESET_VERIFY_TIMEOUT_SECS))
+                )
+            };
+            if check_error.is_some() {
+                title.push_str("\n⚠️ Post-reset check failed");
+            }
+            r.update(reply_body.with_title(title))
+                .link_preview_options(Some(link_preview))
+                .await?;
+            if let Some(log) = check_error.filter(|log| !log.trim().is_empty()) {
+                r.send_document("post_reset_check.txt", log).await?;
+            }
+
+            r.react(Emoji::Hotdog).await?;
+            Ok(())
+        }
+        .await;
+
+        if result.is_err() && !finished.get() {
+            finish(CommitStatusState::Error).await;
+        }
+        result
+    }
+
+    /// Polls the masterchain timings until the network produces a new block
+    /// and the last block time is close to the current one.
+    ///
+    /// Returns `false` if it did not happen within `max_wait_secs`.
+    pub async fn verify_post_reset(&self, network: &str, max_wait_secs: u64) -> Result<bool> {
+        const POLL_INTERVAL: Duration = Duration::from_secs(5);
+        const MAX_MC_TIME_DIFF: i64 = 30;
+
+        let descr = self
+            .networks
+            .get(network)
+            .with_context(|| format!("no inventory found for the network `{network}`"))?;
+
+        let mut initial_seqno = None;
+        let deadline = Instant::now() + Duration::from_secs(max_wait_secs);
+        loop {
+            match descr.jrpc_client().get_timings().await {
+                Ok(timings) => {
+                    let seqno = timings.last_mc_block_seqno;
+                    match initial_seqno {
+                        Some(initial) if seqno > initial => {
+                            if timings.mc_time_diff.abs() <= MAX_MC_TIME_DIFF {
+                                return Ok(true);
+                            }
+                            tracing::debug!(
+                                network,
+                                mc_time_diff = timings.mc_time_diff,
+                                "network is not synced yet"
+                            );
+                        }
+                        Some(_) => tracing::debug!(network, seqno, "no new blocks yet"),
+                        None => initial_seqno = Some(seqno),
+                    }
+                }
+                Err(e) => tracing::debug!(network, "network is not started yet: {e:?}"),
+            }
+
+            if Instant::now() + POLL_INTERVAL > deadline || self.shutdown.is_triggered() {
+                return Ok(false);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn finish_reset(
+        &self,
+        network: &str,
+        commit_info: &CommitInfo,
+        reset_type: ResetType,
+        node_count: usize,
+        started_at: u64,
+        status: CommitStatusState,
+    ) {
+        let entry = ResetHistoryEntry {
+            network: network.to_owned(),
+            commit: Some(commit_info.sha.clone()),
+            branches: commit_info.branches.clone(),
+            reset_type: Some(reset_type),
+            node_count: Some(node_count),
+            started_at,
+            finished_at: now_sec(),
+            success: matches!(status, CommitStatusState::Success),
+        };
+
+        {
+            let mut state_file = self.state_file.lock().unwrap();
+            let history = &mut state_file.latest_data.reset_history;
+            history.push(entry);
+            if history.len() > MAX_RESET_HISTORY_LEN {
+                let excess = history.len() - MAX_RESET_HISTORY_LEN;
+                history.drain(..excess);
+            }
+            if let Err(e) = state_file.save() {
+                tracing::error!("Failed to save state file: {e}");
+            }
+        }
+
+        self.report_commit_status(&commit_info.sha, network, status)
+            .await;
+    }
+
+    async fn report_commit_status(&self, sha: &str, network: &str, state: CommitStatusState) {
+        if !self.github_commit_status_enabled {
+            return;
+        }
+
+        let description = match state {
+            CommitStatusState::Pending => "Network reset is running",
+            CommitStatusState::Success => "Network reset completed",
+            CommitStatusState::Failure => "Network reset failed",
+            CommitStatusState::Error => "Network reset errored",
+        };
+
+        if let Err(e) = self
+            .github()
+            .post_commit_status(sha, state, description, network)
+            .await
+        {
+            tracing::error!("Failed to post commit status: {e:?}");
+        }
+    }
+
+    /// Runs the configured post-reset check script for the network.
+    pub async fn run_custom_check(&self, network: &str) -> Result<std::process::Output> {
+        const TIMEOUT: Duration = Duration::from_secs(300);
+
+        let script = self
+            .settings
+            .borrow()
+            .post_reset_check_script
+            .clone()
+            .context("post-reset check script is not configured")?;
+
+        tokio::time::timeout(
+            TIMEOUT,
+            tokio::process::Command::new("sh")
+                .arg(&script)
+                .env(POST_RESET_CHECK_NETWORK_ENV, network)
+                .kill_on_drop(true)
+                .output(),
+        )
+        .await
+        .context("post-reset check script timed out")?
+        .context("Failed to execute post-reset check script")
+    }
+
+    async fn run_gate_update(&self) -> Result<std::process::Output> {
+        tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg("gate update") // Replace with actual gate update command
+            .stdout(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::inherit())
+            .output()
+            .await
+            .context("Failed to execute gate update command")
+    }
+
+    async fn run_ansible_inventory_list(
+        &self,
+        inventory_path: &str,
+        ansible_config: &str,
+    ) -> Result<()> {
+        let output = tokio::process::Command::new("ansible-inventory")
+            .arg("--list")
+            .arg("-i")
+            .arg(inventory_path)
+            .env(ANSIBLE_CONFIG_ENV, ansible_config)
+            .output()
+            .await
+            .context("Failed to execute ansible-inventory")?;
+
+        anyhow::ensure!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        Ok(())
+    }
+
+    fn vault_password_file(&self) -> Result<Option<VaultPasswordFile>> {
+        self.ansible_vault
+            .as_ref()
+            .map(AnsibleVault::password_file)
+            .transpose()
+    }
+
+    async fn run_ansible_reset(
+        &self,
+        inventory_path: &str,
+        ansible_config: &str,
+        commit: &str,
+        reset_type: ResetType,
+        tags: Option<&str>,
+        timeout_secs: Option<u64>,
+    ) -> Result<std::process::Output> {
+        let restart_only = matches!(reset_type, ResetType::Restart);
+        let mut args = format!("tycho_commit={commit} restart_only={restart_only}");
+        if let Some(timeout) = timeout_secs {
+            args = format!("{args} DEFAULT_TIMEOUT={timeout}");
+        }
+
+        let vault = self.vault_password_file()?;
+
+        let mut command = tokio::process::Command::new("ansible-playbook");
+        command
+            .arg("-i")
+            .arg(inventory_path)
+            .arg(&self.settings.borrow().reset_playbook)
+            .arg("--extra-vars")
+            .arg(args);
+        if let Some(tags) = tags {
+            command.arg("--tags").arg(tags);
+        }
+        if let Some(timeout) = timeout_secs {
+            command.arg("--timeout").arg(timeout.to_string());
+        }
+        if let Some(vault) = &vault {
+            command.arg("--vault-password-file").arg(vault.path());
+        }
+
+        command
+            .stdout(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::inherit())
+            .env(ANSIBLE_CONFIG_ENV, ansible_config)
+            .output()
+            .await
+            .context("Failed to execute reset playbook")
+    }
+
+    async fn run_ansible_setup(
+        &self,
+        inventory_path: &str,
+        ansible_config: &str,
+        params: &ResetParams,
+        timeout_secs: Option<u64>,
+    ) -> Result<std::process::Output> {
+        let mut args = format!(
+            "tycho_commit={} tycho_build_profile={} n_nodes={}",
+            params.commit, params.build_profile, params.node_count,
+        );
+
+        // NOTE: The repository selected by `/setgithub` is used unless overridden
+        let repo = params.repo.clone().or_else(|| {
+            let github = self.github();
+            (github.repo_name() != self.default_github.repo_name()).then(|| github.clone_url())
+        });
+        if let Some(repo) = repo {
+            args = format!("{args} tycho_repo={repo}");
+        }
+        if let Some(timeout) = timeout_secs {
+            args = format!("{args} DEFAULT_TIMEOUT={timeout}");
+        }
+
+        let vault = self.vault_password_file()?;
+
+        let mut command = tokio::process::Command::new("ansible-playbook");
+        command
+            .arg("-i")
+            .arg(inventory_path)
+            .arg(&self.settings.borrow().setup_playbook)
+            .arg("--extra-vars")
+            .arg(args);
+        if let Some(skip_tags) = &params.skip_tags {
+            command.arg("--skip-tags").arg(skip_tags);
+        }
+        if let Some(timeout) = timeout_secs {
+            command.arg("--timeout").arg(timeout.to_string());
+        }
+        if let Some(vault) = &vault {
+            command.arg("--vault-password-file").arg(vault.path());
+        }
+
+        command
+            .stdout(std::process::Stdio::inherit())
+            .stderr(std::process::Stdio::inherit())
+            .env(ANSIBLE_CONFIG_ENV, ansible_config)
+            .output()
+            .await
+            .context("Failed to execute setup playbook")
+    }
+
+    pub fn set_node_config(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+        self.set_config_impl(ConfigType::Node, &self.node_config_file, expr)
+    }
+
+    pub fn get_node_config(&self, expr: &str) -> Result<Reply> {
+        self.get_config_impl(ConfigType::Node, &self.node_config_file, expr)
+            .map(Reply::NodeConfigParam)
+    }
+
+    pub fn set_logger_config(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+        self.set_config_impl(ConfigType::Logger, &self.logger_config_file, expr)
+    }
+
+    pub fn get_logger_config(&self, expr: &str) -> Result<Reply> {
+        self.get_config_impl(ConfigType::Logger, &self.logger_config_file, expr)
+            .map(Reply::LoggerConfigParam)
+    }
+
+    pub fn set_zerostate(&self, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+        self.set_config_impl(ConfigType::Zerostate, &self.zerostate_file, expr)
+    }
+
+    pub fn get_zerostate(&self, expr: &str) -> Result<Reply> {
+        self.get_config_impl(ConfigType::Zerostate, &self.zerostate_file, expr)
+            .map(Reply::ZerostateParam)
+    }
+
+    pub fn check_auth(&self, msg: &Message) -> bool {
+        // NOTE: Auth settings are applied on reload without a restart
+        let settings = self.settings.borrow();
+        !settings.authentication_enabled || settings.allowed_groups.contains(&msg.chat.id.0)
+    }
+
+    async fn get_commit_info(&self, commit: &str) -> Result<CommitInfo> {
+        let commit_sha = self.github().get_commit_sha(commit).await?;
+        let commit_info = self.github().get_commit_info(&commit_sha).await?;
+        let commit_branches = self.github().get_commit_branches(&commit_sha).await?;
+        let ci_status = self.fetch_ci_status(&commit_sha).await;
+
+        Ok(CommitInfo {
+            sha: commit_sha,
+            html_url: commit_info.html_url,
+            message: commit_info.message,
+            branches: commit_branches,
+            ci_status,
+        })
+    }
+
+    async fn fetch_ci_status(&self, sha: &str) -> Option<CiStatus> {
+        // NOTE: CI status is optional since the token may lack access to actions
+        match self.github().get_workflow_runs(sha).await {
+            Ok(runs) => CiStatus::from_runs(&runs),
+            Err(e) => {
+                tracing::warn!("failed to get workflow runs: {e:?}");
+                None
+            }
+        }
+    }
+
+    fn set_config_impl(&self, ty: ConfigType, path: &str, expr: &str) -> Result<Reply> {
+        let env_prefix = self.settings.borrow().config_env_override_prefix.clone();
+        let lookup = env_override_lookup(env_prefix.as_deref());
+
+        let mut state_file = self.state_file.lock().unwrap();
+        let env_overrides = state_file
+            .latest_data
+            .current_workspace_mut()
+            .env_overrides
+            .clone();
+
+        let object = state_file.latest_data.get_config_object(ty);
+        let mut config = match object {
+            Some(object) => Config::from_value(path, object.clone())?,
+            None => load_file_config(path, ty, &env_overrides, env_prefix.as_deref(), &lookup)?,
+        };
+
+        let (verbose, expr) = parse_verbose_flag(expr);
+        let value_path;
+        if let Some(rest) = expr.strip_prefix("rename") {
+            let (path, new_key) = rest
+                .trim()
+                .rsplit_once(char::is_whitespace)
+                .context("expected an expression: rename (.path)+ new_key")?;
+            value_path = path.trim();
+
+            let mut path = parse_config_value_path(path)?;
+            let Some(PathSegment::Key(old_key)) = path.pop() else {
+                anyhow::bail!("expected the path to end with an object key");
+            };
+            config.rename_key(&path, &old_key, new_key.trim())?;
+        } else if let Some(paths) = expr.strip_prefix("delete") {
+            value_path = paths.trim();
+
+            let paths = paths
+                .split(';')
+                .filter(|path| !path.trim().is_empty())
+                .map(|path| {
+                    let path = parse_config_value_path(path)?;
+                    anyhow::ensure!(!path.is_empty(), "cannot delete the config root");
+                    Ok(path)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            config.batch_remove(&paths)?;
+        } else {
+            let (path, value) = expr
+                .split_once('=')
+                .context("expected an expression: (.path)+ = json")?;
+            value_path = path.trim();
+
+            let path = parse_config_value_path(path)?;
+            let value = serde_json::from_str(value)?;
+            config.set(&path, value)?;
+        }
+
+        for rule in &self.config_validators {
+            if rule.config == ty {
+                config.register_validator(&rule.range.path, rule.range.to_validator())?;
+            }
+        }
+        config.validate_registered()?;
+
+        let new_object = config.as_object()?;
+        let full_config = if verbose {
+            Some(serde_json::to_string_pretty(&new_object)?)
+        } else {
+            None
+        };
+
+        // NOTE: The diff is computed before env overrides are applied
+        // so that their values are never sent to the chat.
+        let diff = config.diff()?;
+        apply_env_overrides(
+            &mut config,
+            ty,
+            &env_overrides,
+            env_prefix.as_deref(),
+            &lookup,
+        )?;
+
+        // NOTE: The file could be partially written if saving fails
+        let snapshot = config.snapshot()?;
+        if let Err(e) = config.save() {
+            if let Err(e) = snapshot.restore() {
+                tracing::error!("Failed to restore the config file: {e:?}");
+            }
+            return Err(e);
+        }
+        *object = Some(new_object);
+        if diff.is_empty() {
+            state_file.save()?;
+            return Ok(Reply::ConfigUnchanged {
+                path: value_path.to_owned(),
+            });
+        }
+        state_file.latest_data.current_workspace_mut().last_modified = now_sec();
+        state_file.save()?;
+
+        let updated = match ty {
+            ConfigType::Node => Reply::NodeConfigUpdated(diff),
+            ConfigType::Logger => Reply::LoggerConfigUpdated(diff),
+            ConfigType::Zerostate => Reply::ZerostateUpdated(diff),
+        };
+        Ok(match full_config {
+            Some(config) => Reply::ConfigUpdatedVerbose {
+                ty,
+                updated: Box::new(updated),
+                config,
+            },
+            None => updated,
+        })
+    }
+
+    fn get_config_impl(&self, ty: ConfigType, path: &str, expr: &str) -> Result<ConfigValue> {
+        let (toml, expr) = parse_toml_flag(expr);
+        let field_path = parse_config_value_path(expr)?;
+        let env_prefix = self.settings.borrow().config_env_override_prefix.clone();
+
+        let mut state_file = self.state_file.lock().unwrap();
+
+        let env_overrides = state_file
+            .latest_data
+            .current_workspace_mut()
+            .env_overrides
+            .clone();
+        let object = state_file.latest_data.get_config_object(ty);
+        let config = match object {
+            Some(object) => Config::from_value(path, object.clone())?,
+            None => {
+                let lookup = env_override_lookup(env_prefix.as_deref());
+                let config =
+                    load_file_config(path, ty, &env_overrides, env_prefix.as_deref(), lookup)?;
+                *object = Some(config.as_object()?);
+                state_file.save()?;
+                config
+            }
+        };
+
+        // Wildcards are collected by `get` since there is no single value to default
+        if field_path.contains(&PathSegment::Wildcard) {
+            let value = config.get(&field_path)?;
+            return ConfigValue::new(&value, false, toml);
+        }
+
+        let default = Value::Null;
+        let value = config.get_or_default(&field_path, &default);
+        ConfigValue::new(value, std::ptr::eq(value, &default), toml)
+    }
+
+    pub fn get_pending_changes(&self, ty: ConfigType) -> Result<Reply> {
+        let config = self.load_current_config(ty)?;
+        let diff = config.diff_with_disk()?;
+        Ok(Reply::PendingChanges { ty, diff })
+    }
+
+    /// Compares value types of the current config with the on-disk one.
+    pub fn check_config_types(&self, ty: ConfigType) -> Result<Reply> {
+        let config = self.load_current_config(ty)?;
+        let baseline = Config::from_file(self.config_file_path(ty))?;
+        let mismatches = config.list_type_mismatches(&baseline);
+        Ok(Reply::TypeMismatches { ty, mismatches })
+    }
+
+    /// Renames keys in all configs of the current workspace using a JSON map
+    /// of `{ "old.path": "new_key" }` from the argument or the replied document.
+    pub async fn migrate_config(&self, bot: &Bot, msg: &Message, expr: &str) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let content = match expr.trim() {
+            "" => {
+                let document = msg
+                    .reply_to_message()
+                    .and_then(|reply| reply.document())
+                    .context("pass a JSON map of renames or reply to a document with it")?;
+                let file = bot.get_file(document.file.id.clone()).await?;
+                let mut content = Vec::new();
+                bot.download_file(&file.path, &mut content).await?;
+                content
+            }
+            expr => expr.as_bytes().to_vec(),
+        };
+        let renames = serde_json::from_slice::<HashMap<String, String>>(&content)
+            .context("expected a JSON map of renames")?;
+
+        let env_prefix = self.settings.borrow().config_env_override_prefix.clone();
+        let lookup = env_override_lookup(env_prefix.as_deref());
+
+        let mut state_file = self.state_file.lock().unwrap();
+        let env_overrides = state_file
+            .latest_data
+            .current_workspace_mut()
+            .env_overrides
+            .clone();
+
+        // NOTE: Apply renames to all configs before saving any of them
+        let mut updated = Vec::new();
+        for ty in [ConfigType::Node, ConfigType::Logger, ConfigType::Zerostate] {
+            let path = self.config_file_path(ty);
+            let mut config = match state_file.latest_data.get_config_object(ty) {
+                Some(object) => Config::from_value(path, object.clone())?,
+                None => load_file_config(path, ty, &env_overrides, env_prefix.as_deref(), &lookup)?,
+            };
+            let renamed = config
+                .apply_rename_map(&renames)
+                .with_context(|| format!("failed to migrate the {ty} config"))?;
+            if !renamed.is_empty() {
+                updated.push((ty, config, renamed));
+            }
+        }
+
+        let mut migrated = Vec::new();
+        for (ty, mut config, renamed) in updated {
+            let new_object = config.as_object()?;
+            apply_env_overrides(
+                &mut config,
+                ty,
+                &env_overrides,
+                env_prefix.as_deref(),
+                &lookup,
+            )?;
+            config.save()?;
+            *state_file.latest_data.get_config_object(ty) = Some(new_object);
+            migrated.push((ty, renamed));
+        }
+        if !migrated.is_empty() {
+            state_file.latest_data.current_workspace_mut().last_modified = now_sec();
+            state_file.save()?;
+        }
+
+        Ok(Reply::ConfigMigrated { migrated })
+    }
+
+    pub fn find_config(&self, expr: &str) -> Result<Reply> {
+        let (ty, query) = expr
+            .trim()
+            .split_once(char::is_whitespace)
+            .context("expected `<node|logger|zerostate> <value>`")?;
+        let ty = ty.parse::<ConfigType>()?;
+
+        let config = self.load_current_config(ty)?;
+        let matches = config
+            .find(value_matcher(query))
+            .into_iter()
+            .map(|(path, value)| (path, value.to_string()))
+            .collect();
+
+        Ok(Reply::ConfigMatches { ty, matches })
+    }
+
+    /// Infers a JSON Schema of the current config and saves it next to the config
+    /// file or into the configured schema directory.
+    pub fn generate_schema(&self, msg: &Message, ty: ConfigType) -> Result<Reply> {
+        if !self.check_auth(msg) {
+            return Ok(Reply::AccessDenied);
+        }
+
+        let config = self.load_current_config(ty)?;
+        let schema = serde_json::to_string_pretty(&config.infer_json_schema())?;
+
+        let config_path = Path::new(self.config_file_path(ty));
+        let file_name = format!(
+            "{}.schema.json",
+            config_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("config.json")
+        );
+        let path = match &self.settings.borrow().config_schema_dir {
+            Some(dir) => Path::new(dir).join(&file_name),
+            None => config_path.with_file_name(&file_name),
+        };
+        std::fs::write(&path, &schema)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(Reply::ConfigSchema {
+            path: path.display().to_string(),
+            file_name,
+            schema,
+        })
+    }
+
+    fn config_file_path(&self, ty: ConfigType) -> &str {
+        match ty {
+            ConfigType::Node => &self.node_config_file,
+            ConfigType::Logger => &self.logger_config_file,
+            ConfigType::Zerostate => &self.zerostate_file,
+        }
+    }
+
+    /// Reads the current contents of all config files.
+    pub fn pre_reset_snapshot(&self) -> Result<HashMap<ConfigType, String>> {
+        [ConfigType::Node, ConfigType::Logger, ConfigType::Zerostate]
+            .into_iter()
+            .map(|ty| {
+                let path = self.config_file_path(ty);
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {path}"))?;
+                Ok((ty, content))
+            })
+            .collect()
+    }
+
+    /// Writes back config files saved by [`State::pre_reset_snapshot`].
+    pub fn restore_pre_reset_snapshot(&self, snapshot: &HashMap<ConfigType, String>) -> Result<()> {
+        for (ty, content) in snapshot {
+            let path = self.config_file_path(*ty);
+            std::fs::write(path, content).with_context(|| format!("failed to restore {path}"))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the current workspace config or the file one if it is not overridden.
+    fn load_current_config(&self, ty: ConfigType) -> Result<Config> {
+        let path = self.config_file_path(ty);
+
+        let mut state_file = self.state_file.lock().unwrap();
+        match state_file.latest_data.get_config_object(ty) {
+            Some(object) => Config::from_value(path, object.clone()),
+            None => Config::from_file(path),
+        }
+    }
+
+    pub fn audit_config_overrides(&self) -> Result<Reply> {
+        let state_file = self.state_file.lock().unwrap();
+
+        let mut workspaces = state_file.latest_data.workspaces.iter().collect::<Vec<_>>();
+        workspaces.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut entries = Vec::new();
+        for (name, workspace) in workspaces {
+            for (config_type, object, path) in [
+                (ConfigType::Node, &workspace.node, &self.node_config_file),
+                (
+                    ConfigType::Logger,
+                    &workspace.logger,
+                    &self.logger_config_file,
+                ),
+                (
+                    ConfigType::Zerostate,
+                    &workspace.zerostate,
+                    &self.zerostate_file,
+                ),
+            ] {
+                let Some(object) = object else {
+                    continue;
+                };
+
+                let diff = Config::from_value(path, object.clone())?.diff_with_disk()?;
+                if !diff.is_empty() {
+                    entries.push(AuditEntry {
+                        workspace: name.clone(),
+                        config_type,
+                        diff,
+                    });
+                }
+            }
+        }
+
+        Ok(Reply::ConfigAudit { entries })
+    }
+
+    /// Returns `true` if this command message was already handled recently.
+    pub fn is_duplicate_command(&self, msg: &Message) -> bool {
+        let key = ReplyKey::new(msg);
+        let duplicate = self
+            .sent_replies
+            .lock()
+            .unwrap()
+            .check(key, Instant::now())
+            .is_some();
+        if duplicate {
+            tracing::warn!(
+                "suppressed duplicate command message {} in chat {}",
+                msg.id.0,
+                msg.chat.id.0
+            );
+        }
+        duplicate
+    }
+
+    /// Starts a long reply, reusing the message sent by a previous attempt if any.
+    async fn begin_long_reply(
+        &self,
+        bot: Bot,
+        msg: &Message,
+        text: impl std::fmt::Display,
+        pinned: bool,
+    ) -> Result<LongReply> {
+        let key = ReplyKey::new(msg);
+        let existing = self
+            .sent_replies
+            .lock()
+            .unwrap()
+            .check(key.clone(), Instant::now())
+            .flatten();
+
+        if let Some(reply_msg_id) = existing {
+            let r = LongReply::from_existing(bot, msg, reply_msg_id);
+            r.update(text).await?;
+            return Ok(r);
+        }
+
+        let r = if pinned {
+            LongReply::begin_pinned(bot, msg, text).await?
+        } else {
+            LongReply::begin(bot, msg, text).await?
+        };
+        self.sent_replies
+            .lock()
+            .unwrap()
+            .set_reply_msg_id(&key, r.reply_msg_id);
+        Ok(r)
+    }
+
+    fn get_current_jrpc_client(&self) -> Result<JrpcClient> {
+        let state_file = self.state_file.lock().unwrap();
+        let network_name = state_file
+            .latest_data
+            .current_network_name(&self.default_network);
+        self.networks
+            .get(network_name)
+            .map(NetworkDescr::jrpc_client)
+            .with_context(|| format!("no JRPC client found for the network `{network_name}`"))
+    }
+
+    async fn watch_task(
+        self: Arc<Self>,
+        bot: Bot,
+        msg: Message,
+        interval: Duration,
+        command: Command,
+    ) {
+        struct WatchText {
+            interval: Duration,
+            response: Result<Reply>,
+        }
+
+        impl std::fmt::Display for WatchText {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let updated_at = humantime::format_rfc3339_seconds(std::time::SystemTime::now());
+                writeln!(
+                    f,
+                    "👀 Watching every {}, updated at {updated_at}\n",
+                    humantime::format_duration(self.interval)
+                )?;
+                match &self.response {
+                    Ok(reply) => std::fmt::Display::fmt(reply, f),
+                    Err(e) => {
+                        let reply = Reply::from_error("Failed to handle command", e);
+                        std::fmt::Display::fmt(&reply, f)
+                    }
+                }
+            }
+        }
+
+        let response = self.handle_read_only(&command).await;
+        let r = match LongReply::begin(bot, &msg, WatchText { interval, response }).await {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::error!("Failed to start watch: {e:?}");
+                return;
+            }
+        };
+
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let response = self.handle_read_only(&command).await;
+            if let Err(e) = r.update(WatchText { interval, response }).await {
+                tracing::warn!("Failed to update watch message: {e}");
+            }
+        }
+    }
+
+    async fn handle_read_only(&self, command: &Command) -> Result<Reply> {
+        match command {
+            Command::Status => self.get_status().await,
+            Command::Shards => self.get_shards().await,
+            Command::Account { address } => self.get_account(address).await,
+            Command::GetParam { params } => self.get_params(&params.0).await,
+            Command::GetCommit => self.get_saved_commit().await,
+            _ => anyhow::bail!("command is not read-only"),
+        }
+    }
+
+    /// Polls the giver balance in the default network and notifies its thread
+    /// when it drops below the threshold (and once again when it recovers).
+    async fn giver_monitor_task(self: Arc<Self>, bot: Bot) {
+        let Some(giver) = &self.giver else {
+            return;
+        };
+
+        // NOTE: The alert state is shared, so the balance is always checked
+        // in the same network regardless of the current one.
+        let network = &self.default_network;
+        let descr = &self.networks[network];
+
+        loop {
+            let poll_interval = self.settings.borrow().giver_poll_interval_secs;
+            tokio::time::sleep(Duration::from_secs(poll_interval)).await;
+            if self.shutdown.is_triggered() {
+                return;
+            }
+
+            let balance = match get_giver_balance(&descr.jrpc_client(), giver).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    tracing::warn!("failed to check giver wallet: {e:?}");
+                    continue;
+                }
+            };
+            let threshold = self.settings.borrow().min_giver_balance_tokens;
+            let is_low = balance.into_inner() < threshold;
+
+            // NOTE: The alert flag is not updated while paused,
+            // so the alert is sent once monitoring is resumed.
+            if self.monitoring_paused_until().is_some() {
+                continue;
+            }
+
+            {
+                let mut state_file = self.state_file.lock().unwrap();
+                if state_file.latest_data.giver_alert_sent == is_low {
+                    continue;
+                }
+                state_file.latest_data.giver_alert_sent = is_low;
+                if let Err(e) = state_file.save() {
+                    tracing::error!("Failed to save state file: {e}");
+                }
+            }
+
+            let reply = Reply::GiverBalanceAlert {
+                address: giver.address().clone(),
+                balance: DecimalTokens(balance),
+                threshold,
+                is_low,
+            };
+            self.send_network_alert(&bot, network, &reply.to_string())
+                .await;
+        }
+    }
+
+    /// Periodically rechecks JRPC endpoints of all networks and notifies
+    /// the network threads when their health changes.
+    async fn network_monitor_task(self: Arc<Self>, bot: Bot) {
+        loop {
+            let poll_interval = self.settings.borrow().network_poll_interval_secs;
+            tokio::time::sleep(Duration::from_secs(poll_interval)).await;
+            if self.shutdown.is_triggered() {
+                return;
+            }
+
+            let was_unhealthy = self.unhealthy_networks.lock().unwrap().clone();
+            let networks = match self.check_networks(None).await {
+                Ok(networks) => networks,
+                Err(e) => {
+                    tracing::warn!("failed to check networks: {e:?}");
+                    continue;
+                }
+            };
+
+            for (network, error) in networks {
+                if was_unhealthy.contains(&network) == error.is_some() {
+                    continue;
+                }
+
+                let reply = Reply::NetworkHealth(vec![(network.clone(), error)]);
+                self.send_network_alert(&bot, &network, &reply.to_string())
+                    .await;
+            }
+        }
+    }
+
+    async fn unfreeze_task(self: Arc<Self>, bot: Bot, network: String, duration: Duration) {
+        tokio::time::sleep(duration).await;
 
         let frozen = {
             let mut state_file = self.state_file.lock().unwrap();
@@ -938,13 +3727,279 @@ impl State {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+async fn query_account(
+    jrpc_client: &JrpcClient,
+    address: &StdAddr,
+) -> Result<(DecimalTokens, AccountStatus)> {
+    parse_account_state(jrpc_client.get_account(address).await?)
+}
+
+async fn fetch_block_seqno(client: &JrpcClient) -> Result<u32> {
+    let timings = client
+        .get_timings()
+        .await
+        .context("Failed to get block seqno")?;
+    Ok(timings.last_mc_block_seqno)
+}
+
+async fn get_giver_balance(jrpc_client: &JrpcClient, giver: &GiverWallet) -> Result<Tokens> {
+    match jrpc_client.get_account(giver.address()).await? {
+        jrpc_client::AccountStateResponse::Exists { account, .. } => Ok(account.balance.tokens),
+        jrpc_client::AccountStateResponse::NotExists { .. } => Ok(Default::default()),
+        jrpc_client::AccountStateResponse::Unchanged { .. } => {
+            anyhow::bail!("Unexpected response")
+        }
+    }
+}
+
+async fn query_accounts_concurrently(
+    jrpc_client: &JrpcClient,
+    addresses: &[StdAddr],
+) -> Vec<Result<(DecimalTokens, AccountStatus), String>> {
+    const MAX_CONCURRENT_REQUESTS: usize = 5;
+
+    let mut results = Vec::with_capacity(addresses.len());
+    results.resize_with(addresses.len(), || None);
+
+    let mut pending = addresses.iter().enumerate();
+    let mut futures = FuturesUnordered::new();
+    loop {
+        while futures.len() < MAX_CONCURRENT_REQUESTS {
+            let Some((i, address)) = pending.next() else {
+                break;
+            };
+            futures.push(async move { (i, query_account(jrpc_client, address).await) });
+        }
+
+        let Some((i, res)) = futures.next().await else {
+            break;
+        };
+        results[i] = Some(res.map_err(|e| e.to_string()));
+    }
+
+    results.into_iter().flatten().collect()
+}
+
+fn parse_account_state(
+    response: jrpc_client::AccountStateResponse,
+) -> Result<(DecimalTokens, AccountStatus)> {
+    match response {
+        jrpc_client::AccountStateResponse::NotExists { .. } => {
+            Ok((Default::default(), AccountStatus::NotExists))
+        }
+        jrpc_client::AccountStateResponse::Exists { account, .. } => {
+            let status = match account.state {
+                AccountState::Uninit => AccountStatus::Uninit,
+                AccountState::Active { .. } => AccountStatus::Active,
+                AccountState::Frozen { .. } => AccountStatus::Frozen,
+            };
+            Ok((DecimalTokens(account.balance.tokens), status))
+        }
+        jrpc_client::AccountStateResponse::Unchanged { .. } => {
+            anyhow::bail!("Unexpected response")
+        }
+    }
+}
+
+/// AES-256-GCM cipher for state exports. The nonce is prepended to the ciphertext.
+struct StateCipher(Aes256Gcm);
+
+impl StateCipher {
+    const NONCE_LEN: usize = 12;
+
+    fn new(key: &str) -> Result<Self> {
+        let key = HashBytes::from_str(key.trim()).context("invalid state export key")?;
+        let key = Key::<Aes256Gcm>::from_slice(key.as_slice());
+        Ok(Self(Aes256Gcm::new(key)))
+    }
+
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, data)
+            .map_err(|_| anyhow::anyhow!("failed to encrypt state"))?;
+
+        let mut result = nonce.to_vec();
+        result.extend_from_slice(&ciphertext);
+        Ok(result)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        anyhow::ensure!(data.len() > Self::NONCE_LEN, "encrypted state is too short");
+        let (nonce, ciphertext) = data.split_at(Self::NONCE_LEN);
+        self.0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::anyhow!("failed to decrypt state: invalid key or corrupted data"))
+    }
+}
+
+/// Recently sent replies, used to suppress duplicates on Telegram retries.
+#[derive(Default)]
+struct SentReplies {
+    entries: HashMap<ReplyKey, SentReply>,
+}
+
+impl SentReplies {
+    const WINDOW: Duration = Duration::from_secs(10);
+    const MAX_ENTRIES: usize = 1000;
+
+    /// Returns the previous entry if the key was seen within the window,
+    /// otherwise remembers the key.
+    fn check(&mut self, key: ReplyKey, now: Instant) -> Option<Option<MessageId>> {
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.last_used = now;
+            if now.saturating_duration_since(entry.sent_at) < Self::WINDOW {
+                return Some(entry.reply_msg_id);
+            }
+        }
+
+        if self.entries.len() >= Self::MAX_ENTRIES && !self.entries.contains_key(&key) {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(lru) = lru {
+                self.entries.remove(&lru);
+            }
+        }
+
+        self.entries.insert(
+            key,
+            SentReply {
+                sent_at: now,
+                last_used: now,
+                reply_msg_id: None,
+            },
+        );
+        None
+    }
+
+    fn set_reply_msg_id(&mut self, key: &ReplyKey, reply_msg_id: MessageId) {
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.reply_msg_id = Some(reply_msg_id);
+        }
+    }
+}
+
+/// Client-side idempotency key of a reply.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ReplyKey {
+    chat_id: ChatId,
+    message_id: MessageId,
+    command: String,
+}
+
+impl ReplyKey {
+    fn new(msg: &Message) -> Self {
+        let command = msg
+            .text()
+            .and_then(|text| text.split_whitespace().next())
+            .unwrap_or_default();
+
+        Self {
+            chat_id: msg.chat.id,
+            message_id: msg.id,
+            command: command.to_owned(),
+        }
+    }
+}
+
+struct SentReply {
+    sent_at: Instant,
+    last_used: Instant,
+    reply_msg_id: Option<MessageId>,
+}
+
+pub struct ShutdownToken {
+    tx: watch::Sender<bool>,
+}
+
+impl ShutdownToken {
+    fn new() -> Self {
+        Self {
+            tx: watch::Sender::new(false),
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.tx.borrow()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ConfigType {
     Logger,
     Node,
     Zerostate,
 }
 
+impl ConfigType {
+    const NODE: &'static str = "node";
+    const LOGGER: &'static str = "logger";
+    const ZEROSTATE: &'static str = "zerostate";
+}
+
+impl std::fmt::Display for ConfigType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Node => Self::NODE,
+            Self::Logger => Self::LOGGER,
+            Self::Zerostate => Self::ZEROSTATE,
+        })
+    }
+}
+
+impl FromStr for ConfigType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            Self::NODE => Ok(Self::Node),
+            Self::LOGGER => Ok(Self::Logger),
+            Self::ZEROSTATE => Ok(Self::Zerostate),
+            _ => anyhow::bail!("unknown config type, expected one of: node, logger, zerostate"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ConfigValidatorRule {
+    config: ConfigType,
+    #[serde(flatten)]
+    range: IntegerRange,
+}
+
+impl ConfigValidatorRule {
+    fn defaults() -> Vec<Self> {
+        let node_range = |path: &str, min, max| Self {
+            config: ConfigType::Node,
+            range: IntegerRange {
+                path: path.to_owned(),
+                min,
+                max,
+            },
+        };
+
+        vec![
+            node_range(".server.port", Some(1), Some(65535)),
+            node_range(".database.max_connections", Some(1), None),
+        ]
+    }
+
+    fn load(path: &str) -> Result<Vec<Self>> {
+        let content =
+            std::fs::read_to_string(path).context("failed to read config validators file")?;
+        serde_json::from_str(&content).context("failed to parse config validators file")
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ConfigSource {
     Unchanged,
@@ -954,14 +4009,89 @@ pub enum ConfigSource {
 
 const ANSIBLE_CONFIG_ENV: &str = "ANSIBLE_CONFIG";
 
+enum AnsibleVault {
+    PasswordFile(PathBuf),
+    /// Inline password which is written to a temp file for each command.
+    Password(String),
+}
+
+impl AnsibleVault {
+    fn password_file(&self) -> Result<VaultPasswordFile> {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let password = match self {
+            Self::PasswordFile(path) => {
+                return Ok(VaultPasswordFile {
+                    path: path.clone(),
+                    temporary: false,
+                })
+            }
+            Self::Password(password) => password,
+        };
+
+        let nonce = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path =
+            std::env::temp_dir().join(format!("tychonet-vault-{}-{nonce}", std::process::id()));
+
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+            .context("failed to create vault password file")?;
+
+        // Remove the file even if writing fails
+        let guard = VaultPasswordFile {
+            path,
+            temporary: true,
+        };
+        file.write_all(password.as_bytes())
+            .context("failed to write vault password file")?;
+
+        Ok(guard)
+    }
+}
+
+/// Path to the vault password file, removed on drop if it was temporary.
+struct VaultPasswordFile {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl VaultPasswordFile {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for VaultPasswordFile {
+    fn drop(&mut self) {
+        if self.temporary {
+            if let Err(e) = std::fs::remove_file(&self.path) {
+                tracing::error!("failed to remove vault password file: {e}");
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ResetParams {
     pub commit: String,
+    /// Whether the commit was specified instead of using the default one.
+    pub explicit_commit: bool,
     pub node_count: usize,
     pub build_profile: String,
     pub repo: Option<String>,
     pub reset_type: Option<ResetType>,
     pub network: Option<String>,
+    /// Comma-separated tags passed to the reset playbook as `--tags`.
+    pub tags: Option<String>,
+    /// Comma-separated tags passed to the setup playbook as `--skip-tags`.
+    pub skip_tags: Option<String>,
 }
 
 impl ResetParams {
@@ -970,10 +4100,126 @@ impl ResetParams {
     const PARAM_BUILD_PROFILE: &'static str = "profile";
     const PARAM_RESET_TYPE: &'static str = "type";
     const PARAM_NETWORK: &'static str = "network";
+    const PARAM_TAGS: &'static str = "tags";
+    const PARAM_SKIP_TAGS: &'static str = "skip_tags";
 
     const DEFAULT_COMMIT: &'static str = "master";
     const DEFAULT_NODE_COUNT: usize = 13;
     const DEFAULT_BUILD_PROFILE: &'static str = "release";
+
+    /// Normalizes a comma-separated list of ansible tags.
+    fn parse_tags(value: &str) -> Result<String> {
+        let tags = value
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| {
+                anyhow::ensure!(
+                    tag.chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')),
+                    "invalid tag: {tag}"
+                );
+                Ok(tag)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        anyhow::ensure!(!tags.is_empty(), "no tags specified");
+        Ok(tags.join(","))
+    }
+
+    /// Collects problems which don't block the reset but are worth noticing.
+    pub async fn dry_validate(&self, state: &State, network: &str) -> Result<Vec<ResetWarning>> {
+        const TIMEOUT: Duration = Duration::from_secs(5);
+
+        let descr = state
+            .networks
+            .get(network)
+            .with_context(|| format!("no inventory found for the network `{network}`"))?;
+
+        let mut warnings = Vec::new();
+
+        // NOTE: Unreachable network is not a warning since it's a common reason to reset
+        if let Ok(Ok(timings)) =
+            tokio::time::timeout(TIMEOUT, descr.jrpc_client().get_timings()).await
+        {
+            if timings.mc_time_diff.abs() > SLOW_NETWORK_MC_TIME_DIFF {
+                warnings.push(ResetWarning::SlowNetwork {
+                    mc_time_diff: timings.mc_time_diff,
+                });
+            }
+        }
+
+        if self.build_profile == "debug"
+            && state
+                .settings
+                .borrow()
+                .production_networks
+                .iter()
+                .any(|name| name == network)
+        {
+            warnings.push(ResetWarning::DebugBuild);
+        }
+
+        let prev_node_count = {
+            let state_file = state.state_file.lock().unwrap();
+            state_file.latest_data.last_reset_node_count(network)
+        };
+        if let Some(prev) = prev_node_count {
+            if prev != self.node_count {
+                warnings.push(ResetWarning::NodeCountChanged {
+                    prev,
+                    new: self.node_count,
+                });
+            }
+        }
+
+        Ok(warnings)
+    }
+}
+
+/// Masterchain time lag after which the network is considered slow.
+const SLOW_NETWORK_MC_TIME_DIFF: i64 = 60;
+/// Masterchain time lag up to which the network is considered synced.
+const SYNCED_MC_TIME_DIFF: i64 = 10;
+
+/// Qualitative assessment of the masterchain time lag.
+struct SyncHealth(i64);
+
+impl std::fmt::Display for SyncHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let diff = self.0.abs();
+        let lag = humantime::format_duration(Duration::from_secs(diff as u64));
+        if diff <= SYNCED_MC_TIME_DIFF {
+            write!(f, "✅ synced")
+        } else if diff <= SLOW_NETWORK_MC_TIME_DIFF {
+            write!(f, "⚠️ slightly behind ({lag})")
+        } else {
+            write!(f, "🔴 severely behind ({lag})")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResetWarning {
+    SlowNetwork { mc_time_diff: i64 },
+    DebugBuild,
+    NodeCountChanged { prev: usize, new: usize },
+}
+
+impl std::fmt::Display for ResetWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SlowNetwork { mc_time_diff } => {
+                write!(f, "Network is lagging: mc time diff is {mc_time_diff}s")
+            }
+            Self::DebugBuild => write!(f, "Debug build for a production network"),
+            Self::NodeCountChanged { prev, new } => {
+                write!(
+                    f,
+                    "Node count changed since the last reset: {prev} -> {new}"
+                )
+            }
+        }
+    }
 }
 
 impl FromStr for ResetParams {
@@ -986,6 +4232,8 @@ impl FromStr for ResetParams {
         let mut build_profile = Self::DEFAULT_BUILD_PROFILE.to_string();
         let mut reset_type = None::<ResetType>;
         let mut network = None::<String>;
+        let mut tags = None::<String>;
+        let mut skip_tags = None::<String>;
 
         for item in s.split(';') {
             match item.split_once('=') {
@@ -1004,18 +4252,23 @@ impl FromStr for ResetParams {
                     Self::PARAM_BUILD_PROFILE => value.trim().clone_into(&mut build_profile),
                     Self::PARAM_RESET_TYPE => reset_type = Some(value.trim().parse()?),
                     Self::PARAM_NETWORK => network = Some(value.trim().to_owned()),
+                    Self::PARAM_TAGS => tags = Some(Self::parse_tags(value)?),
+                    Self::PARAM_SKIP_TAGS => skip_tags = Some(Self::parse_tags(value)?),
                     param => anyhow::bail!("unknown param: {param}"),
                 },
             }
         }
 
         Ok(Self {
+            explicit_commit: commit.is_some(),
             commit: commit.unwrap_or_else(|| Self::DEFAULT_COMMIT.to_owned()),
             node_count,
             repo,
             build_profile,
             reset_type,
             network,
+            tags,
+            skip_tags,
         })
     }
 }
@@ -1023,34 +4276,256 @@ impl FromStr for ResetParams {
 struct StateFile {
     path: PathBuf,
     latest_data: StateFileData,
+    backups: Option<StateBackups>,
 }
 
 impl StateFile {
     pub fn load(path: &str) -> Result<Self> {
         let path = Path::new(path);
         let latest_data = if path.exists() {
-            let content = std::fs::read_to_string(path).context("failed to read state file")?;
-            serde_json::from_str(&content).context("failed to parse state file")?
+            let content = std::fs::read(path).context("failed to read state file")?;
+            StateFileData::from_slice(&content).context("failed to parse state file")?
         } else {
-            StateFileData::default()
+            StateFileData {
+                schema_version: STATE_SCHEMA_VERSION,
+                ..Default::default()
+            }
         };
 
         Ok(Self {
             path: path.to_owned(),
             latest_data,
+            backups: None,
         })
     }
 
+    pub fn with_backups(mut self, dir: &str, max_count: usize) -> Self {
+        self.backups = Some(StateBackups {
+            dir: PathBuf::from(dir),
+            max_count,
+        });
+        self
+    }
+
+    /// Copies the current on-disk state into the backup directory.
+    pub fn backup(&self) -> Result<Option<PathBuf>> {
+        let Some(backups) = &self.backups else {
+            return Ok(None);
+        };
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        std::fs::create_dir_all(&backups.dir).context("failed to create backup dir")?;
+
+        let timestamp = humantime::format_rfc3339_millis(std::time::SystemTime::now())
+            .to_string()
+            .replace(':', "-");
+        let target = backups.dir.join(format!(
+            "{BACKUP_FILE_PREFIX}{timestamp}{BACKUP_FILE_SUFFIX}"
+        ));
+        std::fs::copy(&self.path, &target).context("failed to backup state file")?;
+
+        backups.cleanup()?;
+        Ok(Some(target))
+    }
+
+    /// Trims the reset history to the newest `max_history` entries.
+    ///
+    /// NOTE: The state is not saved.
+    pub fn compact(&mut self, max_history: usize) -> CompactionReport {
+        let size_before = self.latest_data.serialized_len();
+
+        let history = &mut self.latest_data.reset_history;
+        let entries_removed = history.len().saturating_sub(max_history);
+        history.drain(..entries_removed);
+
+        CompactionReport {
+            entries_removed,
+            bytes_freed: size_before.saturating_sub(self.latest_data.serialized_len()),
+        }
+    }
+
     pub fn save(&self) -> Result<()> {
+        if let Err(e) = self.backup() {
+            tracing::error!("Failed to backup state file: {e:?}");
+        }
+
         let content = serde_json::to_string_pretty(&self.latest_data)
             .context("failed to serialize state file")?;
         std::fs::write(&self.path, content).context("failed to write state file")
     }
 }
 
-#[derive(Default, Debug, Serialize, Deserialize)]
+const BACKUP_FILE_PREFIX: &str = "state_";
+const BACKUP_FILE_SUFFIX: &str = ".json";
+
+struct StateBackups {
+    dir: PathBuf,
+    max_count: usize,
+}
+
+impl StateBackups {
+    /// Returns backups sorted from the oldest to the newest.
+    fn list(&self) -> Result<Vec<BackupInfo>> {
+        let mut backups = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).context("failed to read backup dir")? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !is_backup_file_name(&name) {
+                continue;
+            }
+
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                backups.push(BackupInfo {
+                    name,
+                    size: metadata.len(),
+                });
+            }
+        }
+
+        backups.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(backups)
+    }
+
+    fn cleanup(&self) -> Result<()> {
+        let backups = self.list()?;
+        if backups.len() <= self.max_count {
+            return Ok(());
+        }
+
+        let excess = backups.len() - self.max_count;
+        for backup in &backups[..excess] {
+            std::fs::remove_file(self.dir.join(&backup.name))
+                .with_context(|| format!("failed to remove backup {}", backup.name))?;
+        }
+        Ok(())
+    }
+
+    fn load(&self, name: &str) -> Result<StateFileData> {
+        anyhow::ensure!(
+            is_backup_file_name(name) && !name.contains(['/', '\\']),
+            "invalid backup name: `{name}`"
+        );
+
+        let content = std::fs::read_to_string(self.dir.join(name))
+            .with_context(|| format!("failed to read backup `{name}`"))?;
+        serde_json::from_str(&content).context("failed to parse backup")
+    }
+}
+
+fn is_backup_file_name(name: &str) -> bool {
+    name.starts_with(BACKUP_FILE_PREFIX) && name.ends_with(BACKUP_FILE_SUFFIX)
+}
+
+pub struct BackupInfo {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnsibleConnectivityReport {
+    pub reachable: Vec<String>,
+    pub unreachable: Vec<String>,
+}
+
+impl AnsibleConnectivityReport {
+    /// Parses `<host> | <STATUS> ...` lines of `ansible -m ping --one-line`.
+    fn from_ping_output(output: &str) -> Self {
+        let mut report = Self::default();
+        for line in output.lines() {
+            let Some((host, status)) = line.split_once(" | ") else {
+                continue;
+            };
+            let host = host.trim().to_owned();
+            if status.starts_with("SUCCESS") || status.starts_with("CHANGED") {
+                report.reachable.push(host);
+            } else if status.starts_with("UNREACHABLE") || status.starts_with("FAILED") {
+                report.unreachable.push(host);
+            }
+        }
+        report
+    }
+
+    fn is_empty(&self) -> bool {
+        self.reachable.is_empty() && self.unreachable.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MetricSample {
+    value: f64,
+    labels: HashMap<String, String>,
+}
+
+impl MetricSample {
+    /// Finds the first sample of the metric in the prometheus text format.
+    /// Counters are also matched by the name without the `_total` suffix.
+    fn find(text: &str, name: &str) -> Option<Self> {
+        Self::find_exact(text, name).or_else(|| Self::find_exact(text, &format!("{name}_total")))
+    }
+
+    /// Parses `<name>[{<label>="<value>",...}] <value> [timestamp]` lines.
+    fn find_exact(text: &str, name: &str) -> Option<Self> {
+        text.lines().find_map(|line| {
+            let rest = line.trim().strip_prefix(name)?;
+            let (labels, rest) = match rest.strip_prefix('{') {
+                Some(rest) => parse_metric_labels(rest)?,
+                None if rest.starts_with(char::is_whitespace) => (HashMap::new(), rest),
+                None => return None,
+            };
+            let value = rest.split_whitespace().next()?.parse().ok()?;
+            Some(Self { value, labels })
+        })
+    }
+}
+
+/// Parses labels after the opening brace, returns them with the rest of the line.
+fn parse_metric_labels(s: &str) -> Option<(HashMap<String, String>, &str)> {
+    let mut labels = HashMap::new();
+    let mut rest = s.trim_start();
+    loop {
+        if let Some(rest) = rest.strip_prefix('}') {
+            return Some((labels, rest));
+        }
+
+        let (key, value_part) = rest.split_once('=')?;
+        let value_part = value_part.trim_start().strip_prefix('"')?;
+
+        let mut value = String::new();
+        let mut chars = value_part.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (i, '"') => break i,
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    c => value.push(c),
+                },
+                (_, c) => value.push(c),
+            }
+        };
+        labels.insert(key.trim().to_owned(), value);
+
+        rest = value_part[end + 1..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionReport {
+    pub entries_removed: usize,
+    pub bytes_freed: usize,
+}
+
+/// Version of the [`StateFileData`] layout, bumped on incompatible changes.
+const STATE_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 struct StateFileData {
+    /// Zero for files written before versioning was introduced.
+    schema_version: u32,
     last_commit_info: Option<CommitInfo>,
     reset_frozen: HashMap<String, ResetFrozen>,
     #[serde(default)]
@@ -1059,30 +4534,67 @@ struct StateFileData {
     current_workspace: Option<String>,
     #[serde(default)]
     workspaces: HashMap<String, Workspace>,
+    #[serde(default)]
+    reset_history: Vec<ResetHistoryEntry>,
+    /// Whether the low giver balance alert was sent and not yet recovered.
+    #[serde(default)]
+    giver_alert_sent: bool,
+    /// Blockchain config saved before the last reset.
+    #[serde(default)]
+    last_blockchain_config: Option<BlockchainConfigSnapshot>,
+    /// JRPC endpoints set with `/setrpcurl`, take precedence over the settings.
+    #[serde(default)]
+    rpc_url_overrides: HashMap<String, String>,
 }
 
 impl StateFileData {
+    /// Size of the state as it is written to the file.
+    fn serialized_len(&self) -> usize {
+        serde_json::to_vec_pretty(self).map_or(0, |data| data.len())
+    }
+
+    fn from_slice(data: &[u8]) -> Result<Self> {
+        let mut data = serde_json::from_slice::<Self>(data).context("invalid state data")?;
+        anyhow::ensure!(
+            data.schema_version <= STATE_SCHEMA_VERSION,
+            "unsupported state schema version {} (expected at most {STATE_SCHEMA_VERSION})",
+            data.schema_version
+        );
+        data.schema_version = STATE_SCHEMA_VERSION;
+        Ok(data)
+    }
+
     fn apply_workspace_configs(
         &mut self,
         node_path: &str,
         logger_path: &str,
         zerostate_path: &str,
+        config_env_prefix: Option<&str>,
     ) -> Result<()> {
+        let env_overrides = self.current_workspace_mut().env_overrides.clone();
+        let lookup = env_override_lookup(config_env_prefix);
         for (ty, path) in [
             (ConfigType::Node, node_path),
             (ConfigType::Logger, logger_path),
             (ConfigType::Zerostate, zerostate_path),
         ] {
             let object = self.get_config_object(ty);
-            match object {
-                Some(object) => {
-                    let config = Config::from_value(path, object.clone())?;
-                    config.save()?;
-                }
+            let (mut config, changed) = match object {
+                Some(object) => (Config::from_value(path, object.clone())?, true),
                 None => {
-                    let config = Config::from_file(path)?;
+                    let config =
+                        load_file_config(path, ty, &env_overrides, config_env_prefix, &lookup)?;
                     *object = Some(config.as_object()?);
+                    (config, false)
                 }
+            };
+
+            // NOTE: Env overrides are applied after the object is stored
+            // so that their values are written only to the config file.
+            let overridden =
+                apply_env_overrides(&mut config, ty, &env_overrides, config_env_prefix, &lookup)?;
+            if changed || overridden {
+                config.save()?;
             }
         }
         Ok(())
@@ -1095,6 +4607,12 @@ impl StateFileData {
             .get_config_object(ty)
     }
 
+    fn current_workspace_mut(&mut self) -> &mut Workspace {
+        self.workspaces
+            .entry(self.current_workspace_name())
+            .or_default()
+    }
+
     fn current_workspace_name(&self) -> String {
         self.current_workspace
             .as_deref()
@@ -1109,6 +4627,208 @@ impl StateFileData {
             .and_then(|w| w.network.as_deref())
             .unwrap_or(default_network)
     }
+
+    /// Summary of the named workspace, missing ones are reported with defaults.
+    fn workspace_info(&self, name: &str, default_network: &str) -> WorkspaceInfo {
+        let workspace = self.workspaces.get(name);
+        let network = workspace
+            .and_then(|w| w.network.clone())
+            .unwrap_or_else(|| default_network.to_owned());
+
+        let mut overrides = Vec::new();
+        if let Some(workspace) = workspace {
+            for (ty, object) in [
+                (ConfigType::Node, &workspace.node),
+                (ConfigType::Logger, &workspace.logger),
+                (ConfigType::Zerostate, &workspace.zerostate),
+            ] {
+                if object.is_some() {
+                    overrides.push(ty);
+                }
+            }
+        }
+
+        WorkspaceInfo {
+            is_current: name == self.current_workspace_name(),
+            frozen: self.reset_frozen.contains_key(&network),
+            last_modified: workspace.map(|w| w.last_modified).filter(|&at| at > 0),
+            reset_type: workspace.and_then(|w| w.default_reset_type),
+            tracked_branch: workspace.and_then(|w| w.tracked_branch.clone()),
+            name: name.to_owned(),
+            network,
+            overrides,
+        }
+    }
+
+    /// Workspace reset type with a fallback to the global one.
+    fn current_reset_type(&self) -> ResetType {
+        let current_workspace = self.current_workspace_name();
+        self.workspaces
+            .get(&current_workspace)
+            .and_then(|w| w.default_reset_type)
+            .unwrap_or(self.reset_type)
+    }
+
+    /// Node count of the last successful reset of the network.
+    fn last_reset_node_count(&self, network: &str) -> Option<usize> {
+        self.reset_history
+            .iter()
+            .rev()
+            .find(|e| e.network == network && e.success)
+            .and_then(|e| e.node_count)
+    }
+}
+
+const MAX_RESET_HISTORY_LEN: usize = 1000;
+
+/// Network name passed to the post-reset check script.
+const POST_RESET_CHECK_NETWORK_ENV: &str = "TYCHONET_NETWORK";
+/// How long to wait for the network to start after the setup playbook.
+const POST_RESET_VERIFY_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResetHistoryEntry {
+    pub network: String,
+    pub commit: Option<String>,
+    pub branches: Vec<String>,
+    pub reset_type: Option<ResetType>,
+    pub node_count: Option<usize>,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub success: bool,
+}
+
+impl ResetHistoryEntry {
+    fn duration(&self) -> Option<Duration> {
+        if self.started_at == 0 || self.finished_at < self.started_at {
+            return None;
+        }
+        Some(Duration::from_secs(self.finished_at - self.started_at))
+    }
+
+    fn commit_name(&self) -> Option<String> {
+        match self.branches.first() {
+            Some(branch) => Some(branch.clone()),
+            None => self
+                .commit
+                .as_ref()
+                .map(|sha| sha.chars().take(7).collect()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BotInfo {
+    pub workspace: String,
+    pub network: String,
+    pub reset_type: ResetType,
+    pub commit: Option<String>,
+    pub frozen_networks: Vec<String>,
+    pub workspace_count: usize,
+    pub network_count: usize,
+    pub last_reset_at: Option<u64>,
+}
+
+pub struct HealthReport {
+    pub networks: Vec<NetworkHealth>,
+    pub last_reset: Option<ResetHistoryEntry>,
+    /// `None` if the giver is not configured.
+    pub giver_balance: Option<Result<DecimalTokens, String>>,
+    pub min_giver_balance: u128,
+    /// See [`State::pause_monitoring`].
+    pub monitoring_paused_until: Option<u64>,
+}
+
+pub struct NetworkStatus {
+    pub name: String,
+    pub current: bool,
+    /// `None` if the network didn't respond in time.
+    pub timings: Option<StateTimings>,
+    pub frozen: bool,
+    pub reset_running: bool,
+}
+
+pub struct NetworkHealth {
+    pub name: String,
+    pub is_current: bool,
+    pub timings: Result<StateTimings, String>,
+    pub frozen_until: Option<u64>,
+    /// Whether JRPC requests to the network are skipped until /rechecknetwork.
+    pub jrpc_unhealthy: bool,
+    pub reset_running: bool,
+}
+
+pub struct ResetStats {
+    pub total: usize,
+    pub successful: usize,
+    pub avg_duration: Option<Duration>,
+    pub top_commit: Option<(String, usize)>,
+    pub top_reset_type: Option<(ResetType, usize)>,
+    /// `(network, total, successful)` sorted by network name.
+    pub networks: Vec<(String, usize, usize)>,
+}
+
+impl ResetStats {
+    fn from_history(history: &[ResetHistoryEntry]) -> Self {
+        fn most_used<K: Ord + Clone>(counts: HashMap<K, usize>) -> Option<(K, usize)> {
+            counts
+                .into_iter()
+                .max_by(|(a_key, a), (b_key, b)| a.cmp(b).then_with(|| b_key.cmp(a_key)))
+        }
+
+        let mut commits = HashMap::<String, usize>::new();
+        let mut reset_types = HashMap::<ResetType, usize>::new();
+        let mut networks = HashMap::<&str, (usize, usize)>::new();
+        let mut successful = 0;
+        let mut total_duration = Duration::ZERO;
+        let mut timed = 0u32;
+
+        for entry in history {
+            if entry.success {
+                successful += 1;
+            }
+            if let Some(duration) = entry.duration() {
+                total_duration += duration;
+                timed += 1;
+            }
+            if let Some(commit) = entry.commit_name() {
+                *commits.entry(commit).or_default() += 1;
+            }
+            if let Some(reset_type) = entry.reset_type {
+                *reset_types.entry(reset_type).or_default() += 1;
+            }
+
+            let network = networks.entry(entry.network.as_str()).or_default();
+            network.0 += 1;
+            if entry.success {
+                network.1 += 1;
+            }
+        }
+
+        let mut networks = networks
+            .into_iter()
+            .map(|(name, (total, successful))| (name.to_owned(), total, successful))
+            .collect::<Vec<_>>();
+        networks.sort_unstable();
+
+        Self {
+            total: history.len(),
+            successful,
+            avg_duration: (timed > 0).then(|| total_duration / timed),
+            top_commit: most_used(commits),
+            top_reset_type: most_used(reset_types),
+            networks,
+        }
+    }
+}
+
+fn success_rate(successful: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        successful as f64 * 100.0 / total as f64
+    }
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -1121,10 +4841,84 @@ struct Workspace {
     logger: Option<JsonObject>,
     #[serde(default)]
     zerostate: Option<JsonObject>,
+    /// Unix timestamp of the last config change.
+    #[serde(default)]
+    last_modified: u64,
+    /// Overrides the global reset type for this workspace.
+    #[serde(default)]
+    default_reset_type: Option<ResetType>,
+    /// Branch used by resets without an explicit commit.
+    #[serde(default)]
+    tracked_branch: Option<String>,
+    /// Head of the tracked branch at the last refresh.
+    #[serde(default)]
+    tracked_commit: Option<CommitInfo>,
+    /// Config paths (`node.api.key`) filled from environment variables on apply.
+    #[serde(default)]
+    env_overrides: HashMap<String, String>,
 }
 
 impl Workspace {
-    fn preload(&mut self, node_path: &str, logger_path: &str, zerostate_path: &str) -> Result<()> {
+    /// Checks present configs with the validator rules and against value types
+    /// of the config files. Returns errors of the broken ones.
+    fn validate_configs(
+        &self,
+        node_path: &str,
+        logger_path: &str,
+        zerostate_path: &str,
+        validators: &[ConfigValidatorRule],
+    ) -> Result<Vec<String>> {
+        let mut errors = Vec::new();
+        for (ty, path) in [
+            (ConfigType::Node, node_path),
+            (ConfigType::Logger, logger_path),
+            (ConfigType::Zerostate, zerostate_path),
+        ] {
+            let Some(object) = self.config_object(ty) else {
+                continue;
+            };
+            let mut config = match Config::from_value(path, object.clone()) {
+                Ok(config) => config,
+                Err(e) => {
+                    errors.push(format!("{ty} config: {e:#}"));
+                    continue;
+                }
+            };
+
+            for rule in validators {
+                if rule.config == ty {
+                    config.register_validator(&rule.range.path, rule.range.to_validator())?;
+                }
+            }
+            if let Err(e) = config.validate_registered() {
+                errors.push(format!("{ty} config: {e:#}"));
+            }
+
+            let baseline = Config::from_file(path)?;
+            for m in config.list_type_mismatches(&baseline) {
+                // NOTE: Null values are left by env overrides
+                if m.current_type == json_type_name(&Value::Null) {
+                    continue;
+                }
+                errors.push(format!(
+                    "{ty} config: `{}` is {} on disk, {} in the workspace",
+                    m.path, m.baseline_type, m.current_type
+                ));
+            }
+        }
+        Ok(errors)
+    }
+
+    /// Loads missing configs from files.
+    fn preload(
+        &mut self,
+        node_path: &str,
+        logger_path: &str,
+        zerostate_path: &str,
+        config_env_prefix: Option<&str>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<()> {
+        let env_overrides = self.env_overrides.clone();
         for (ty, path) in [
             (ConfigType::Node, node_path),
             (ConfigType::Logger, logger_path),
@@ -1132,39 +4926,404 @@ impl Workspace {
         ] {
             let object = self.get_config_object(ty);
             if object.is_none() {
-                let config = Config::from_file(path)?;
+                let config =
+                    load_file_config(path, ty, &env_overrides, config_env_prefix, &lookup)?;
                 *object = Some(config.as_object()?);
             }
         }
-        Ok(())
-    }
+        Ok(())
+    }
+
+    /// Fills missing fields of the loaded node config from `defaults`.
+    fn apply_node_defaults(&mut self, defaults: Option<&serde_json::Value>) -> Result<()> {
+        let (Some(object), Some(defaults)) = (&mut self.node, defaults) else {
+            return Ok(());
+        };
+
+        let mut config = Config::from_value("", object.clone())?;
+        let added = config.apply_defaults(defaults);
+        if !added.is_empty() {
+            tracing::info!(?added, "applied node config defaults");
+            *object = config.as_object()?;
+        }
+        Ok(())
+    }
+
+    /// Copies values at the specified paths from the source workspace.
+    ///
+    /// The first path segment selects the config (`node`, `logger` or `zerostate`),
+    /// the rest is the path inside it. Both workspaces must be preloaded.
+    fn merge_from(&mut self, source: &Workspace, keys: &[Vec<PathSegment>]) -> Result<()> {
+        for key in keys {
+            let Some((PathSegment::Key(ty), path)) = key.split_first() else {
+                anyhow::bail!("config type expected in `{}`", format_copy_key(key));
+            };
+            let ty = ty.parse::<ConfigType>()?;
+
+            let source_object = source
+                .config_object(ty)
+                .with_context(|| format!("source {ty} config is not loaded"))?;
+            let value = Config::from_value("", source_object.clone())?
+                .get(path)
+                .with_context(|| format!("failed to copy `{}`", format_copy_key(key)))?
+                .into_owned();
+
+            let object = self
+                .get_config_object(ty)
+                .as_mut()
+                .with_context(|| format!("{ty} config is not loaded"))?;
+            let mut config = Config::from_value("", object.clone())?;
+            config
+                .set(path, value)
+                .with_context(|| format!("failed to copy `{}`", format_copy_key(key)))?;
+            *object = config.as_object()?;
+        }
+        Ok(())
+    }
+
+    /// Returns paths of all leaf values of the loaded configs,
+    /// each prefixed with the config type as expected by [`Workspace::merge_from`].
+    ///
+    /// Only non-empty objects are descended into, so arrays and empty objects
+    /// are copied as a whole instead of element by element.
+    fn leaf_keys(&self) -> Vec<Vec<PathSegment>> {
+        fn collect(
+            object: &JsonObject,
+            key: &mut Vec<PathSegment>,
+            keys: &mut Vec<Vec<PathSegment>>,
+        ) {
+            for (name, value) in object {
+                key.push(PathSegment::Key(name.clone()));
+                match value {
+                    Value::Object(inner) if !inner.is_empty() => collect(inner, key, keys),
+                    _ => keys.push(key.clone()),
+                }
+                key.pop();
+            }
+        }
+
+        let mut keys = Vec::new();
+        for ty in [ConfigType::Node, ConfigType::Logger, ConfigType::Zerostate] {
+            if let Some(object) = self.config_object(ty) {
+                collect(
+                    object,
+                    &mut vec![PathSegment::Key(ty.to_string())],
+                    &mut keys,
+                );
+            }
+        }
+        keys
+    }
+
+    fn config_object(&self, ty: ConfigType) -> Option<&JsonObject> {
+        match ty {
+            ConfigType::Node => self.node.as_ref(),
+            ConfigType::Logger => self.logger.as_ref(),
+            ConfigType::Zerostate => self.zerostate.as_ref(),
+        }
+    }
+
+    fn get_config_object(&mut self, ty: ConfigType) -> &mut Option<JsonObject> {
+        match ty {
+            ConfigType::Node => &mut self.node,
+            ConfigType::Logger => &mut self.logger,
+            ConfigType::Zerostate => &mut self.zerostate,
+        }
+    }
+}
+
+/// Parses a `copy_keys` path like `node.mempool`.
+fn parse_copy_key(key: &str) -> Result<Vec<PathSegment>> {
+    let path = if key.starts_with(['.', '[']) {
+        parse_config_value_path(key)?
+    } else {
+        parse_config_value_path(&format!(".{key}"))?
+    };
+    match path.first() {
+        Some(PathSegment::Key(ty)) => {
+            ty.parse::<ConfigType>()?;
+        }
+        _ => anyhow::bail!("config type expected in `{key}`"),
+    }
+    Ok(path)
+}
+
+/// Sets config values from environment variables of the workspace `env_overrides`
+/// and from `<config_env_prefix>_<TYPE>_CONFIG_*` variables.
+/// Returns whether any value was changed.
+///
+/// Must only be used for configs written for ansible, never for stored objects.
+fn apply_env_overrides(
+    config: &mut Config,
+    ty: ConfigType,
+    env_overrides: &HashMap<String, String>,
+    config_env_prefix: Option<&str>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<bool> {
+    let mut env_overrides = env_overrides.iter().collect::<Vec<_>>();
+    env_overrides.sort_unstable();
+
+    let mut changed = false;
+    for (key, var) in env_overrides {
+        let path = parse_copy_key(key)?;
+        let Some((PathSegment::Key(key_ty), path)) = path.split_first() else {
+            continue;
+        };
+        if key_ty.parse::<ConfigType>()? != ty {
+            continue;
+        }
+
+        if config
+            .set_path_from_env(path, var, &lookup)
+            .with_context(|| format!("failed to override `{key}`"))?
+        {
+            changed = true;
+        } else {
+            tracing::warn!(key, var, "env override skipped, variable is not set");
+        }
+    }
+
+    if let Some(prefix) = config_env_prefix {
+        let paths = config.apply_env_overrides(&config_type_env_prefix(prefix, ty))?;
+        if !paths.is_empty() {
+            tracing::info!(?paths, "applied {ty} config overrides");
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+fn config_type_env_prefix(prefix: &str, ty: ConfigType) -> String {
+    format!("{prefix}_{}", ty.to_string().to_uppercase())
+}
+
+/// Loads the config file without the values of env overrides (see [`apply_env_overrides`])
+/// which are set, so that they never get into the state file.
+fn load_file_config(
+    path: &str,
+    ty: ConfigType,
+    env_overrides: &HashMap<String, String>,
+    config_env_prefix: Option<&str>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Config> {
+    let mut config = Config::from_file(path)?;
+
+    // NOTE: The file could have been written with overrides already.
+    // Values are nulled instead of removed to keep array indices and key names.
+    if let Some(prefix) = config_env_prefix {
+        for path in config.env_override_paths(&config_type_env_prefix(prefix, ty))? {
+            if config.get(&path).is_ok() {
+                config.set(&path, Value::Null)?;
+            }
+        }
+    }
+
+    for (key, var) in env_overrides {
+        let path = parse_copy_key(key)?;
+        let Some((PathSegment::Key(key_ty), path)) = path.split_first() else {
+            continue;
+        };
+        // NOTE: The file value is kept if there is nothing to replace it with
+        if key_ty.parse::<ConfigType>()? == ty && lookup(var).is_some() {
+            config
+                .remove(path)
+                .with_context(|| format!("failed to strip the `{key}` override"))?;
+        }
+    }
+
+    // NOTE: The initial value is reset so that diffs don't include the stripped values
+    Config::from_value(path, config.as_object()?)
+}
+
+/// Returns the lookup of env override variables which ignores
+/// the ones rejected by [`check_env_override_var`].
+fn env_override_lookup(prefix: Option<&str>) -> impl Fn(&str) -> Option<String> + '_ {
+    move |var| match check_env_override_var(var, prefix) {
+        Ok(()) => std::env::var(var).ok(),
+        Err(e) => {
+            tracing::warn!(var, "env override ignored: {e:#}");
+            None
+        }
+    }
+}
+
+/// Env overrides can only read dedicated `<CONFIG_ENV_OVERRIDE_PREFIX>_SECRET_*`
+/// variables, so that bot settings (tokens, keys) are never copied into configs.
+fn check_env_override_var(var: &str, prefix: Option<&str>) -> Result<()> {
+    let prefix = prefix.context("env overrides require CONFIG_ENV_OVERRIDE_PREFIX to be set")?;
+    let name = var
+        .strip_prefix(prefix)
+        .and_then(|name| name.strip_prefix("_SECRET_"));
+    anyhow::ensure!(
+        matches!(name, Some(name) if !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')),
+        "env override variable must match `{prefix}_SECRET_*`"
+    );
+    anyhow::ensure!(
+        !settings::is_settings_var(var),
+        "`{var}` is a bot settings variable"
+    );
+    Ok(())
+}
+
+fn sorted_env_overrides(workspace: &Workspace) -> Vec<String> {
+    let mut keys = workspace.env_overrides.keys().cloned().collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys
+}
+
+/// Formats a `copy_keys` path as `node.mempool`.
+fn format_copy_key(key: &[PathSegment]) -> String {
+    let key = key.iter().map(ToString::to_string).collect::<String>();
+    match key.strip_prefix('.') {
+        Some(key) => key.to_owned(),
+        None => key,
+    }
+}
+
+/// Strips the trailing `--toml` flag from the config query expression.
+fn parse_toml_flag(expr: &str) -> (bool, &str) {
+    let expr = expr.trim();
+    match expr.strip_suffix("--toml") {
+        Some(rest) if rest.is_empty() || rest.ends_with(char::is_whitespace) => {
+            (true, rest.trim_end())
+        }
+        _ => (false, expr),
+    }
+}
+
+/// Strips the leading `--verbose` flag from the config edit expression.
+fn parse_verbose_flag(expr: &str) -> (bool, &str) {
+    let expr = expr.trim();
+    match expr.strip_prefix("--verbose") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            (true, rest.trim_start())
+        }
+        _ => (false, expr),
+    }
+}
+
+type JsonObject = serde_json::Map<String, serde_json::Value>;
+
+struct SetWorkspaceParams {
+    workspace: String,
+    copy_from: Option<String>,
+    copy_keys: Vec<Vec<PathSegment>>,
+    /// An empty branch stops tracking.
+    tracked_branch: Option<String>,
+}
+
+impl SetWorkspaceParams {
+    const PARAM_COPY_FROM: &'static str = "copy_from";
+    const PARAM_COPY_KEYS: &'static str = "copy_keys";
+    const PARAM_BRANCH: &'static str = "branch";
+
+    fn parse_copy_keys(value: &str) -> Result<Vec<Vec<PathSegment>>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(parse_copy_key)
+            .collect()
+    }
+}
+
+impl FromStr for SetWorkspaceParams {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut workspace = None;
+        let mut copy_from = None::<String>;
+        let mut copy_keys = Vec::new();
+        let mut tracked_branch = None;
+
+        for item in s.split(';') {
+            match item.split_once('=') {
+                None => {
+                    let item = item.trim();
+                    if item.is_empty() {
+                        continue;
+                    }
+
+                    anyhow::ensure!(workspace.is_none(), "invalid param: {item}");
+                    workspace = Some(item.trim().to_owned());
+                }
+                Some((param, value)) => match param.trim() {
+                    Self::PARAM_COPY_FROM => copy_from = Some(value.trim().to_owned()),
+                    Self::PARAM_COPY_KEYS => copy_keys = Self::parse_copy_keys(value)?,
+                    Self::PARAM_BRANCH => tracked_branch = Some(value.trim().to_owned()),
+                    param => anyhow::bail!("unknown param: {param}"),
+                },
+            }
+        }
+
+        anyhow::ensure!(
+            copy_keys.is_empty() || copy_from.is_some(),
+            "`{}` requires `{}`",
+            Self::PARAM_COPY_KEYS,
+            Self::PARAM_COPY_FROM
+        );
+
+        Ok(Self {
+            workspace: workspace.context("workspace name expected")?,
+            copy_from,
+            copy_keys,
+            tracked_branch,
+        })
+    }
+}
+
+pub struct CloneWorkspaceParams {
+    pub source: String,
+    pub dest: String,
+    pub overwrite: bool,
+}
+
+impl CloneWorkspaceParams {
+    const FLAG_OVERWRITE: &'static str = "--overwrite";
+}
+
+impl FromStr for CloneWorkspaceParams {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut names = Vec::with_capacity(2);
+        let mut overwrite = false;
+        for item in s.split_whitespace() {
+            if item == Self::FLAG_OVERWRITE {
+                overwrite = true;
+            } else {
+                anyhow::ensure!(!item.starts_with("--"), "unknown flag: {item}");
+                names.push(item.to_owned());
+            }
+        }
 
-    fn get_config_object(&mut self, ty: ConfigType) -> &mut Option<JsonObject> {
-        match ty {
-            ConfigType::Node => &mut self.node,
-            ConfigType::Logger => &mut self.logger,
-            ConfigType::Zerostate => &mut self.zerostate,
-        }
+        let [source, dest] = <[String; 2]>::try_from(names)
+            .map_err(|_| anyhow::anyhow!("expected `<source> <dest> [--overwrite]`"))?;
+
+        Ok(Self {
+            source,
+            dest,
+            overwrite,
+        })
     }
 }
 
-type JsonObject = serde_json::Map<String, serde_json::Value>;
-
-struct SetWorkspaceParams {
-    workspace: String,
-    copy_from: Option<String>,
+pub struct CreateFromTemplateParams {
+    pub workspace: String,
+    pub template: String,
 }
 
-impl SetWorkspaceParams {
-    const PARAM_COPY_FROM: &'static str = "copy_from";
+impl CreateFromTemplateParams {
+    const PARAM_TEMPLATE: &'static str = "template";
 }
 
-impl FromStr for SetWorkspaceParams {
+impl FromStr for CreateFromTemplateParams {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut workspace = None;
-        let mut copy_from = None::<String>;
+        let mut template = None;
 
         for item in s.split(';') {
             match item.split_once('=') {
@@ -1175,10 +5334,10 @@ impl FromStr for SetWorkspaceParams {
                     }
 
                     anyhow::ensure!(workspace.is_none(), "invalid param: {item}");
-                    workspace = Some(item.trim().to_owned());
+                    workspace = Some(item.to_owned());
                 }
                 Some((param, value)) => match param.trim() {
-                    Self::PARAM_COPY_FROM => copy_from = Some(value.trim().to_owned()),
+                    Self::PARAM_TEMPLATE => template = Some(value.trim().to_owned()),
                     param => anyhow::bail!("unknown param: {param}"),
                 },
             }
@@ -1186,15 +5345,38 @@ impl FromStr for SetWorkspaceParams {
 
         Ok(Self {
             workspace: workspace.context("workspace name expected")?,
-            copy_from,
+            template: template.with_context(|| format!("`{}` expected", Self::PARAM_TEMPLATE))?,
         })
     }
 }
 
+/// Loads a partial workspace from the template file.
+fn load_workspace_template(path: &str) -> Result<Workspace> {
+    let data = std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+    serde_json::from_str(&data).with_context(|| format!("failed to parse {path}"))
+}
+
 const DEFAULT_WORKSPACE: &str = "default";
 
+const WORKSPACE_CALLBACK_PREFIX: &str = "ws:";
+const NEW_WORKSPACE_CALLBACK: &str = "ws_new";
+const IMPORT_CONFIRM_CALLBACK: &str = "import:confirm";
+const IMPORT_CANCEL_CALLBACK: &str = "import:cancel";
+const PR_CALLBACK_PREFIX: &str = "pr:";
+
+#[derive(Debug, Clone, Copy)]
+enum PendingInput {
+    WorkspaceName,
+}
+
 struct SetNetworkParams {
     network: String,
+    /// Skips the JRPC connectivity check.
+    force: bool,
+}
+
+impl SetNetworkParams {
+    const FLAG_FORCE: &'static str = "--force";
 }
 
 impl FromStr for SetNetworkParams {
@@ -1202,17 +5384,20 @@ impl FromStr for SetNetworkParams {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut network = None;
+        let mut force = false;
 
         for item in s.split(';') {
             match item.split_once('=') {
                 None => {
-                    let item = item.trim();
-                    if item.is_empty() {
-                        continue;
-                    }
+                    for word in item.split_whitespace() {
+                        if word == Self::FLAG_FORCE {
+                            force = true;
+                            continue;
+                        }
 
-                    anyhow::ensure!(network.is_none(), "invalid param: {item}");
-                    network = Some(item.trim().to_owned());
+                        anyhow::ensure!(network.is_none(), "invalid param: {word}");
+                        network = Some(word.to_owned());
+                    }
                 }
                 Some((param, _)) => anyhow::bail!("unknown param: {}", param.trim()),
             }
@@ -1220,6 +5405,7 @@ impl FromStr for SetNetworkParams {
 
         Ok(Self {
             network: network.context("network name expected")?,
+            force,
         })
     }
 }
@@ -1230,11 +5416,33 @@ pub struct CommitInfo {
     pub html_url: String,
     pub message: String,
     pub branches: Vec<String>,
+    /// Combined status of workflow runs when the commit was fetched.
+    #[serde(default)]
+    pub ci_status: Option<CiStatus>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FrozenEntry {
+    pub network: String,
+    pub workspace: Option<String>,
+    pub reason: Option<String>,
+    pub remaining_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlockchainConfigSnapshot {
+    network: String,
+    /// Key block seqno of the config.
+    seqno: u32,
+    params: serde_json::Value,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResetFrozen {
     pub network: String,
+    /// Workspace which was current when the reset was frozen.
+    #[serde(default)]
+    pub workspace: Option<String>,
     pub reason: Option<String>,
     pub timestamp_until: u64,
 
@@ -1243,23 +5451,9 @@ pub struct ResetFrozen {
     pub message_thread_id: Option<ThreadId>,
 }
 
-fn parse_config_value_path(s: &str) -> Result<Vec<String>> {
-    let s = s.trim();
-    if s.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    let s = s.strip_prefix('.').unwrap_or(s);
-    s.split('.')
-        .map(|item| {
-            let item = item.trim();
-            anyhow::ensure!(!item.is_empty(), "empty path items are not allowed");
-            Ok(item.to_owned())
-        })
-        .collect()
-}
-
-#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub enum ResetType {
     #[default]
     Full,
@@ -1267,9 +5461,18 @@ pub enum ResetType {
 }
 
 impl ResetType {
+    pub const ALL_TYPES: &'static [Self] = &[Self::Full, Self::Restart];
+
     const FULL: &'static str = "full";
     const RESTART: &'static str = "restart";
 
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Full => "Full: tears down all data and redeploys from scratch",
+            Self::Restart => "Restart: restarts nodes without wiping chain data",
+        }
+    }
+
     fn as_emoji(&self) -> &'static str {
         match self {
             Self::Full => "💣",
@@ -1305,9 +5508,13 @@ struct LongReply {
     original_msg_id: MessageId,
     reply_msg_id: MessageId,
     reply_thread_id: Option<ThreadId>,
+    pinned: bool,
 }
 
 impl LongReply {
+    /// Telegram allows up to 4096 chars, leave some room for entities.
+    const MAX_LEN: usize = 3900;
+
     async fn begin(bot: Bot, msg: &Message, text: impl std::fmt::Display) -> Result<Self> {
         let chat_id = msg.chat.id;
         let reply = bot
@@ -1322,9 +5529,37 @@ impl LongReply {
             original_msg_id: msg.id,
             reply_msg_id: reply.id,
             reply_thread_id: reply.thread_id,
+            pinned: false,
         })
     }
 
+    /// Same as [`LongReply::begin`] but also pins the reply until it is dropped.
+    ///
+    /// Falls back to an unpinned reply if the bot is not allowed to pin messages.
+    async fn begin_pinned(bot: Bot, msg: &Message, text: impl std::fmt::Display) -> Result<Self> {
+        let mut r = Self::begin(bot, msg, text).await?;
+
+        let mut req = r.bot.pin_chat_message(r.chat_id, r.reply_msg_id);
+        req.disable_notification = Some(true);
+        match req.await {
+            Ok(_) => r.pinned = true,
+            Err(e) => tracing::warn!("failed to pin the reply, continuing unpinned: {e}"),
+        }
+
+        Ok(r)
+    }
+
+    fn from_existing(bot: Bot, msg: &Message, reply_msg_id: MessageId) -> Self {
+        Self {
+            bot,
+            chat_id: msg.chat.id,
+            original_msg_id: msg.id,
+            reply_msg_id,
+            reply_thread_id: msg.thread_id,
+            pinned: false,
+        }
+    }
+
     fn update(
         &self,
         text: impl std::fmt::Display,
@@ -1333,7 +5568,7 @@ impl LongReply {
             inner: teloxide::payloads::EditMessageText::new(
                 self.chat_id,
                 self.reply_msg_id,
-                text.to_string(),
+                truncate_middle(&text.to_string(), Self::MAX_LEN).into_owned(),
             ),
             link_preview_options: None,
         };
@@ -1367,27 +5602,146 @@ impl LongReply {
     }
 }
 
+impl Drop for LongReply {
+    fn drop(&mut self) {
+        if !self.pinned {
+            return;
+        }
+
+        let mut req = self.bot.unpin_chat_message(self.chat_id);
+        req.message_id = Some(self.reply_msg_id);
+        tokio::spawn(async move {
+            if let Err(e) = req.await {
+                tracing::warn!("failed to unpin the reply: {e}");
+            }
+        });
+    }
+}
+
+pub struct ConfigValue {
+    pub text: String,
+    /// Code block language of the text, `json` or `toml`.
+    pub format: &'static str,
+    /// JSON type of the value, e.g. `number`.
+    pub value_type: &'static str,
+    pub is_default: bool,
+    /// Whether TOML was requested but the value has no TOML representation.
+    pub toml_fallback: bool,
+}
+
+impl ConfigValue {
+    fn new(value: &Value, is_default: bool, toml: bool) -> Result<Self> {
+        let toml_text = if toml { to_toml_display(value) } else { None };
+        let (text, format) = match toml_text {
+            Some(text) => (text.trim_end().to_owned(), "toml"),
+            None => (serde_json::to_string_pretty(value)?, "json"),
+        };
+        Ok(Self {
+            toml_fallback: toml && format == "json",
+            text,
+            format,
+            value_type: json_type_name(value),
+            is_default,
+        })
+    }
+}
+
+impl std::fmt::Display for ConfigValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.value_type {
+            "array" | "object" => write!(
+                f,
+                "{}:\n```{}\n{}\n```",
+                self.value_type, self.format, self.text
+            )?,
+            _ => write!(f, "{}: `{}`", self.value_type, self.text)?,
+        }
+        if self.toml_fallback {
+            write!(f, "\n(no TOML representation, shown as JSON)")?;
+        }
+        if self.is_default {
+            write!(f, "\n(using default)")?;
+        }
+        Ok(())
+    }
+}
+
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub network: String,
+    pub overrides: Vec<ConfigType>,
+    pub frozen: bool,
+    pub last_modified: Option<u64>,
+    /// Reset type override of the workspace.
+    pub reset_type: Option<ResetType>,
+    pub tracked_branch: Option<String>,
+    pub is_current: bool,
+}
+
+pub struct AuditEntry {
+    pub workspace: String,
+    pub config_type: ConfigType,
+    pub diff: ConfigDiff,
+}
+
 pub enum Reply {
-    Timings(StateTimings),
+    Error {
+        context: String,
+        cause: String,
+    },
+    Seqno(u32),
+    Timings {
+        timings: StateTimings,
+        shards: Option<ShardClientInfo>,
+    },
+    Shards(ShardClientInfo),
+    Elections(ElectionStatus),
+    CommitComparison(CompareResult),
+    CiStatus {
+        sha: String,
+        runs: Vec<WorkflowRun>,
+    },
     Commit(CommitInfo),
-    Workspaces {
-        current: String,
-        workspaces: Vec<String>,
+    WorkspacesDetailed(Vec<WorkspaceInfo>),
+    WorkspaceInfo(WorkspaceInfo),
+    /// Sent as a document by the handler.
+    ConfigSchema {
+        path: String,
+        file_name: String,
+        schema: String,
     },
-    Networks {
-        current: String,
-        networks: Vec<String>,
+    NetworkUnreachable {
+        network: String,
+        reason: String,
     },
+    WorkspaceCommitsRefreshed(Vec<(String, CommitInfo)>),
+    NetworkSummary(Vec<NetworkStatus>),
+    Accounts(Vec<(StdAddr, Result<(DecimalTokens, AccountStatus), String>)>),
     Account {
         address: StdAddr,
         balance: DecimalTokens,
         status: AccountStatus,
     },
-    ConfigParam {
+    AccountVerified {
+        address: StdAddr,
+        proof_root_hash: HashBytes,
+        proof_consistent: bool,
+    },
+    AccountProof {
+        address: StdAddr,
+        state_hash: HashBytes,
+        proof_hash: HashBytes,
+    },
+    ConfigParams {
         global_id: i32,
         seqno: u32,
-        value: Value,
-        param: i32,
+        values: Vec<(i32, Value)>,
+    },
+    BlockchainConfigChanges {
+        network: String,
+        baseline_seqno: u32,
+        seqno: u32,
+        diff: ConfigDiff,
     },
     Freeze {
         network: String,
@@ -1395,16 +5749,59 @@ pub enum Reply {
     Unfreeze {
         network: String,
     },
+    FreezeList {
+        entries: Vec<FrozenEntry>,
+    },
+    ConfigMatches {
+        ty: ConfigType,
+        matches: Vec<(String, String)>,
+    },
+    TypeMismatches {
+        ty: ConfigType,
+        mismatches: Vec<TypeMismatch>,
+    },
+    ConfigMigrated {
+        migrated: Vec<(ConfigType, Vec<String>)>,
+    },
+    PendingChanges {
+        ty: ConfigType,
+        diff: ConfigDiff,
+    },
     NodeConfigUpdated(ConfigDiff),
-    NodeConfigParam(String),
+    NodeConfigParam(ConfigValue),
     LoggerConfigUpdated(ConfigDiff),
-    LoggerConfigParam(String),
+    LoggerConfigParam(ConfigValue),
     ZerostateUpdated(ConfigDiff),
-    ZerostateParam(String),
+    /// Config update with the full resulting config attached.
+    ConfigUpdatedVerbose {
+        ty: ConfigType,
+        updated: Box<Reply>,
+        config: String,
+    },
+    ConfigUnchanged {
+        path: String,
+    },
+    ZerostateParam(ConfigValue),
     AccessDenied,
     ResetFrozen(ResetFrozen),
     ResetType(ResetType),
+    ResetTypeList {
+        unknown: Option<String>,
+    },
+    EnvOverride {
+        path: String,
+        env_var: Option<String>,
+    },
     WorkspaceRemoved,
+    WorkspaceCloned {
+        source: String,
+        dest: String,
+    },
+    WorkspaceFromTemplate {
+        workspace: String,
+        template: String,
+    },
+    Templates(Vec<(String, String)>),
     WorkspaceChanged {
         is_new: bool,
         network: String,
@@ -1412,70 +5809,388 @@ pub enum Reply {
         logger_source: ConfigSource,
         zerostate_source: ConfigSource,
         copy_from: Option<String>,
+        merged_keys: Vec<String>,
+        env_overrides: Vec<String>,
+    },
+    GithubTokenStatus(Vec<(usize, bool)>),
+    GithubRepo {
+        repo: String,
+        is_default: bool,
+    },
+    Unwatch {
+        stopped: bool,
+    },
+    MonitoringPaused {
+        /// `None` if paused until `/resume`.
+        duration: Option<Duration>,
+    },
+    MonitoringResumed {
+        was_paused: bool,
+    },
+    ResetStats(ResetStats),
+    Info(BotInfo),
+    HealthReport(HealthReport),
+    Backups(Vec<BackupInfo>),
+    Compacted(CompactionReport),
+    WorkspaceImportFailed(Vec<String>),
+    StateExported {
+        data: Vec<u8>,
+    },
+    BackupRestored {
+        name: String,
+    },
+    UnknownPlaybook {
+        alias: String,
+        available: Vec<String>,
+    },
+    ConfigAudit {
+        entries: Vec<AuditEntry>,
+    },
+    WalletStatus {
+        address: StdAddr,
+        balance: DecimalTokens,
+        is_funded: bool,
+    },
+    WalletNotConfigured,
+    GiverBalanceAlert {
+        address: StdAddr,
+        balance: DecimalTokens,
+        threshold: u128,
+        is_low: bool,
+    },
+    NetworkHealth(Vec<(String, Option<String>)>),
+    NodeMetric {
+        name: String,
+        value: f64,
+        labels: HashMap<String, String>,
+    },
+    /// Elapsed time of running resets by network.
+    ResetStatus(Vec<(String, Option<Duration>)>),
+    AnsibleConnectivity {
+        network: String,
+        report: AnsibleConnectivityReport,
+    },
+    RpcUrlChanged {
+        network: String,
+        url: String,
+        seqno: u32,
+    },
+    UnhealthyNetwork {
+        network: String,
+        reply: Box<Reply>,
     },
 }
 
 impl Reply {
+    /// Max number of chars of the error cause shown in the message.
+    const MAX_ERROR_LEN: usize = 3500;
+
+    pub fn from_error(context: &str, err: &anyhow::Error) -> Self {
+        Self::Error {
+            context: context.to_owned(),
+            cause: format!("{err:#}"),
+        }
+    }
+
+    /// Returns the full error cause if it was truncated in the message.
+    pub fn truncated_error(&self) -> Option<&str> {
+        match self {
+            Self::Error { cause, .. }
+                if matches!(truncate_middle(cause, Self::MAX_ERROR_LEN), Cow::Owned(_)) =>
+            {
+                Some(cause)
+            }
+            _ => None,
+        }
+    }
+
     pub fn link_preview_options(&self) -> Option<LinkPreviewOptions> {
         match self {
             Self::Commit(commit) => Some(LinkPreviewOptions {
                 url: commit.html_url.clone(),
             }),
+            Self::CommitComparison(result) => Some(LinkPreviewOptions {
+                url: result.html_url.clone(),
+            }),
+            Self::UnhealthyNetwork { reply, .. } => reply.link_preview_options(),
+            _ => None,
+        }
+    }
+
+    /// File name to use when the reply is too long for a message.
+    pub fn document_name(&self) -> Option<&'static str> {
+        match self {
+            Self::ConfigAudit { .. } => Some("config_audit.txt"),
+            Self::ConfigMatches { .. } => Some("config_matches.txt"),
+            Self::HealthReport(_) => Some("health_report.md"),
+            Self::BlockchainConfigChanges { .. } => Some("config_changes.md"),
+            Self::UnhealthyNetwork { reply, .. } => reply.document_name(),
             _ => None,
         }
     }
 }
 
-impl std::fmt::Display for Reply {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Timings(timings) => {
-                let reply_data = serde_json::to_string_pretty(&timings).unwrap();
-                write!(f, "Timings:\n```json\n{reply_data}\n```")
-            }
-            Self::Commit(commit) => {
-                for line in commit.message.lines() {
-                    writeln!(f, "> {line}")?;
+impl std::fmt::Display for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error { context, cause } => {
+                // NOTE: Backticks would close the code block, and the escaped
+                // length is checked since the text is escaped when sent
+                let cause = cause.replace('`', "'");
+                let cause = truncate_middle(&cause, Self::MAX_ERROR_LEN);
+                write!(f, "{context}:\n```\n{cause}\n```")
+            }
+            Self::Seqno(seqno) => write!(f, "{seqno}"),
+            Self::Timings { timings, shards } => {
+                let age = now_sec().saturating_sub(timings.last_mc_utime as u64);
+                writeln!(f, "Timings:")?;
+                writeln!(f, "Last mc block: `{}`", timings.last_mc_block_seqno)?;
+                writeln!(
+                    f,
+                    "Last mc block age: `{}`",
+                    humantime::format_duration(Duration::from_secs(age))
+                )?;
+                writeln!(
+                    f,
+                    "Mc time diff: `{}s` {}",
+                    timings.mc_time_diff,
+                    SyncHealth(timings.mc_time_diff)
+                )?;
+                match timings.smallest_known_lt {
+                    Some(lt) => write!(f, "Smallest known LT: `{lt:x}`")?,
+                    None => write!(f, "Smallest known LT: unknown")?,
+                }
+
+                if let Some(shards) = shards {
+                    let lagging = shards.lagging().count();
+                    if lagging == 0 {
+                        write!(f, "\n✅ All {} shards are in sync", shards.shards.len())?;
+                    } else {
+                        write!(
+                            f,
+                            "\n⚠️ {lagging} of {} shards are lagging, see /shards",
+                            shards.shards.len()
+                        )?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Shards(info) => {
+                if info.shards.is_empty() {
+                    return write!(f, "No shards found");
+                }
+                for shard in &info.shards {
+                    let indicator = if shard.is_healthy() { "✅" } else { "⚠️" };
+                    writeln!(
+                        f,
+                        "{indicator} `{:016x}` seqno: `{}`, time diff: `{}s`",
+                        shard.shard_id, shard.last_seqno, shard.time_diff
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Elections(elections) => {
+                match (elections.election_open, elections.election_id) {
+                    (true, Some(id)) => writeln!(f, "🗳 Elections `{id}` are open")?,
+                    (true, None) => writeln!(f, "🗳 Elections are open")?,
+                    (false, _) => writeln!(f, "🔒 Elections are closed")?,
+                }
+                writeln!(f, "Candidates: `{}`", elections.candidates_count)?;
+
+                let frozen_stake = Tokens::new(elections.frozen_stake);
+                write!(
+                    f,
+                    "Frozen stake: {} {Currency}",
+                    DecimalTokens(frozen_stake)
+                )
+            }
+            Self::CommitComparison(result) => {
+                const MAX_COMMITS: usize = 10;
+
+                writeln!(
+                    f,
+                    "Ahead by *{}*, behind by *{}*\n",
+                    result.ahead_by, result.behind_by
+                )?;
+                for commit in result.commits.iter().rev().take(MAX_COMMITS) {
+                    let sha = &commit.sha[..commit.sha.len().min(7)];
+                    let title = commit.message.lines().next().unwrap_or_default();
+                    writeln!(f, "- `{sha}` {title}")?;
+                }
+                if result.commits.len() > MAX_COMMITS {
+                    writeln!(f, "- and {} more", result.commits.len() - MAX_COMMITS)?;
+                }
+                if !result.commits.is_empty() {
+                    writeln!(f)?;
+                }
+                f.write_str(&result.html_url)
+            }
+            Self::CiStatus { sha, runs } => {
+                if runs.is_empty() {
+                    return write!(f, "No workflow runs found for `{sha}`");
+                }
+                writeln!(f, "Workflow runs for `{sha}`:")?;
+                for run in runs {
+                    let status = run.conclusion.as_deref().unwrap_or(&run.status);
+                    writeln!(
+                        f,
+                        "{} {}: {status}\n{}",
+                        run.ci_status().as_emoji(),
+                        run.name,
+                        run.html_url
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Commit(commit) => {
+                for line in commit.message.lines() {
+                    writeln!(f, "> {line}")?;
+                }
+                write!(f, "Commit: `{}`", commit.sha)?;
+                if let Some(ci_status) = commit.ci_status {
+                    write!(f, " CI: {}", ci_status.as_emoji())?;
+                }
+                writeln!(f, "\n")?;
+
+                if !commit.branches.is_empty() {
+                    let mut first = true;
+                    write!(f, "Branch: ")?;
+                    for name in &commit.branches {
+                        write!(
+                            f,
+                            "{}`{name}`",
+                            if std::mem::take(&mut first) { "" } else { ", " }
+                        )?;
+                    }
+                    writeln!(f, "\n")?;
+                }
+
+                f.write_str(&commit.html_url)
+            }
+            Self::WorkspacesDetailed(workspaces) => {
+                for info in workspaces {
+                    let current = if info.is_current {
+                        " // <- current"
+                    } else {
+                        ""
+                    };
+                    writeln!(f, "- `{}`{current}", info.name)?;
+
+                    let frozen = if info.frozen { " ❄️" } else { "" };
+                    writeln!(f, "  🌐 `{}`{frozen}", info.network)?;
+
+                    if !info.overrides.is_empty() {
+                        write!(f, "  ⚙️ ")?;
+                        for (i, ty) in info.overrides.iter().enumerate() {
+                            let sep = if i == 0 { "" } else { ", " };
+                            write!(f, "{sep}{ty}")?;
+                        }
+                        writeln!(f)?;
+                    }
+
+                    if let Some(at) = info.last_modified {
+                        let elapsed = Duration::from_secs(now_sec().saturating_sub(at));
+                        writeln!(f, "  🕒 {} ago", humantime::format_duration(elapsed))?;
+                    }
+                }
+                Ok(())
+            }
+            Self::WorkspaceCommitsRefreshed(updated) if updated.is_empty() => {
+                write!(f, "All tracked branches are up to date")
+            }
+            Self::WorkspaceCommitsRefreshed(updated) => {
+                writeln!(f, "Updated workspaces:")?;
+                for (name, commit) in updated {
+                    let sha = &commit.sha[..commit.sha.len().min(7)];
+                    let title = commit.message.lines().next().unwrap_or_default();
+                    writeln!(f, "- `{name}`: `{sha}` {title}")?;
+                }
+                Ok(())
+            }
+            Self::NetworkUnreachable { network, reason } => {
+                write!(
+                    f,
+                    "⚠️ Network `{network}` is unreachable, the network was not changed:\n\
+                    ```\n{reason}\n```\n\
+                    Use `/setnetwork {network} --force` to switch anyway"
+                )
+            }
+            Self::ConfigSchema { path, .. } => {
+                write!(f, "Schema saved to `{path}`")
+            }
+            Self::WorkspaceInfo(info) => {
+                let current = if info.is_current { " (current)" } else { "" };
+                writeln!(f, "Workspace: `{}`{current}", info.name)?;
+
+                let frozen = if info.frozen { " ❄️ frozen" } else { "" };
+                writeln!(f, "🌐 Network: `{}`{frozen}", info.network)?;
+
+                match info.reset_type {
+                    Some(reset_type) => writeln!(f, "Reset type: *{reset_type}*")?,
+                    None => writeln!(f, "Reset type: global")?,
+                }
+                if let Some(branch) = &info.tracked_branch {
+                    writeln!(f, "Tracked branch: `{branch}`")?;
+                }
+
+                writeln!(f, "\n⚙️ Configs:")?;
+                for ty in [ConfigType::Node, ConfigType::Logger, ConfigType::Zerostate] {
+                    let source = if info.overrides.contains(&ty) {
+                        "workspace"
+                    } else {
+                        "file"
+                    };
+                    writeln!(f, "- {ty}: {source}")?;
                 }
-                writeln!(f, "Commit: `{}`\n", commit.sha)?;
 
-                if !commit.branches.is_empty() {
-                    let mut first = true;
-                    write!(f, "Branch: ")?;
-                    for name in &commit.branches {
+                match info.last_modified {
+                    Some(at) => {
+                        let elapsed = Duration::from_secs(now_sec().saturating_sub(at));
                         write!(
                             f,
-                            "{}`{name}`",
-                            if std::mem::take(&mut first) { "" } else { ", " }
-                        )?;
+                            "\n🕒 Modified {} ago",
+                            humantime::format_duration(elapsed)
+                        )
                     }
-                    writeln!(f, "\n")?;
+                    None => write!(f, "\n🕒 Never modified"),
                 }
-
-                f.write_str(&commit.html_url)
             }
-            Self::Workspaces {
-                current,
-                workspaces,
-            } => {
-                for workspace in workspaces {
-                    let current = if workspace == current {
+            Self::NetworkSummary(networks) => {
+                for network in networks {
+                    let current = if network.current {
                         " // <- current"
                     } else {
                         ""
                     };
-                    writeln!(f, "- `{workspace}`{current}")?;
+                    writeln!(f, "- `{}`{current}", network.name)?;
+                    match &network.timings {
+                        Some(timings) => write!(
+                            f,
+                            "  {}, seqno: `{}`",
+                            SyncHealth(timings.mc_time_diff),
+                            timings.last_mc_block_seqno
+                        )?,
+                        None => write!(f, "  ❌ unreachable")?,
+                    }
+                    if network.frozen {
+                        write!(f, " ❄️ frozen")?;
+                    }
+                    if network.reset_running {
+                        write!(f, " 🔄 reset is running")?;
+                    }
+                    writeln!(f)?;
                 }
                 Ok(())
             }
-            Self::Networks { current, networks } => {
-                for network in networks {
-                    let current = if network == current {
-                        " // <- current"
-                    } else {
-                        ""
-                    };
-                    writeln!(f, "- `{network}`{current}")?;
+            Self::Accounts(accounts) => {
+                for (address, res) in accounts {
+                    writeln!(f, "`{address}`")?;
+                    match res {
+                        Ok((balance, status)) => {
+                            writeln!(f, "`{status:?}` {balance} {Currency}")?;
+                        }
+                        Err(e) => writeln!(f, "⚠️ {e}")?,
+                    }
                 }
                 Ok(())
             }
@@ -1489,19 +6204,63 @@ impl std::fmt::Display for Reply {
                     "Address:\n`{address}`\nStatus:\n`{status:?}`\nBalance:\n{balance} {Currency}"
                 )
             }
-            Self::ConfigParam {
-                global_id,
-                seqno,
-                value,
-                param,
+            Self::AccountVerified {
+                address,
+                proof_root_hash,
+                proof_consistent,
+            } => {
+                let status = if *proof_consistent {
+                    "✅ Proof is consistent with the account state"
+                } else {
+                    "❌ Proof is inconsistent with the account state"
+                };
+                write!(
+                    f,
+                    "{status}\n(not checked against the chain)\n\nAddress:\n`{address}`\nProof root hash:\n`{proof_root_hash}`"
+                )
+            }
+            Self::AccountProof {
+                address,
+                state_hash,
+                proof_hash,
             } => {
-                let value_str =
-                    serde_json::to_string_pretty(&value.get(param.to_string())).unwrap_or_default();
                 write!(
                     f,
-                    "Global ID: {global_id}\nKey Block Seqno: {seqno}\n\nParam {param}:\n```json\n{value_str}\n```"
+                    "Address:\n`{address}`\nState hash:\n`{state_hash}`\nProof hash:\n`{proof_hash}`"
                 )
             }
+            Self::ConfigParams {
+                global_id,
+                seqno,
+                values,
+            } => {
+                write!(f, "Global ID: {global_id}\nKey Block Seqno: {seqno}")?;
+                for (param, value) in values {
+                    let value_str = serde_json::to_string_pretty(value).unwrap_or_default();
+                    write!(f, "\n\nParam {param}:\n```json\n{value_str}\n```")?;
+                }
+                Ok(())
+            }
+            Self::BlockchainConfigChanges {
+                network,
+                baseline_seqno,
+                seqno,
+                diff,
+            } => {
+                writeln!(f, "🌐 Network: `{network}`\n")?;
+                if diff.is_empty() {
+                    write!(
+                        f,
+                        "No blockchain config changes since key block `{baseline_seqno}`"
+                    )
+                } else {
+                    write!(
+                        f,
+                        "Blockchain config changes from key block `{baseline_seqno}` \
+                        to `{seqno}`:\n```json\n{diff}\n```"
+                    )
+                }
+            }
             Self::Freeze { network } => {
                 writeln!(f, "🌐 Network: `{network}`\n")?;
                 writeln!(f, "Reset is now frozen")
@@ -1510,23 +6269,124 @@ impl std::fmt::Display for Reply {
                 writeln!(f, "🌐 Network: `{network}`\n")?;
                 writeln!(f, "Reset is now available")
             }
+            Self::FreezeList { entries } => {
+                if entries.is_empty() {
+                    return write!(f, "No networks are currently frozen.");
+                }
+
+                let remaining = entries
+                    .iter()
+                    .map(|e| {
+                        humantime::format_duration(Duration::from_secs(e.remaining_secs))
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>();
+                let network_width = entries
+                    .iter()
+                    .map(|e| e.network.len())
+                    .chain(["Network".len()])
+                    .max()
+                    .unwrap_or_default();
+                let workspace_width = entries
+                    .iter()
+                    .map(|e| e.workspace.as_deref().unwrap_or("-").len())
+                    .chain(["Workspace".len()])
+                    .max()
+                    .unwrap_or_default();
+                let remaining_width = remaining
+                    .iter()
+                    .map(String::len)
+                    .chain(["Remaining".len()])
+                    .max()
+                    .unwrap_or_default();
+
+                writeln!(f, "❄️ Frozen networks:\n```")?;
+                writeln!(
+                    f,
+                    "{:network_width$}  {:workspace_width$}  {:remaining_width$}  Reason",
+                    "Network", "Workspace", "Remaining"
+                )?;
+                for (entry, remaining) in entries.iter().zip(&remaining) {
+                    // NOTE: Backticks would close the code block
+                    let reason = entry.reason.as_deref().unwrap_or("-").replace('`', "'");
+                    writeln!(
+                        f,
+                        "{:network_width$}  {:workspace_width$}  \
+                        {remaining:remaining_width$}  {reason}",
+                        entry.network,
+                        entry.workspace.as_deref().unwrap_or("-"),
+                    )?;
+                }
+                write!(f, "```")
+            }
+            Self::ConfigMatches { ty, matches } => {
+                if matches.is_empty() {
+                    return write!(f, "No matches in the *{ty}* config");
+                }
+                writeln!(f, "Matches in the *{ty}* config:")?;
+                for (path, value) in matches {
+                    writeln!(f, "- `{path}`: `{value}`")?;
+                }
+                Ok(())
+            }
+            Self::TypeMismatches { ty, mismatches } => {
+                if mismatches.is_empty() {
+                    return write!(f, "No type mismatches in the *{ty}* config");
+                }
+                writeln!(f, "Type mismatches in the *{ty}* config:")?;
+                for m in mismatches {
+                    writeln!(
+                        f,
+                        "- `{}`: {} on disk, {} in the workspace",
+                        m.path, m.baseline_type, m.current_type
+                    )?;
+                }
+                Ok(())
+            }
+            Self::ConfigMigrated { migrated } => {
+                if migrated.is_empty() {
+                    return write!(f, "Nothing to migrate, no source paths were found");
+                }
+                for (ty, renamed) in migrated {
+                    writeln!(f, "Renamed in the *{ty}* config:")?;
+                    for path in renamed {
+                        writeln!(f, "- `{path}`")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::PendingChanges { ty, diff } => {
+                if diff.is_empty() {
+                    write!(f, "No pending changes in the *{ty}* config")
+                } else {
+                    write!(f, "Pending *{ty}* config changes:\n```json\n{diff}\n```")
+                }
+            }
             Self::NodeConfigUpdated(msg) => {
                 write!(f, "Node config updated:\n```json\n{msg}\n```")
             }
-            Self::NodeConfigParam(config) => {
-                write!(f, "```json\n{config}\n```")
+            Self::NodeConfigParam(config) => std::fmt::Display::fmt(config, f),
+            Self::ConfigUnchanged { path } => {
+                write!(
+                    f,
+                    "Config unchanged: `{path}` already has this value, nothing was saved"
+                )
             }
             Self::LoggerConfigUpdated(msg) => {
                 write!(f, "Logger config updated:\n```json\n{msg}\n```")
             }
-            Self::LoggerConfigParam(config) => {
-                write!(f, "```json\n{config}\n```")
-            }
+            Self::LoggerConfigParam(config) => std::fmt::Display::fmt(config, f),
             Self::ZerostateUpdated(msg) => {
                 write!(f, "Zerostate config updated:\n```json\n{msg}\n```")
             }
-            Self::ZerostateParam(config) => {
-                write!(f, "```json\n{config}\n```")
+            Self::ZerostateParam(config) => std::fmt::Display::fmt(config, f),
+            Self::ConfigUpdatedVerbose {
+                ty,
+                updated,
+                config,
+            } => {
+                std::fmt::Display::fmt(updated, f)?;
+                write!(f, "\n\nFull *{ty}* config:\n```json\n{config}\n```")
             }
             Self::AccessDenied => {
                 write!(f, "👮‍♀️ Access denied")
@@ -1546,67 +6406,473 @@ impl std::fmt::Display for Reply {
                 if let Some(reason) = &frozen.reason {
                     write!(f, "\n\n> {reason}")?;
                 }
-
+
+                Ok(())
+            }
+            Self::ResetType(reset_type) => {
+                write!(f, "Reset type: *{reset_type}*")
+            }
+            Self::ResetTypeList { unknown } => {
+                if let Some(unknown) = unknown {
+                    writeln!(f, "Unknown reset type `{unknown}`\n")?;
+                }
+                writeln!(f, "Available reset types:")?;
+                for ty in ResetType::ALL_TYPES {
+                    writeln!(f, "{} `{ty}` - {}", ty.as_emoji(), ty.description())?;
+                }
+                write!(f, "\nUsage: `/setresettype <type>`")
+            }
+            Self::EnvOverride {
+                path,
+                env_var: Some(env_var),
+            } => {
+                write!(f, "🔐 `{path}` is now overridden from env `{env_var}`")
+            }
+            Self::EnvOverride {
+                path,
+                env_var: None,
+            } => {
+                write!(f, "`{path}` is no longer overridden from env")
+            }
+            Self::WorkspaceCloned { source, dest } => {
+                write!(f, "Workspace `{source}` copied to `{dest}`")
+            }
+            Self::WorkspaceFromTemplate {
+                workspace,
+                template,
+            } => {
+                write!(
+                    f,
+                    "Workspace `{workspace}` created from template `{template}`"
+                )
+            }
+            Self::Templates(templates) if templates.is_empty() => {
+                write!(f, "No workspace templates configured")
+            }
+            Self::Templates(templates) => {
+                for (name, path) in templates {
+                    writeln!(f, "- `{name}`: `{path}`")?;
+                }
+                Ok(())
+            }
+            Self::WorkspaceRemoved => {
+                write!(f, "Workspace removed")
+            }
+            Self::WorkspaceChanged {
+                is_new,
+                network,
+                logger_source,
+                node_source,
+                zerostate_source,
+                copy_from,
+                merged_keys,
+                env_overrides,
+            } => {
+                struct SourceWithWorkspace<'a> {
+                    workspace: &'a str,
+                    source: ConfigSource,
+                }
+
+                impl std::fmt::Display for SourceWithWorkspace<'_> {
+                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        match self.source {
+                            ConfigSource::Unchanged => write!(f, "unchanged"),
+                            ConfigSource::Copied => write!(f, "copied from `{}`", self.workspace),
+                            ConfigSource::FromFile => write!(f, "loaded from file"),
+                        }
+                    }
+                }
+
+                let workspace = copy_from.as_deref().unwrap_or(DEFAULT_WORKSPACE);
+
+                let action = if *is_new { "created" } else { "selected" };
+                writeln!(f, "✅ Workspace {action}.")?;
+                writeln!(f, "🌐 Network: `{network}`\n")?;
+                writeln!(
+                    f,
+                    "- Node config: {};",
+                    SourceWithWorkspace {
+                        workspace,
+                        source: *node_source
+                    }
+                )?;
+                writeln!(
+                    f,
+                    "- Logger config: {};",
+                    SourceWithWorkspace {
+                        workspace,
+                        source: *logger_source
+                    }
+                )?;
+                writeln!(
+                    f,
+                    "- Zerostate config: {};",
+                    SourceWithWorkspace {
+                        workspace,
+                        source: *zerostate_source
+                    }
+                )?;
+                if !merged_keys.is_empty() {
+                    write!(f, "\n🔀 Merged from `{workspace}`:")?;
+                    for key in merged_keys {
+                        write!(f, " `{key}`")?;
+                    }
+                    writeln!(f)?;
+                }
+                if !env_overrides.is_empty() {
+                    write!(f, "\n🔐 Overridden from env:")?;
+                    for key in env_overrides {
+                        write!(f, " `{key}`")?;
+                    }
+                    writeln!(f)?;
+                }
+                Ok(())
+            }
+            Self::GithubRepo { repo, is_default } => {
+                write!(f, "GitHub repository: `{repo}`")?;
+                if *is_default {
+                    write!(f, " (default)")?;
+                }
+                Ok(())
+            }
+            Self::GithubTokenStatus(tokens) => {
+                writeln!(f, "GitHub tokens:")?;
+                for (index, healthy) in tokens {
+                    let status = if *healthy {
+                        "✅ healthy"
+                    } else {
+                        "⛔ rate limited"
+                    };
+                    writeln!(f, "- Token #{index}: {status}")?;
+                }
+                Ok(())
+            }
+            Self::Unwatch { stopped } => {
+                if *stopped {
+                    write!(f, "Watch stopped")
+                } else {
+                    write!(f, "No active watch in this chat")
+                }
+            }
+            Self::MonitoringPaused { duration } => match duration {
+                Some(duration) => write!(
+                    f,
+                    "⏸️ Health alerts are paused for {}",
+                    humantime::format_duration(*duration)
+                ),
+                None => write!(f, "⏸️ Health alerts are paused until /resume"),
+            },
+            Self::MonitoringResumed { was_paused } => {
+                if *was_paused {
+                    write!(f, "▶️ Health alerts are resumed")
+                } else {
+                    write!(f, "Health alerts are not paused")
+                }
+            }
+            Self::Info(info) => {
+                writeln!(f, "ℹ️ Bot v{}\n", env!("CARGO_PKG_VERSION"))?;
+                writeln!(f, "Workspace: `{}`", info.workspace)?;
+                writeln!(f, "Network: `{}`", info.network)?;
+                writeln!(f, "Reset type: *{}*", info.reset_type)?;
+                match &info.commit {
+                    Some(sha) => writeln!(f, "Commit: `{}`", &sha[..sha.len().min(7)])?,
+                    None => writeln!(f, "Commit: unknown")?,
+                }
+                if info.frozen_networks.is_empty() {
+                    writeln!(f, "Frozen: no")?;
+                } else {
+                    write!(f, "Frozen: ❄️")?;
+                    for network in &info.frozen_networks {
+                        write!(f, " `{network}`")?;
+                    }
+                    writeln!(f)?;
+                }
+                writeln!(f, "Workspaces: {}", info.workspace_count)?;
+                writeln!(f, "Networks: {}", info.network_count)?;
+                match info.last_reset_at {
+                    Some(at) => {
+                        let elapsed = Duration::from_secs(now_sec().saturating_sub(at));
+                        write!(f, "Last reset: {} ago", humantime::format_duration(elapsed))
+                    }
+                    None => write!(f, "Last reset: never"),
+                }
+            }
+            Self::HealthReport(report) => {
+                writeln!(f, "🩺 *Health report*\n")?;
+                match report.monitoring_paused_until {
+                    Some(u64::MAX) => writeln!(f, "⏸️ Alerts are paused until /resume\n")?,
+                    Some(until) => {
+                        let remaining = Duration::from_secs(until.saturating_sub(now_sec()));
+                        writeln!(
+                            f,
+                            "⏸️ Alerts are paused for {}\n",
+                            humantime::format_duration(remaining)
+                        )?;
+                    }
+                    None => {}
+                }
+
+                writeln!(f, "*Networks*")?;
+                for network in &report.networks {
+                    let current = if network.is_current {
+                        " // <- current"
+                    } else {
+                        ""
+                    };
+                    writeln!(f, "- `{}`{current}", network.name)?;
+                    match &network.timings {
+                        Ok(timings) => writeln!(
+                            f,
+                            "  ✅ seqno: `{}`, time diff: `{}s`",
+                            timings.last_mc_block_seqno, timings.mc_time_diff
+                        )?,
+                        Err(e) => writeln!(f, "  ❌ timings unavailable: {e}")?,
+                    }
+                    if network.jrpc_unhealthy {
+                        writeln!(f, "  ⚠️ JRPC marked unhealthy, see /rechecknetwork")?;
+                    }
+                    if let Some(until) = network.frozen_until {
+                        let remaining = Duration::from_secs(until.saturating_sub(now_sec()));
+                        writeln!(
+                            f,
+                            "  ❄️ frozen for {}",
+                            humantime::format_duration(remaining)
+                        )?;
+                    }
+                    if network.reset_running {
+                        writeln!(f, "  🔄 reset is running")?;
+                    }
+                }
+
+                writeln!(f, "\n*Last reset*")?;
+                match &report.last_reset {
+                    Some(entry) => {
+                        let status = if entry.success { "✅" } else { "❌" };
+                        let elapsed =
+                            Duration::from_secs(now_sec().saturating_sub(entry.finished_at));
+                        write!(f, "{status} `{}`", entry.network)?;
+                        if let Some(commit) = entry.commit_name() {
+                            write!(f, " to `{commit}`")?;
+                        }
+                        writeln!(f, ", {} ago", humantime::format_duration(elapsed))?;
+                    }
+                    None => writeln!(f, "never")?,
+                }
+
+                writeln!(f, "\n*Giver*")?;
+                match &report.giver_balance {
+                    Some(Ok(balance)) => {
+                        let indicator = if balance.0.into_inner() >= report.min_giver_balance {
+                            "✅"
+                        } else {
+                            "⚠️"
+                        };
+                        write!(f, "{indicator} {balance} {Currency}")
+                    }
+                    Some(Err(e)) => write!(f, "❌ balance unavailable: {e}"),
+                    None => write!(f, "not configured"),
+                }
+            }
+            Self::ResetStats(stats) => {
+                if stats.total == 0 {
+                    return write!(f, "No resets recorded yet");
+                }
+
+                writeln!(f, "📊 Reset statistics\n")?;
+                writeln!(f, "Total resets: {}", stats.total)?;
+                writeln!(
+                    f,
+                    "Success rate: {:.1}%",
+                    success_rate(stats.successful, stats.total)
+                )?;
+                if let Some(duration) = stats.avg_duration {
+                    let duration = Duration::from_secs(duration.as_secs());
+                    writeln!(
+                        f,
+                        "Average duration: {}",
+                        humantime::format_duration(duration)
+                    )?;
+                }
+                if let Some((commit, count)) = &stats.top_commit {
+                    writeln!(f, "Most used commit: `{commit}` ({count})")?;
+                }
+                if let Some((reset_type, count)) = &stats.top_reset_type {
+                    writeln!(f, "Most used reset type: *{reset_type}* ({count})")?;
+                }
+
+                writeln!(f, "\nNetworks:")?;
+                for (network, total, successful) in &stats.networks {
+                    writeln!(
+                        f,
+                        "- `{network}`: {total} resets, {:.1}% success",
+                        success_rate(*successful, *total)
+                    )?;
+                }
+                Ok(())
+            }
+            Self::Backups(backups) => {
+                if backups.is_empty() {
+                    return write!(f, "No backups found");
+                }
+
+                writeln!(f, "Backups:")?;
+                for backup in backups {
+                    writeln!(f, "- `{}` ({} bytes)", backup.name, backup.size)?;
+                }
+                Ok(())
+            }
+            Self::Compacted(report) => {
+                if report.entries_removed == 0 {
+                    return write!(f, "Nothing to compact");
+                }
+                write!(
+                    f,
+                    "🧹 Removed {} reset history entries, freed {} bytes",
+                    report.entries_removed, report.bytes_freed
+                )
+            }
+            Self::WorkspaceImportFailed(errors) => {
+                writeln!(f, "❌ Workspace import failed, nothing was saved:")?;
+                for error in errors {
+                    writeln!(f, "- {error}")?;
+                }
+                Ok(())
+            }
+            Self::StateExported { data } => {
+                write!(f, "State exported, {} bytes", data.len())
+            }
+            Self::BackupRestored { name } => {
+                write!(f, "✅ State restored from `{name}`")
+            }
+            Self::WalletStatus {
+                address,
+                balance,
+                is_funded,
+            } => {
+                let status = if *is_funded {
+                    "✅ Funded"
+                } else {
+                    "⚠️ Balance is low"
+                };
+                write!(
+                    f,
+                    "Giver:\n`{address}`\nBalance:\n{balance} {Currency}\n\n{status}"
+                )
+            }
+            Self::ResetStatus(networks) => {
+                if networks.iter().all(|(_, elapsed)| elapsed.is_none()) {
+                    return write!(f, "✅ No resets are running");
+                }
+                for (network, elapsed) in networks {
+                    match elapsed {
+                        Some(elapsed) => writeln!(
+                            f,
+                            "🔄 `{network}`: reset is running for {}",
+                            humantime::format_duration(Duration::from_secs(elapsed.as_secs()))
+                        )?,
+                        None => writeln!(f, "💤 `{network}`: idle")?,
+                    }
+                }
                 Ok(())
             }
-            Self::ResetType(reset_type) => {
-                write!(f, "Reset type: *{reset_type}*")
+            Self::NodeMetric {
+                name,
+                value,
+                labels,
+            } => {
+                write!(f, "📈 `{name}`: `{value}`")?;
+                let mut labels = labels.iter().collect::<Vec<_>>();
+                labels.sort_unstable();
+                for (key, value) in labels {
+                    write!(f, "\n- `{key}`: `{value}`")?;
+                }
+                Ok(())
             }
-            Self::WorkspaceRemoved => {
-                write!(f, "Workspace removed")
+            Self::NetworkHealth(networks) => {
+                for (network, error) in networks {
+                    match error {
+                        None => writeln!(f, "✅ `{network}`")?,
+                        Some(e) => writeln!(f, "🟥 `{network}`: {e}")?,
+                    }
+                }
+                Ok(())
             }
-            Self::WorkspaceChanged {
-                is_new,
+            Self::RpcUrlChanged {
                 network,
-                logger_source,
-                node_source,
-                zerostate_source,
-                copy_from,
+                url,
+                seqno,
             } => {
-                struct SourceWithWorkspace<'a> {
-                    workspace: &'a str,
-                    source: ConfigSource,
-                }
-
-                impl std::fmt::Display for SourceWithWorkspace<'_> {
-                    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                        match self.source {
-                            ConfigSource::Unchanged => write!(f, "unchanged"),
-                            ConfigSource::Copied => write!(f, "copied from `{}`", self.workspace),
-                            ConfigSource::FromFile => write!(f, "loaded from file"),
-                        }
-                    }
+                writeln!(f, "✅ JRPC endpoint of `{network}` changed to `{url}`")?;
+                write!(f, "Last mc block: {seqno}")
+            }
+            Self::AnsibleConnectivity { network, report } => {
+                writeln!(f, "🌐 Network: `{network}`")?;
+                let total = report.reachable.len() + report.unreachable.len();
+                if report.unreachable.is_empty() {
+                    return write!(f, "✅ All {total} hosts are reachable");
                 }
-
-                let workspace = copy_from.as_deref().unwrap_or(DEFAULT_WORKSPACE);
-
-                let action = if *is_new { "created" } else { "selected" };
-                writeln!(f, "✅ Workspace {action}.")?;
-                writeln!(f, "🌐 Network: `{network}`\n")?;
-                writeln!(
-                    f,
-                    "- Node config: {};",
-                    SourceWithWorkspace {
-                        workspace,
-                        source: *node_source
-                    }
-                )?;
                 writeln!(
                     f,
-                    "- Logger config: {};",
-                    SourceWithWorkspace {
-                        workspace,
-                        source: *logger_source
-                    }
+                    "⚠️ {} of {total} hosts are unreachable:",
+                    report.unreachable.len()
                 )?;
+                for host in &report.unreachable {
+                    writeln!(f, "- `{host}`")?;
+                }
+                Ok(())
+            }
+            Self::UnhealthyNetwork { network, reply } => {
                 writeln!(
                     f,
-                    "- Zerostate config: {};",
-                    SourceWithWorkspace {
-                        workspace,
-                        source: *zerostate_source
-                    }
+                    "⚠️ Network `{network}` is unhealthy, use /rechecknetwork to retry\n"
                 )?;
+                std::fmt::Display::fmt(reply, f)
+            }
+            Self::WalletNotConfigured => {
+                write!(f, "Giver wallet is not configured")
+            }
+            Self::GiverBalanceAlert {
+                address,
+                balance,
+                threshold,
+                is_low,
+            } => {
+                if *is_low {
+                    let threshold = DecimalTokens(Tokens::new(*threshold));
+                    write!(
+                        f,
+                        "⚠️ Giver balance is below {threshold} {Currency}\n\n\
+                        Giver:\n`{address}`\nBalance:\n{balance} {Currency}"
+                    )
+                } else {
+                    write!(
+                        f,
+                        "✅ Giver balance recovered\n\n\
+                        Giver:\n`{address}`\nBalance:\n{balance} {Currency}"
+                    )
+                }
+            }
+            Self::ConfigAudit { entries } => {
+                if entries.is_empty() {
+                    return write!(f, "No workspace config overrides");
+                }
+                for entry in entries {
+                    write!(
+                        f,
+                        "*{}* / *{}*:\n```json\n{}\n```\n",
+                        entry.workspace, entry.config_type, entry.diff
+                    )?;
+                }
+                Ok(())
+            }
+            Self::UnknownPlaybook { alias, available } => {
+                if available.is_empty() {
+                    return write!(f, "Unknown playbook `{alias}`. No playbooks are configured");
+                }
+                writeln!(f, "Unknown playbook `{alias}`. Available playbooks:")?;
+                for alias in available {
+                    writeln!(f, "- `{alias}`")?;
+                }
                 Ok(())
             }
         }
@@ -1619,16 +6885,19 @@ mod tests {
     use everscale_types::models::BlockchainConfig;
 
     use super::*;
+    use crate::util::TempFile;
 
     #[test]
     fn reset_params_from_str() {
         let params = "".parse::<ResetParams>().unwrap();
         assert_eq!(params.commit, "master");
+        assert!(!params.explicit_commit);
         assert_eq!(params.node_count, ResetParams::DEFAULT_NODE_COUNT);
         assert_eq!(params.build_profile, ResetParams::DEFAULT_BUILD_PROFILE);
 
         let params = "feature/new".parse::<ResetParams>().unwrap();
         assert_eq!(params.commit, "feature/new");
+        assert!(params.explicit_commit);
         assert_eq!(params.node_count, ResetParams::DEFAULT_NODE_COUNT);
         assert_eq!(params.build_profile, ResetParams::DEFAULT_BUILD_PROFILE);
 
@@ -1636,6 +6905,719 @@ mod tests {
         assert_eq!(params.commit, "master");
         assert_eq!(params.node_count, 10);
         assert_eq!(params.build_profile, "debug");
+        assert_eq!(params.tags, None);
+
+        let params = "tags=deploy, config; skip_tags=build"
+            .parse::<ResetParams>()
+            .unwrap();
+        assert_eq!(params.tags.as_deref(), Some("deploy,config"));
+        assert_eq!(params.skip_tags.as_deref(), Some("build"));
+
+        assert!("tags=".parse::<ResetParams>().is_err());
+        assert!("tags=deploy config".parse::<ResetParams>().is_err());
+    }
+
+    #[test]
+    fn config_value_toml() {
+        let value = serde_json::json!({ "server": { "port": 8080 }, "peers": ["a"] });
+        let text = ConfigValue::new(&value, false, true).unwrap().to_string();
+        assert!(text.starts_with("object:\n```toml\n"), "{text}");
+        assert!(text.contains("\"a\""), "{text}");
+        assert!(text.ends_with("[server]\nport = 8080\n```"), "{text}");
+
+        let text = ConfigValue::new(&serde_json::json!(8080), false, true)
+            .unwrap()
+            .to_string();
+        assert_eq!(text, "number: `8080`");
+
+        let value = serde_json::json!({ "proxy": null });
+        let text = ConfigValue::new(&value, false, true).unwrap().to_string();
+        assert!(text.starts_with("object:\n```json\n"), "{text}");
+        assert!(
+            text.ends_with("\n(no TOML representation, shown as JSON)"),
+            "{text}"
+        );
+
+        assert_eq!(parse_toml_flag(".a.b --toml"), (true, ".a.b"));
+        assert_eq!(parse_toml_flag("--toml"), (true, ""));
+        assert_eq!(parse_toml_flag(".a.b"), (false, ".a.b"));
+        assert_eq!(parse_toml_flag(".a--toml"), (false, ".a--toml"));
+    }
+
+    #[test]
+    fn verbose_flag() {
+        assert_eq!(parse_verbose_flag(" --verbose .a = 1"), (true, ".a = 1"));
+        assert_eq!(parse_verbose_flag("--verbose"), (true, ""));
+        assert_eq!(parse_verbose_flag(".a = 1"), (false, ".a = 1"));
+        assert_eq!(
+            parse_verbose_flag("--verbosely .a = 1"),
+            (false, "--verbosely .a = 1")
+        );
+    }
+
+    #[test]
+    fn set_workspace_params_copy_keys() {
+        let params = "dev; copy_from=prod; copy_keys=node.mempool, logger.outputs[0]"
+            .parse::<SetWorkspaceParams>()
+            .unwrap();
+        assert_eq!(params.workspace, "dev");
+        assert_eq!(params.copy_from.as_deref(), Some("prod"));
+        let keys = params
+            .copy_keys
+            .iter()
+            .map(|key| format_copy_key(key))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["node.mempool", "logger.outputs[0]"]);
+
+        assert!("dev; copy_keys=node.mempool"
+            .parse::<SetWorkspaceParams>()
+            .is_err());
+        assert!("dev; copy_from=prod; copy_keys=unknown.key"
+            .parse::<SetWorkspaceParams>()
+            .is_err());
+    }
+
+    #[test]
+    fn set_network_params_force() {
+        let params = "devnet".parse::<SetNetworkParams>().unwrap();
+        assert_eq!(params.network, "devnet");
+        assert!(!params.force);
+
+        let params = "devnet --force".parse::<SetNetworkParams>().unwrap();
+        assert_eq!(params.network, "devnet");
+        assert!(params.force);
+
+        assert!("--force".parse::<SetNetworkParams>().is_err());
+        assert!("a b".parse::<SetNetworkParams>().is_err());
+    }
+
+    #[test]
+    fn set_workspace_params_branch() {
+        let params = "staging; branch=staging"
+            .parse::<SetWorkspaceParams>()
+            .unwrap();
+        assert_eq!(params.tracked_branch.as_deref(), Some("staging"));
+
+        let params = "staging; branch=".parse::<SetWorkspaceParams>().unwrap();
+        assert_eq!(params.tracked_branch.as_deref(), Some(""));
+
+        let params = "staging".parse::<SetWorkspaceParams>().unwrap();
+        assert_eq!(params.tracked_branch, None);
+    }
+
+    #[test]
+    fn reset_type_list_roundtrip() {
+        for ty in ResetType::ALL_TYPES {
+            assert_eq!(ty.to_string().parse::<ResetType>().unwrap(), *ty);
+            assert!(!ty.description().is_empty());
+        }
+    }
+
+    #[test]
+    fn workspace_info_named_and_current() {
+        let mut data = StateFileData {
+            current_workspace: Some("dev".to_owned()),
+            ..Default::default()
+        };
+        data.workspaces.insert(
+            "dev".to_owned(),
+            Workspace {
+                network: Some("devnet".to_owned()),
+                node: Some(Default::default()),
+                last_modified: 100,
+                default_reset_type: Some(ResetType::Restart),
+                ..Default::default()
+            },
+        );
+
+        let info = data.workspace_info(&data.current_workspace_name(), "mainnet");
+        assert_eq!(info.name, "dev");
+        assert_eq!(info.network, "devnet");
+        assert!(info.is_current);
+        assert_eq!(info.overrides, [ConfigType::Node]);
+        assert_eq!(info.last_modified, Some(100));
+        assert_eq!(info.reset_type, Some(ResetType::Restart));
+
+        let info = data.workspace_info(DEFAULT_WORKSPACE, "mainnet");
+        assert_eq!(info.name, DEFAULT_WORKSPACE);
+        assert_eq!(info.network, "mainnet");
+        assert!(!info.is_current);
+        assert!(info.overrides.is_empty());
+        assert_eq!(info.last_modified, None);
+        assert_eq!(info.reset_type, None);
+    }
+
+    #[test]
+    fn last_reset_node_count_skips_failed() {
+        let entry = |network: &str, node_count, success| ResetHistoryEntry {
+            network: network.to_owned(),
+            node_count,
+            success,
+            ..Default::default()
+        };
+        let data = StateFileData {
+            reset_history: vec![
+                entry("devnet", Some(13), true),
+                entry("devnet", Some(7), false),
+                entry("testnet", Some(5), true),
+                entry("legacy", None, true),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(data.last_reset_node_count("devnet"), Some(13));
+        assert_eq!(data.last_reset_node_count("testnet"), Some(5));
+        assert_eq!(data.last_reset_node_count("legacy"), None);
+        assert_eq!(data.last_reset_node_count("unknown"), None);
+    }
+
+    #[test]
+    fn error_reply_chain_and_truncation() {
+        let err = anyhow::anyhow!("connection refused").context("failed to get timings");
+        let reply = Reply::from_error("Failed to handle command", &err);
+        assert_eq!(
+            reply.to_string(),
+            "Failed to handle command:\n```\nfailed to get timings: connection refused\n```"
+        );
+        assert!(reply.truncated_error().is_none());
+
+        let long = "ф".repeat(Reply::MAX_ERROR_LEN + 1);
+        let reply = Reply::from_error("Failed", &anyhow::anyhow!(long.clone()));
+        assert_eq!(reply.truncated_error(), Some(long.as_str()));
+        let text = reply.to_string();
+        assert!(text.contains("ф\n...\nф"), "{text}");
+        assert!(text.matches('ф').count() < Reply::MAX_ERROR_LEN);
+
+        // Escaped characters count twice
+        let escaped = ".".repeat(Reply::MAX_ERROR_LEN / 2 + 1);
+        let reply = Reply::from_error("Failed", &anyhow::anyhow!(escaped.clone()));
+        assert_eq!(reply.truncated_error(), Some(escaped.as_str()));
+
+        let reply = Reply::from_error("Failed", &anyhow::anyhow!("invalid `node.rpc`"));
+        assert_eq!(reply.to_string(), "Failed:\n```\ninvalid 'node.rpc'\n```");
+    }
+
+    #[test]
+    fn config_params_display() {
+        let reply = Reply::ConfigParams {
+            global_id: 42,
+            seqno: 7,
+            values: vec![(15, serde_json::json!({ "a": 1 })), (99, Value::Null)],
+        };
+        assert_eq!(
+            reply.to_string(),
+            "Global ID: 42\nKey Block Seqno: 7\n\n\
+            Param 15:\n```json\n{\n  \"a\": 1\n}\n```\n\n\
+            Param 99:\n```json\nnull\n```"
+        );
+    }
+
+    #[test]
+    fn metric_sample_find() {
+        let text = "\
+            # HELP tycho_uptime Node uptime\n\
+            # TYPE tycho_uptime gauge\n\
+            tycho_uptime 123.5\n\
+            # TYPE tycho_blocks_total counter\n\
+            tycho_blocks_total{shard=\"0:80\",kind=\"a \\\"b\\\"\"} 42 1700000000000\n\
+            tycho_blocks_total{shard=\"-1:80\"} 7\n\
+            tycho_uptime_seconds 1\n";
+
+        let sample = MetricSample::find(text, "tycho_uptime").unwrap();
+        assert_eq!(sample.value, 123.5);
+        assert!(sample.labels.is_empty());
+
+        let sample = MetricSample::find(text, "tycho_blocks").unwrap();
+        assert_eq!(sample.value, 42.0);
+        assert_eq!(sample.labels["shard"], "0:80");
+        assert_eq!(sample.labels["kind"], "a \"b\"");
+
+        assert_eq!(
+            MetricSample::find(text, "tycho_blocks_total"),
+            MetricSample::find(text, "tycho_blocks")
+        );
+        assert!(MetricSample::find(text, "tycho_up").is_none());
+        assert!(MetricSample::find(text, "TYPE").is_none());
+    }
+
+    #[test]
+    fn ansible_ping_output() {
+        let output = "\
+            node1 | SUCCESS => {\"changed\": false, \"ping\": \"pong\"}
+            node2 | UNREACHABLE!: Failed to connect to the host via ssh
+            node3 | FAILED! => {\"msg\": \"error\"}
+            [WARNING]: Invalid characters were found in group names";
+        let report = AnsibleConnectivityReport::from_ping_output(output);
+        assert_eq!(report.reachable, ["node1"]);
+        assert_eq!(report.unreachable, ["node2", "node3"]);
+
+        let reply = Reply::AnsibleConnectivity {
+            network: "devnet1".to_owned(),
+            report,
+        };
+        assert!(reply
+            .to_string()
+            .contains("⚠️ 2 of 3 hosts are unreachable"));
+    }
+
+    #[test]
+    fn timings_display() {
+        assert_eq!(SyncHealth(3).to_string(), "✅ synced");
+        assert_eq!(SyncHealth(-30).to_string(), "⚠️ slightly behind (30s)");
+        assert_eq!(SyncHealth(300).to_string(), "🔴 severely behind (5m)");
+
+        let reply = Reply::Timings {
+            timings: StateTimings {
+                last_mc_block_seqno: 100,
+                last_mc_utime: now_sec() as u32,
+                mc_time_diff: 1,
+                smallest_known_lt: Some(0xabc),
+            },
+            shards: None,
+        };
+        let text = reply.to_string();
+        assert!(text.contains("Last mc block: `100`"), "{text}");
+        assert!(text.contains("Mc time diff: `1s` ✅ synced"), "{text}");
+        assert!(text.contains("Smallest known LT: `abc`"), "{text}");
+    }
+
+    #[test]
+    fn blockchain_config_changes_display() {
+        let baseline = serde_json::json!({ "1": "aa", "2": { "value": 1 } });
+        let reply = |params: &serde_json::Value| Reply::BlockchainConfigChanges {
+            network: "main".to_owned(),
+            baseline_seqno: 10,
+            seqno: 20,
+            diff: ConfigDiff::from_values(&baseline, params).unwrap(),
+        };
+
+        let text = reply(&baseline).to_string();
+        assert!(
+            text.contains("No blockchain config changes since key block `10`"),
+            "{text}"
+        );
+
+        let text = reply(&serde_json::json!({ "1": "aa", "2": { "value": 2 } })).to_string();
+        assert!(text.contains("from key block `10` to `20`"), "{text}");
+        assert!(text.contains("-    \"value\": 1"), "{text}");
+        assert!(text.contains("+    \"value\": 2"), "{text}");
+    }
+
+    #[test]
+    fn reset_status_display() {
+        let idle = Reply::ResetStatus(vec![("main".to_owned(), None)]);
+        assert_eq!(idle.to_string(), "✅ No resets are running");
+
+        let running = Reply::ResetStatus(vec![
+            ("main".to_owned(), Some(Duration::from_millis(65_500))),
+            ("test".to_owned(), None),
+        ]);
+        assert_eq!(
+            running.to_string(),
+            "🔄 `main`: reset is running for 1m 5s\n💤 `test`: idle\n"
+        );
+    }
+
+    #[test]
+    fn network_summary_display() {
+        let status = |name: &str, timings| NetworkStatus {
+            name: name.to_owned(),
+            current: name == "main",
+            timings,
+            frozen: name == "test",
+            reset_running: false,
+        };
+        let reply = Reply::NetworkSummary(vec![
+            status(
+                "main",
+                Some(StateTimings {
+                    last_mc_block_seqno: 100,
+                    last_mc_utime: 0,
+                    mc_time_diff: 2,
+                    smallest_known_lt: None,
+                }),
+            ),
+            status("test", None),
+        ]);
+        let text = reply.to_string();
+        assert!(
+            text.contains("- `main` // <- current\n  ✅ synced, seqno: `100`\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("- `test`\n  ❌ unreachable ❄️ frozen\n"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn freeze_list_table() {
+        let empty = Reply::FreezeList {
+            entries: Vec::new(),
+        };
+        assert_eq!(empty.to_string(), "No networks are currently frozen.");
+
+        let entry = |network: &str, reason: Option<&str>, remaining_secs| FrozenEntry {
+            network: network.to_owned(),
+            workspace: Some("default".to_owned()),
+            reason: reason.map(str::to_owned),
+            remaining_secs,
+        };
+        let reply = Reply::FreezeList {
+            entries: vec![
+                entry("devnet1", Some("`testing`"), 60),
+                entry("testnet", None, 7200),
+            ],
+        };
+        let text = reply.to_string();
+        assert!(
+            text.contains("Network  Workspace  Remaining  Reason\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("devnet1  default    1m         'testing'\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("testnet  default    2h         -\n"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn workspace_merge_from() {
+        let object = |value: serde_json::Value| value.as_object().cloned();
+
+        let mut workspace = Workspace {
+            node: object(serde_json::json!({ "mempool": { "size": 1 }, "port": 1 })),
+            logger: object(serde_json::json!({})),
+            zerostate: object(serde_json::json!({})),
+            ..Default::default()
+        };
+        let source = Workspace {
+            node: object(serde_json::json!({ "mempool": { "size": 2 }, "port": 2 })),
+            logger: object(serde_json::json!({ "outputs": ["stderr"] })),
+            zerostate: object(serde_json::json!({})),
+            ..Default::default()
+        };
+
+        let keys = SetWorkspaceParams::parse_copy_keys("node.mempool,logger").unwrap();
+        workspace.merge_from(&source, &keys).unwrap();
+
+        assert_eq!(
+            workspace.node,
+            object(serde_json::json!({ "mempool": { "size": 2 }, "port": 1 }))
+        );
+        assert_eq!(workspace.logger, source.logger);
+    }
+
+    #[test]
+    fn workspace_import_rejects_invalid_configs() {
+        let file = TempFile::new("json");
+        std::fs::write(
+            file.path(),
+            r#"{"server":{"port":8080},"peers":["a"],"api":{"key":"secret"}}"#,
+        )
+        .unwrap();
+        let path = file.path();
+
+        let workspace: Workspace = serde_json::from_value(serde_json::json!({
+            "node": { "server": { "port": 99999 }, "peers": "a", "api": { "key": null } },
+        }))
+        .unwrap();
+        let errors = workspace
+            .validate_configs(path, "", "", &ConfigValidatorRule::defaults())
+            .unwrap();
+        assert_eq!(
+            errors,
+            [
+                "node config: invalid value at '.server.port': expected a value <= 65535, got 99999",
+                "node config: `.peers` is array on disk, string in the workspace",
+            ]
+        );
+
+        let text = Reply::WorkspaceImportFailed(errors).to_string();
+        assert!(text.starts_with("❌ Workspace import failed"), "{text}");
+
+        // Nulled env override paths are not reported
+        let workspace: Workspace = serde_json::from_value(serde_json::json!({
+            "node": { "server": { "port": 80 }, "api": { "key": null } },
+        }))
+        .unwrap();
+        let errors = workspace
+            .validate_configs(path, "", "", &ConfigValidatorRule::defaults())
+            .unwrap();
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn clone_workspace_params_from_str() {
+        let params = "default  my-test".parse::<CloneWorkspaceParams>().unwrap();
+        assert_eq!(params.source, "default");
+        assert_eq!(params.dest, "my-test");
+        assert!(!params.overwrite);
+
+        let params = "a --overwrite b".parse::<CloneWorkspaceParams>().unwrap();
+        assert_eq!((params.source.as_str(), params.dest.as_str()), ("a", "b"));
+        assert!(params.overwrite);
+
+        assert!("a".parse::<CloneWorkspaceParams>().is_err());
+        assert!("a b c".parse::<CloneWorkspaceParams>().is_err());
+        assert!("a b --force".parse::<CloneWorkspaceParams>().is_err());
+    }
+
+    #[test]
+    fn workspace_template_overrides_leaves() {
+        let object = |value: serde_json::Value| value.as_object().cloned();
+
+        let mut workspace = Workspace {
+            node: object(serde_json::json!({
+                "mempool": { "size": 1, "ttl": 10 },
+                "port": 1,
+                "peers": ["a", "b", "c"],
+                "limits": { "max": 1 },
+            })),
+            logger: object(serde_json::json!({ "level": "info" })),
+            zerostate: object(serde_json::json!({})),
+            ..Default::default()
+        };
+        let template: Workspace = serde_json::from_value(serde_json::json!({
+            "node": { "mempool": { "size": 2 }, "peers": ["d"], "limits": {}, "filters": [] },
+            "logger": { "level": "debug", "targets": ["net"] },
+        }))
+        .unwrap();
+
+        workspace
+            .merge_from(&template, &template.leaf_keys())
+            .unwrap();
+
+        // Arrays are replaced as a whole, empty containers are kept
+        assert_eq!(
+            workspace.node,
+            object(serde_json::json!({
+                "mempool": { "size": 2, "ttl": 10 },
+                "port": 1,
+                "peers": ["d"],
+                "limits": {},
+                "filters": [],
+            }))
+        );
+        // Missing arrays are created
+        assert_eq!(
+            workspace.logger,
+            object(serde_json::json!({ "level": "debug", "targets": ["net"] }))
+        );
+    }
+
+    #[test]
+    fn create_from_template_params_from_str() {
+        let params = "my-test; template=debug"
+            .parse::<CreateFromTemplateParams>()
+            .unwrap();
+        assert_eq!(params.workspace, "my-test");
+        assert_eq!(params.template, "debug");
+
+        assert!("my-test".parse::<CreateFromTemplateParams>().is_err());
+        assert!("template=debug"
+            .parse::<CreateFromTemplateParams>()
+            .is_err());
+        assert!("a; b; template=debug"
+            .parse::<CreateFromTemplateParams>()
+            .is_err());
+        assert!("a; copy_from=b"
+            .parse::<CreateFromTemplateParams>()
+            .is_err());
+    }
+
+    #[test]
+    fn temp_vault_password_file() {
+        let vault = AnsibleVault::Password("secret".to_owned());
+        let file = vault.password_file().unwrap();
+        let path = file.path().to_owned();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "secret");
+
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn env_override_var_names() {
+        let check = |var| check_env_override_var(var, Some("TYCHONET")).is_ok();
+        assert!(check("TYCHONET_SECRET_API_KEY"));
+        assert!(!check("TYCHONET_SECRET_"));
+        assert!(!check("TYCHONET_BOT_TOKEN"));
+        assert!(!check("TYCHONET_GIVER_PRIVATE_KEY"));
+        assert!(!check("HOME"));
+        assert!(!check("OTHER_SECRET_KEY"));
+
+        // The settings namespace is protected whatever the prefix is
+        assert!(check_env_override_var("CI_SECRET_KEY", Some("CI")).is_ok());
+        assert!(
+            check_env_override_var("TYCHONET_GITHUB_SECRET_X", Some("TYCHONET_GITHUB")).is_err()
+        );
+        assert!(check_env_override_var("TYCHONET_SECRET_KEY", None).is_err());
+    }
+
+    #[test]
+    fn load_file_config_strips_env_overrides() {
+        let file = TempFile::new("json");
+        std::fs::write(
+            file.path(),
+            r#"{"rpc":{"token":"secret","port":8080},"storage":{"root":"db"}}"#,
+        )
+        .unwrap();
+
+        let env_overrides = HashMap::from([
+            ("node.rpc.token".to_owned(), "RPC_TOKEN".to_owned()),
+            ("node.missing".to_owned(), "MISSING".to_owned()),
+            ("logger.level".to_owned(), "LOG_LEVEL".to_owned()),
+        ]);
+        let lookup = |var: &str| (var == "RPC_TOKEN").then(|| "env".to_owned());
+        let config =
+            load_file_config(file.path(), ConfigType::Node, &env_overrides, None, lookup).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(config.as_object().unwrap()),
+            serde_json::json!({ "rpc": { "port": 8080 }, "storage": { "root": "db" } })
+        );
+
+        // Values are kept when the variable is not set
+        let config = load_file_config(file.path(), ConfigType::Node, &env_overrides, None, |_| {
+            None
+        })
+        .unwrap();
+        assert_eq!(
+            serde_json::Value::Object(config.as_object().unwrap()),
+            serde_json::json!({ "rpc": { "token": "secret", "port": 8080 }, "storage": { "root": "db" } })
+        );
+
+        let mut config = config;
+        config
+            .set(
+                &parse_config_value_path(".rpc.port").unwrap(),
+                serde_json::json!(8081),
+            )
+            .unwrap();
+        assert!(!config.diff().unwrap().to_string().contains("secret"));
+    }
+
+    #[test]
+    fn state_export_roundtrip() {
+        let cipher = StateCipher::new(&"11".repeat(32)).unwrap();
+        let data = serde_json::to_vec(&StateFileData::default()).unwrap();
+
+        let encrypted = cipher.encrypt(&data).unwrap();
+        assert_ne!(encrypted, data);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), data);
+
+        let other = StateCipher::new(&"22".repeat(32)).unwrap();
+        assert!(other.decrypt(&encrypted).is_err());
+
+        let imported = StateFileData::from_slice(&data).unwrap();
+        assert_eq!(imported.schema_version, STATE_SCHEMA_VERSION);
+
+        let newer = format!("{{\"schema_version\":{}}}", STATE_SCHEMA_VERSION + 1);
+        assert!(StateFileData::from_slice(newer.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn compact_keeps_newest_history() {
+        let history = serde_json::from_str::<Vec<ResetHistoryEntry>>(
+            r#"[
+                {"network": "devnet1", "started_at": 100},
+                {"network": "devnet1", "started_at": 200},
+                {"network": "devnet2", "started_at": 300}
+            ]"#,
+        )
+        .unwrap();
+        let mut state_file = StateFile {
+            path: PathBuf::new(),
+            latest_data: StateFileData {
+                reset_history: history,
+                ..Default::default()
+            },
+            backups: None,
+        };
+
+        let report = state_file.compact(2);
+        assert_eq!(report.entries_removed, 1);
+        assert!(report.bytes_freed > 0);
+        let started_at = state_file
+            .latest_data
+            .reset_history
+            .iter()
+            .map(|e| e.started_at)
+            .collect::<Vec<_>>();
+        assert_eq!(started_at, [200, 300]);
+
+        assert_eq!(state_file.compact(2), CompactionReport::default());
+    }
+
+    #[test]
+    fn sent_replies_dedup() {
+        let key = |id: i32| ReplyKey {
+            chat_id: ChatId(1),
+            message_id: MessageId(id),
+            command: "/status".to_owned(),
+        };
+
+        let now = Instant::now();
+        let mut replies = SentReplies::default();
+        assert_eq!(replies.check(key(1), now), None);
+        assert_eq!(replies.check(key(1), now), Some(None));
+
+        replies.set_reply_msg_id(&key(1), MessageId(100));
+        assert_eq!(
+            replies.check(key(1), now + Duration::from_secs(5)),
+            Some(Some(MessageId(100)))
+        );
+
+        // Outside of the window
+        assert_eq!(replies.check(key(1), now + SentReplies::WINDOW), None);
+
+        // Least recently used entry is evicted
+        let mut replies = SentReplies::default();
+        for id in 0..SentReplies::MAX_ENTRIES as i32 {
+            replies.check(key(id), now + Duration::from_millis(id as u64));
+        }
+        replies.check(key(0), now + Duration::from_secs(1));
+        replies.check(key(-1), now + Duration::from_secs(2));
+        assert_eq!(replies.entries.len(), SentReplies::MAX_ENTRIES);
+        assert!(replies.entries.contains_key(&key(0)));
+        assert!(!replies.entries.contains_key(&key(1)));
+    }
+
+    #[test]
+    fn reset_stats_from_history() {
+        let stats = ResetStats::from_history(&[]);
+        assert_eq!(stats.total, 0);
+        assert!(stats.avg_duration.is_none());
+
+        let history = serde_json::from_str::<Vec<ResetHistoryEntry>>(
+            r#"[
+                {"network": "devnet1", "commit": "abcdef0123", "branches": ["master"],
+                 "reset_type": "Full", "started_at": 100, "finished_at": 400, "success": true},
+                {"network": "devnet1", "commit": "abcdef0123", "branches": ["master"],
+                 "reset_type": "Restart", "started_at": 500, "finished_at": 600, "success": false},
+                {"network": "devnet2", "reset_type": "Full", "success": true},
+                {}
+            ]"#,
+        )
+        .unwrap();
+
+        let stats = ResetStats::from_history(&history);
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.successful, 2);
+        assert_eq!(stats.avg_duration, Some(Duration::from_secs(200)));
+        assert_eq!(stats.top_commit, Some(("master".to_owned(), 2)));
+        assert_eq!(stats.top_reset_type, Some((ResetType::Full, 2)));
+        assert_eq!(
+            stats.networks,
+            vec![
+                ("".to_owned(), 1, 0),
+                ("devnet1".to_owned(), 2, 1),
+                ("devnet2".to_owned(), 1, 1),
+            ]
+        );
     }
 
     #[test]
@@ -1,13 +1,52 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
 use serde::Serialize;
 use teloxide::types::ReplyParameters;
 
+/// Returns the current unix time from the process-wide [`MonotonicClock`].
 pub fn now_sec() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
+    static CLOCK: OnceLock<MonotonicClock> = OnceLock::new();
+    CLOCK.get_or_init(MonotonicClock::new).now_sec()
+}
+
+/// Unix time in seconds which never goes backward.
+///
+/// The system time is read on every call, so forward NTP corrections and time
+/// spent in suspend are picked up, while backward jumps are clamped to the
+/// largest value returned so far.
+pub struct MonotonicClock {
+    last_sec: AtomicU64,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            last_sec: AtomicU64::new(system_now_sec()),
+        }
+    }
+
+    pub fn now_sec(&self) -> u64 {
+        let now = system_now_sec();
+        let last = self.last_sec.fetch_max(now, Ordering::AcqRel);
+        now.max(last)
+    }
+}
+
+fn system_now_sec() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs()
 }
 
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Emoji {
     Clown,
@@ -160,24 +199,81 @@ pub struct LinkPreviewOptions {
     pub url: String,
 }
 
+static ESCAPED_CHARACTERS: [char; 17] = [
+    '_', '*', '[', ']', '(', ')', '~', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!',
+];
+
+/// Returns an upper bound of the text length in chars after markdown escaping.
+pub fn escaped_len(text: &str) -> usize {
+    text.chars().map(escaped_char_len).sum()
+}
+
+fn escaped_char_len(c: char) -> usize {
+    if c == '\\' || ESCAPED_CHARACTERS.contains(&c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Cuts out the middle of the text (preferably at line boundaries) so that
+/// it fits into `max_len` chars after markdown escaping.
+pub fn truncate_middle(text: &str, max_len: usize) -> std::borrow::Cow<'_, str> {
+    const MARKER: &str = "\n...\n";
+
+    if escaped_len(text) <= max_len {
+        return text.into();
+    }
+
+    let budget = max_len.saturating_sub(escaped_len(MARKER));
+    let head_budget = budget / 2;
+    let tail_budget = budget - head_budget;
+
+    let mut len = 0;
+    let mut head_end = 0;
+    for (i, c) in text.char_indices() {
+        len += escaped_char_len(c);
+        if len > head_budget {
+            break;
+        }
+        head_end = i + c.len_utf8();
+    }
+
+    let mut len = 0;
+    let mut tail_start = text.len();
+    for (i, c) in text.char_indices().rev() {
+        len += escaped_char_len(c);
+        if len > tail_budget {
+            break;
+        }
+        tail_start = i;
+    }
+
+    let head = &text[..head_end];
+    let head = head.rfind('\n').map_or(head, |i| &head[..i]);
+    let tail = &text[tail_start..];
+    let tail = tail.find('\n').map_or(tail, |i| &tail[i + 1..]);
+
+    format!("{head}{MARKER}{tail}").into()
+}
+
+/// Escapes all MarkdownV2 special characters in a single pass, so inserted
+/// backslashes are never processed again.
+///
+/// Inside code spans only backslashes are escaped, as required by MarkdownV2.
 fn escape_markdown(text: impl Into<String>) -> String {
-    static ESCAPED_CHARACTERS: [char; 17] = [
-        '_', '*', '[', ']', '(', ')', '~', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!',
-    ];
-
-    static ESCAPED_CHARACTERS_REPLACEMENT: [&str; 17] = [
-        "\\_", "\\*", "\\[", "\\]", "\\(", "\\)", "\\~", "\\>", "\\#", "\\+", "\\-", "\\=", "\\|",
-        "\\{", "\\}", "\\.", "\\!",
-    ];
-
-    let mut text: String = text.into();
-    for (character, replacement) in ESCAPED_CHARACTERS
-        .iter()
-        .zip(ESCAPED_CHARACTERS_REPLACEMENT.iter())
-    {
-        text = text.replace(*character, replacement);
+    let text: String = text.into();
+    let mut escaped = String::with_capacity(text.len());
+    let mut in_code = false;
+    for c in text.chars() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == '\\' || (!in_code && ESCAPED_CHARACTERS.contains(&c)) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
     }
-    text
+    escaped
 }
 
 pub mod serde_string {
@@ -221,6 +317,7 @@ pub mod serde_option_string {
         value.as_ref().map(Helper).serialize(serializer)
     }
 
+    /// Also accepts plain integers since some endpoints don't quote them.
     pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
     where
         D: serde::Deserializer<'de>,
@@ -229,9 +326,137 @@ pub mod serde_option_string {
     {
         use serde::de::{Deserialize, Error};
 
-        Option::<String>::deserialize(deserializer).and_then(|s| {
-            s.map(|s| T::from_str(&s).map_err(Error::custom))
-                .transpose()
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            String(String),
+            Unsigned(u64),
+            Signed(i64),
+        }
+
+        Option::<Repr>::deserialize(deserializer).and_then(|repr| {
+            let Some(repr) = repr else {
+                return Ok(None);
+            };
+            let s = match repr {
+                Repr::String(s) => s,
+                Repr::Unsigned(n) => n.to_string(),
+                Repr::Signed(n) => n.to_string(),
+            };
+            T::from_str(&s).map(Some).map_err(Error::custom)
         })
     }
 }
+
+/// A unique path in the temp dir which is removed on drop.
+#[cfg(test)]
+pub struct TempFile {
+    path: std::path::PathBuf,
+}
+
+#[cfg(test)]
+impl TempFile {
+    pub fn new(extension: &str) -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("tychonet-test-{}-{id}.{extension}", std::process::id());
+        Self {
+            path: std::env::temp_dir().join(name),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_middle_keeps_head_and_tail() {
+        assert_eq!(truncate_middle("short", 10), "short");
+
+        let text = (0..100).map(|i| format!("line {i}")).collect::<Vec<_>>();
+        let text = format!("```\n{}\n```", text.join("\n"));
+
+        let truncated = truncate_middle(&text, 100);
+        assert!(truncated.starts_with("```\nline 0\n"));
+        assert!(truncated.ends_with("\nline 99\n```"));
+        assert!(truncated.contains("\n...\n"));
+        assert!(escape_markdown(truncated.as_ref()).chars().count() <= 100);
+    }
+
+    #[test]
+    fn escaped_len_counts_escapes() {
+        assert_eq!(escaped_len("abc"), 3);
+        assert_eq!(escaped_len(r"a.b\c"), 7);
+        assert_eq!(escaped_len("фф!"), 4);
+    }
+
+    #[test]
+    fn monotonic_clock_never_goes_backward() {
+        let clock = MonotonicClock::new();
+        let system_now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut prev = clock.now_sec();
+        assert!(prev.abs_diff(system_now) <= 1);
+        for _ in 0..1000 {
+            let now = clock.now_sec();
+            assert!(now >= prev);
+            prev = now;
+        }
+
+        // A value returned by another thread is never undercut
+        clock.last_sec.store(prev + 100, Ordering::Release);
+        assert_eq!(clock.now_sec(), prev + 100);
+    }
+
+    #[test]
+    fn escape_markdown_all_characters() {
+        assert_eq!(
+            escape_markdown("_*[]()~>#+-=|{}.!"),
+            r"\_\*\[\]\(\)\~\>\#\+\-\=\|\{\}\.\!"
+        );
+        for c in ESCAPED_CHARACTERS {
+            assert_eq!(escape_markdown(c.to_string()), format!("\\{c}"));
+            assert_eq!(escape_markdown(format!("{c}{c}")), format!("\\{c}\\{c}"));
+        }
+
+        assert_eq!(escape_markdown("Hello, world!"), r"Hello, world\!");
+        assert_eq!(escape_markdown("1.5 (approx.)"), r"1\.5 \(approx\.\)");
+        assert_eq!(escape_markdown("a-b_c *d*"), r"a\-b\_c \*d\*");
+        assert_eq!(escape_markdown("`code`"), "`code`");
+        assert_eq!(escape_markdown(""), "");
+    }
+
+    #[test]
+    fn escape_markdown_existing_backslashes() {
+        assert_eq!(escape_markdown(r"C:\path"), r"C:\\path");
+        assert_eq!(escape_markdown(r"\."), r"\\\.");
+        assert_eq!(escape_markdown(r"\\!"), r"\\\\\!");
+        assert_eq!(escape_markdown(r"end\"), r"end\\");
+    }
+
+    #[test]
+    fn escape_markdown_code_spans() {
+        assert_eq!(escape_markdown("`a.b_c`"), "`a.b_c`");
+        assert_eq!(escape_markdown(r"`C:\path` (x)"), r"`C:\\path` \(x\)");
+        assert_eq!(
+            escape_markdown("```\n{ \"a\": 1 }\n```!"),
+            "```\n{ \"a\": 1 }\n```\\!"
+        );
+    }
+}